@@ -0,0 +1,47 @@
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// A record of a secret's contents being exposed, lasting from the
+/// moment it is created until it is dropped.
+///
+/// When the `tracing` feature is enabled, dropping an [`Exposure`]
+/// emits an event with the reason given for the exposure and how long
+/// it lasted. The contents of the secret are never included. Otherwise,
+/// this does nothing.
+pub(crate) struct Exposure {
+    /// the caller-provided reason for exposing the secret
+    #[cfg(feature = "tracing")]
+    reason: &'static str,
+
+    /// the moment at which the exposure began
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl Exposure {
+    /// Begins recording an exposure for `reason`.
+    pub(crate) fn new(reason: &'static str) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = reason;
+
+        Self {
+            #[cfg(feature = "tracing")]
+            reason,
+
+            #[cfg(feature = "tracing")]
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Exposure {
+    fn drop(&mut self) {
+        tracing::info!(
+            target: "secrets::audit",
+            reason  = self.reason,
+            elapsed = ?self.start.elapsed(),
+            "secret exposed",
+        );
+    }
+}
@@ -1,14 +1,27 @@
 #![allow(unsafe_code)]
 
+use crate::error::SecretError;
 use crate::ffi::sodium;
+use crate::protect::{self, Protect};
+use crate::secret;
+use crate::stats;
 use crate::traits::*;
 
 use std::cell::Cell;
 use std::fmt::{self, Debug};
+use std::mem::align_of;
+use std::ops::Deref;
 use std::ptr::NonNull;
 use std::slice;
 use std::thread;
 
+// tests run in parallel, so they need a view of the calls made by their
+// own thread in order to make precise assertions
+#[cfg(test)]
+thread_local! {
+    static READONLY_MPROTECTS: Cell<usize> = Cell::new(0);
+}
+
 /// The page protection applied to the memory underlying a [`Box`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Prot {
@@ -30,11 +43,48 @@ enum Prot {
 /// implausible that there would be many legitimate use-cases where
 /// someone needs more than 255 simultaneous borrows of secret data.
 ///
-/// TODO: Perhaps this could be moved to an associated type on a trait,
-/// such that a user who did need a larger value could provide a
-/// larger replacement.
+/// Those that do can enable the `wide-refcount` feature, which widens
+/// this to a [`u32`].
+#[cfg(not(feature = "wide-refcount"))]
 type RefCount = u8;
 
+/// The type used for storing ref counts, widened by the
+/// `wide-refcount` feature.
+#[cfg(feature = "wide-refcount")]
+type RefCount = u32;
+
+/// The reasons that a [`Box`] may fail to be allocated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AllocError {
+    /// libsodium could not be initialized.
+    Init,
+
+    /// The memory could not be allocated.
+    Allocation,
+
+    /// The memory could not be locked, for the reason given.
+    Lock(SecretError),
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Init       => write!(f, "secrets: failed to initialize libsodium"),
+            Self::Allocation => write!(f, "secrets: failed to allocate memory"),
+            Self::Lock(err)  => write!(f, "secrets: {}", err),
+        }
+    }
+}
+
+impl From<AllocError> for SecretError {
+    fn from(err: AllocError) -> Self {
+        match err {
+            AllocError::Init | AllocError::Allocation => Self::Allocation,
+            AllocError::Lock(err)                     => err,
+        }
+    }
+}
+
 /// NOTE: This implementation is not meant to be exposed directly to
 /// end-users, and user-facing wrappers must be written with care to
 /// ensure they statically enforce the required invariants. These
@@ -44,7 +94,6 @@ type RefCount = u8;
 /// statically.
 ///
 /// TODO: document invariants
-#[derive(Eq)]
 pub(crate) struct Box<T: Bytes> {
     /// the non-null pointer to the underlying protected memory
     ptr: NonNull<T>,
@@ -59,6 +108,32 @@ pub(crate) struct Box<T: Bytes> {
     /// here even though there is a max of one, so that asserts can
     /// ensure invariants are obeyed
     refs: Cell<RefCount>,
+
+    /// whether or not a panic occurred while the contents were mutably
+    /// borrowed, potentially leaving them in an inconsistent state
+    poisoned: Cell<bool>,
+
+    /// whether or not the size of the contents is revealed when the
+    /// [`Box`] or its wrappers are formatted with [`Debug`]
+    debug_size: bool,
+
+    /// whether or not the memory was successfully `mlock`ed when it was
+    /// allocated
+    mlocked: bool,
+
+    /// the backend that allocated the memory, and which must be used
+    /// to protect and free it
+    backend: &'static dyn Protect,
+}
+
+/// An immutable unlock of a [`Box`], taken internally by its own trait
+/// implementations. This is refcounted exactly like the unlocks taken
+/// by the borrows of user-facing wrappers, so it coalesces with any of
+/// them that are outstanding, and it re-locks the [`Box`] when dropped
+/// so that the unlock is balanced even if the caller panics.
+struct Unlocked<'a, T: Bytes> {
+    /// an immutably-unlocked [`Box`]
+    boxed: &'a Box<T>,
 }
 
 impl<T: Bytes> Box<T> {
@@ -69,16 +144,30 @@ impl<T: Bytes> Box<T> {
     pub(crate) fn new<F>(len: usize, init: F) -> Self
     where
         F: FnOnce(&mut Self),
+    {
+        Self::new_with(len, init).0
+    }
+
+    /// Instantiates a new [`Box`] that can hold `len` elements of type
+    /// `T`. Has equivalent semantics to [`new`](Box::new), but returns
+    /// the value produced by the callback `F` alongside the [`Box`].
+    pub(crate) fn new_with<R, F>(len: usize, init: F) -> (Self, R)
+    where
+        F: FnOnce(&mut Self) -> R,
     {
         let mut boxed = Self::new_unlocked(len);
 
         proven!(boxed.ptr != std::ptr::NonNull::dangling());
         proven!(boxed.len == len);
 
-        init(&mut boxed);
+        // if `init` panics, `boxed` is dropped while still unlocked;
+        // this is safe, since `sodium::free` zeroes the memory before
+        // releasing it regardless of its protection level, so nothing
+        // partially written by `init` survives the unwinding
+        let result = init(&mut boxed);
 
         boxed.lock();
-        boxed
+        (boxed, result)
     }
 
     /// Instantiates a new [`Box`] that can hold `len` elements of type
@@ -103,6 +192,122 @@ impl<T: Bytes> Box<T> {
         result.map(|_| boxed)
     }
 
+    /// Moves the contents of the [`Box`] into a new allocation that can
+    /// hold `len` elements of type `T`, which may be larger or smaller
+    /// than the current one. As many existing elements as will fit are
+    /// transferred to the front of the new allocation, and the callback
+    /// `F` is called with the remainder of it in order to initialize
+    /// any elements beyond them.
+    ///
+    /// The old allocation is zeroed before it is freed. Since this
+    /// requires exclusive access, the [`Box`] must have no outstanding
+    /// unlocks; the new allocation is likewise locked, with no
+    /// outstanding unlocks, when this returns. Whether or not the
+    /// [`Box`] is poisoned and how it is formatted by [`Debug`] carry
+    /// over to the new allocation.
+    pub(crate) fn grow<F>(&mut self, len: usize, init: F)
+    where
+        F: FnOnce(&mut [T]),
+    {
+        *self = self.transfer_into(len, init);
+    }
+
+    /// Reinterprets the memory underlying the [`Box`] as holding
+    /// elements of type `U`, without copying or reallocating it. Returns
+    /// [`None`] (dropping, and thereby zeroing, the [`Box`]) if its size
+    /// isn't a whole multiple of the size of `U`, or if the allocation
+    /// isn't suitably aligned for `U`.
+    ///
+    /// This relies on the contract of [`Bytes`], which requires that any
+    /// bit pattern is valid for `U`. The [`Box`] must have no
+    /// outstanding unlocks.
+    pub(crate) fn reinterpret<U: Bytes>(self) -> Option<Box<U>> {
+        proven!(self.refs.get() == 0,
+            "secrets: may not reinterpret a Box with outstanding unlocks");
+
+        let size = self.size();
+
+        #[allow(clippy::manual_is_multiple_of)] // not usable on min supported Rust
+        if U::size() == 0
+            || size % U::size() != 0
+            || self.ptr.as_ptr() as usize % align_of::<U>() != 0
+        {
+            return None;
+        }
+
+        // the allocation is handed over to the new [`Box`] as-is, so
+        // the old one must not free it
+        let old = std::mem::ManuallyDrop::new(self);
+
+        Some(Box {
+            ptr:        old.ptr.cast(),
+            len:        size / U::size(),
+            prot:       Cell::new(old.prot.get()),
+            refs:       Cell::new(0),
+            poisoned:   Cell::new(old.poisoned.get()),
+            debug_size: old.debug_size,
+            mlocked:    old.mlocked,
+            backend:    old.backend,
+        })
+    }
+
+    /// Clones the [`Box`] into a new protected allocation, like
+    /// [`clone`](Clone::clone), but returns an error rather than
+    /// panicking if libsodium can't be initialized, if the memory can't
+    /// be allocated or would exceed the limit on locked memory, or if
+    /// the protection level of either allocation can't be changed.
+    pub(crate) fn try_clone(&self) -> Result<Self, SecretError> {
+        // the source is unlocked first, so that there's no new
+        // allocation to clean up if that fails
+        if !self.try_retain(Prot::ReadOnly) {
+            return Err(SecretError::Allocation);
+        }
+
+        let     source = Unlocked { boxed: self };
+        let mut boxed  = Self::try_new_unlocked(self.len)?;
+
+        boxed.as_mut_slice().copy_from_slice(&source);
+
+        drop(source);
+
+        if !boxed.try_release() {
+            // the allocation is freed immediately, and freeing it
+            // doesn't depend on its protection level
+            boxed.prot.set(Prot::NoAccess);
+            return Err(SecretError::Allocation);
+        }
+
+        boxed.set_debug_size(self.debug_size);
+        Ok(boxed)
+    }
+
+    /// Performs the work of [`grow`](Box::grow), returning the new
+    /// allocation and leaving the old one locked but zeroed.
+    fn transfer_into<F>(&mut self, len: usize, init: F) -> Self
+    where
+        F: FnOnce(&mut [T]),
+    {
+        proven!(self.refs.get() == 0,
+            "secrets: may not reallocate a Box with outstanding unlocks");
+
+        let kept = self.len.min(len);
+        let old  = self.unlock_mut().as_mut_slice();
+
+        let mut boxed = Self::new(len, |b| {
+            let (head, tail) = b.as_mut_slice().split_at_mut(kept);
+
+            head.copy_from_slice(&old[..kept]);
+            init(tail);
+        });
+
+        old.zero();
+        self.lock();
+
+        boxed.poisoned.set(self.poisoned.get());
+        boxed.debug_size = self.debug_size;
+        boxed
+    }
+
     /// Returns the number of elements in the [`Box`].
     #[allow(clippy::missing_const_for_fn)] // not usable on min supported Rust
     pub(crate) fn len(&self) -> usize {
@@ -126,6 +331,12 @@ impl<T: Bytes> Box<T> {
         self.len * T::size()
     }
 
+    /// Returns the backend that allocated the memory underlying the
+    /// [`Box`].
+    fn backend(&self) -> &'static dyn Protect {
+        self.backend
+    }
+
     /// Allows the contents of the [`Box`] to be read from. Any call to
     /// this function *must* be balanced with a call to
     /// [`lock`](Box::lock). Mirroring Rust's borrowing rules, there may
@@ -137,6 +348,27 @@ impl<T: Bytes> Box<T> {
         self
     }
 
+    /// Allows the contents of the [`Box`] to be read from, like
+    /// [`unlock`](Box::unlock), but returns an error rather than
+    /// panicking if the [`Box`] is already unlocked as many times as
+    /// its [`RefCount`] can track.
+    pub(crate) fn try_unlock(&self) -> Result<&Self, SecretError> {
+        // a saturated count on locked memory implies an out-of-order
+        // release rather than too many borrows, which is still left
+        // for `retain` to panic on
+        if self.refs.get() == RefCount::MAX && !self.is_locked() {
+            return Err(SecretError::TooManyBorrows);
+        }
+
+        Ok(self.unlock())
+    }
+
+    /// Unlocks the [`Box`] for reading for as long as the returned
+    /// [`Unlocked`] is alive.
+    fn unlocked(&self) -> Unlocked<'_, T> {
+        Unlocked { boxed: self.unlock() }
+    }
+
     /// Allows the contents of the [`Box`] to be read from and written
     /// to. Any call to this function *must* be balanced with a call to
     /// [`lock`](Box::lock). Mirroring Rust's borrowing rules, there may
@@ -148,6 +380,31 @@ impl<T: Bytes> Box<T> {
         self
     }
 
+    /// Allows the contents of the [`Box`] to be read from and written
+    /// to, returning a shared reference to the [`Box`] alongside a
+    /// mutable slice of its contents. This allows the slice to be split
+    /// apart while the [`Box`] itself is retained for the sole purpose
+    /// of eventually calling [`lock`](Box::lock).
+    ///
+    /// This has the same requirements as [`unlock_mut`](Box::unlock_mut)
+    /// and, in addition, the slice must go out of scope before the
+    /// [`Box`] is locked. The returned [`Box`] reference must not be
+    /// used to access the contents while the slice is alive.
+    pub(crate) fn unlock_mut_split(&mut self) -> (&Self, &mut [T]) {
+        self.retain(Prot::ReadWrite);
+
+        // this doesn't alias the returned `&Self`, since the contents
+        // live in a separate allocation only reachable through `ptr`
+        let slice = unsafe {
+            slice::from_raw_parts_mut(
+                self.ptr.as_ptr(),
+                self.len,
+            )
+        };
+
+        (self, slice)
+    }
+
     /// Disables all access to the underlying memory. Must only be
     /// called to precisely balance prior calls to [`unlock`](Box::unlock)
     /// and [`unlock_mut`](Box::unlock_mut).
@@ -156,10 +413,104 @@ impl<T: Bytes> Box<T> {
     /// unlocks will result in a runtime panic. Omitting a call to this
     /// method and leaving an outstanding unlock will result in a
     /// runtime panic when this object is dropped.
+    ///
+    /// If the memory can't be protected again, this panics, unless the
+    /// thread is already panicking. Wrappers call this from their
+    /// [`Drop`] implementations, which run while unwinding, and a second
+    /// panic there would abort the process. In that case the release is
+    /// still counted and the memory is left accessible until it is next
+    /// locked or freed, so a caller that catches the panic is left with
+    /// a [`Box`] that can still be used and dropped.
     pub(crate) fn lock(&self) {
+        if thread::panicking() {
+            let _ = self.try_release();
+            return;
+        }
+
         self.release();
     }
 
+    /// Asserts, in debug builds only, that no wrapper around the [`Box`]
+    /// has been leaked (e.g., with [`mem::forget`](std::mem::forget))
+    /// rather than dropped, which would leave it unlocked indefinitely.
+    /// User-facing wrappers call this before handing out a new borrow.
+    ///
+    /// When `exclusive` is true, the caller is borrowing through a
+    /// mutable reference, so no other borrows can be alive and there
+    /// must be no outstanding unlocks at all. Otherwise, other immutable
+    /// borrows may be alive, but a mutable one cannot be, so the [`Box`]
+    /// must not be unlocked for writing.
+    ///
+    /// This is purely a development aid. Borrows which are leaked are
+    /// only detected the next time the [`Box`] is borrowed, and the
+    /// check is compiled out of release builds.
+    #[allow(unused_variables)] // unused when `proven!` is compiled out
+    pub(crate) fn check_leaked_borrows(&self, exclusive: bool) {
+        proven!(
+            if exclusive { self.refs.get() == 0 } else { self.prot.get() != Prot::ReadWrite },
+            "secrets: a borrow was leaked (e.g., with mem::forget) and left the secret unlocked"
+        );
+    }
+
+    /// Marks the [`Box`] as poisoned. This should be called by wrappers
+    /// that observe a panic while their contents are mutably borrowed,
+    /// since the interrupted writer may have left them partially
+    /// initialized.
+    pub(crate) fn poison(&self) {
+        self.poisoned.set(true);
+    }
+
+    /// Returns the number of outstanding retains on the [`Box`].
+    #[cfg(test)]
+    pub(crate) fn refs(&self) -> RefCount {
+        self.refs.get()
+    }
+
+    /// Skips the count of outstanding retains forward (or back) over
+    /// every value beyond the range of a [`u8`], as though that many
+    /// immutable unlocks had been taken (or released). This lets tests
+    /// exhaust a [`RefCount`] of any width without unlocking billions
+    /// of times, and does nothing when [`RefCount`] is a [`u8`].
+    #[cfg(test)]
+    pub(crate) fn skip_refs(&self, forward: bool) {
+        let skip = RefCount::MAX - RefCount::from(u8::MAX);
+
+        self.refs.set(if forward { self.refs.get() + skip } else { self.refs.get() - skip });
+    }
+
+    /// Returns true if the memory underlying the [`Box`] was
+    /// successfully `mlock`ed when it was allocated.
+    pub(crate) fn is_mlocked(&self) -> bool {
+        self.mlocked
+    }
+
+    /// Returns true if the [`Box`] has been [`poison`](Box::poison)ed.
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.get()
+    }
+
+    /// Sets whether or not the size of the contents is revealed when the
+    /// [`Box`] or its wrappers are formatted with [`Debug`].
+    pub(crate) fn set_debug_size(&mut self, show: bool) {
+        self.debug_size = show;
+    }
+
+    /// Returns true if the size of the contents is revealed when the
+    /// [`Box`] or its wrappers are formatted with [`Debug`].
+    pub(crate) fn debug_size(&self) -> bool {
+        self.debug_size
+    }
+
+    /// Writes the redacted [`Debug`] representation of `bytes` bytes of
+    /// the [`Box`]'s contents, omitting the byte count if so configured.
+    pub(crate) fn fmt_redacted(&self, fmt: &mut fmt::Formatter<'_>, bytes: usize) -> fmt::Result {
+        if self.debug_size {
+            write!(fmt, "{{ {} bytes redacted }}", bytes)
+        } else {
+            write!(fmt, "{{ redacted }}")
+        }
+    }
+
     /// Converts the [`Box`]'s contents into a reference. This must only
     /// happen while it is unlocked, and the reference must go out of
     /// scope before it is locked.
@@ -248,15 +599,56 @@ impl<T: Bytes> Box<T> {
     /// TODO: make `len` a `NonZero` when it's stabilized and remove the
     /// related panic.
     fn new_unlocked(len: usize) -> Self {
+        match Self::try_new_unlocked(len) {
+            Ok(boxed) => boxed,
+            Err(msg)  => panic!("{}", msg),
+        }
+    }
+
+    /// Performs the work of [`new_unlocked`](Box::new_unlocked),
+    /// returning the reason for the failure rather than panicking if
+    /// libsodium can't be initialized, if the memory can't be
+    /// allocated, or if it can't be locked (including when locking it
+    /// would exceed the limit on locked memory).
+    fn try_new_unlocked(len: usize) -> Result<Self, AllocError> {
         tested!(len == 0);
         tested!(std::mem::size_of::<T>() == 0);
 
-        assert!(sodium::init(), "secrets: failed to initialize libsodium");
+        // the heap backend used by tests aligns its allocations no
+        // further than this
+        #[cfg(test)]
+        proven!(align_of::<T>() <= protect::HEAP_HEADER,
+            "secrets: type is too strictly aligned for the heap backend");
+
+        if !sodium::init() {
+            return Err(AllocError::Init);
+        }
 
-        // `sodium::allocarray` returns a memory location that already
-        // allows r/w access
-        let ptr = NonNull::new(unsafe { sodium::allocarray::<T>(len) })
-            .expect("secrets: failed to allocate memory");
+        // libsodium silently leaves memory unlocked when it would
+        // exceed the limit, so this is checked up front in order to
+        // fail with an actionable error
+        stats::check_memlock_budget(len.saturating_mul(T::size()))
+            .map_err(AllocError::Lock)?;
+
+        // `allocarray` returns a memory location that already allows
+        // r/w access
+        let (backend, ptr) = protect::allocarray(len, T::size());
+        let ptr            = NonNull::new(ptr.cast::<T>())
+            .ok_or(AllocError::Allocation)?;
+
+        let mlocked = secret::with_mlock_retries(|| unsafe {
+            backend.mlock(ptr.as_ptr().cast::<u8>(), len * T::size())
+        });
+
+        if !mlocked {
+            // nothing has been written to the memory yet, so there's
+            // nothing to scrub before freeing it
+            unsafe { backend.free(ptr.as_ptr().cast()) }
+
+            return Err(AllocError::Lock(SecretError::Mlock));
+        }
+
+        stats::record_lock(len * T::size());
 
         // NOTE: We technically could save a little extra work here by
         // initializing the struct with [`Prot::NoAccess`] and a zero
@@ -264,17 +656,31 @@ impl<T: Bytes> Box<T> {
         // initialization. However, the `as_mut()` call performs sanity
         // checks that ensure it's [`Prot::ReadWrite`] so it's easier to
         // just send everything through the "normal" code paths.
-        Self {
+        Ok(Self {
             ptr,
             len,
-            prot: Cell::new(Prot::ReadWrite),
-            refs: Cell::new(1),
-        }
+            prot:       Cell::new(Prot::ReadWrite),
+            refs:       Cell::new(1),
+            poisoned:   Cell::new(false),
+            debug_size: true,
+            mlocked,
+            backend,
+        })
     }
 
     /// Performs the underlying retain half of the retain/release logic
     /// for monitoring outstanding calls to unlock.
     fn retain(&self, prot: Prot) {
+        if !self.try_retain(prot) {
+            panic!("secrets: error setting memory protection to {:?}", prot);
+        }
+    }
+
+    /// Performs the work of [`retain`](Box::retain), returning false
+    /// rather than panicking if the memory's protection level can't be
+    /// changed. In that case, the memory remains locked and the retain
+    /// is not counted.
+    fn try_retain(&self, prot: Prot) -> bool {
         let refs = self.refs.get();
 
         tested!(refs == RefCount::min_value());
@@ -294,7 +700,11 @@ impl<T: Bytes> Box<T> {
             // internal protection flag first so we never run the risk
             // of believing that memory is protected when it isn't
             self.prot.set(prot);
-            mprotect(self.ptr.as_ptr(), prot);
+
+            if !try_mprotect(self.backend(), self.ptr.as_ptr(), prot) {
+                self.prot.set(Prot::NoAccess);
+                return false;
+            }
         } else {
             // if we have a nonzero retain count, there is nothing to
             // change, but we can assert some invariants:
@@ -322,7 +732,8 @@ impl<T: Bytes> Box<T> {
         // counter. This is ensured even in production builds because
         // it's infeasible for consumers of this API to actually enforce
         // this. That said, it's unlikely that anyone would need to
-        // have more than 255 outstanding retains at one time.
+        // have more than 255 outstanding retains at one time (and
+        // those who do can widen [`RefCount`]).
         //
         // This also protects us in the event of balanced, out-of-order
         // retain/release code. If an out-of-order `release` causes the
@@ -333,12 +744,24 @@ impl<T: Bytes> Box<T> {
             None if self.is_locked() => panic!("secrets: out-of-order retain/release detected"),
             None                     => panic!("secrets: retained too many times"),
         };
+
+        true
     }
 
     /// Removes one outsdanding retain, and changes the memory
     /// protection level back to [`Prot::NoAccess`] when the number of
     /// outstanding retains reaches zero.
     fn release(&self) {
+        if !self.try_release() {
+            panic!("secrets: error setting memory protection to {:?}", Prot::NoAccess);
+        }
+    }
+
+    /// Performs the work of [`release`](Box::release), returning false
+    /// rather than panicking if the memory's protection level can't be
+    /// changed. In that case, the release is still counted but the
+    /// memory is left accessible.
+    fn try_release(&self) -> bool {
         // When releasing, we should always have at least one retain
         // outstanding. This is enforced by all users through
         // refcounting on allocation and drop.
@@ -374,14 +797,19 @@ impl<T: Bytes> Box<T> {
         self.refs.set(refs);
 
         if refs == 0 {
-            mprotect(self.ptr.as_ptr(), Prot::NoAccess);
+            if !try_mprotect(self.backend(), self.ptr.as_ptr(), Prot::NoAccess) {
+                return false;
+            }
+
             self.prot.set(Prot::NoAccess);
         }
+
+        true
     }
 
     /// Returns true if the protection level is [`NoAccess`]. Ignores
     /// ref count.
-    fn is_locked(&self) -> bool {
+    pub(crate) fn is_locked(&self) -> bool {
         self.prot.get() == Prot::NoAccess
     }
 }
@@ -395,9 +823,23 @@ impl<T: Bytes + Randomizable> Box<T> {
 }
 
 impl<T: Bytes + Zeroable> Box<T> {
+    /// Instantiates a new [`Box`] that can hold `len` elements of type
+    /// `T`. Has equivalent semantics to [`new`](Box::new), but the
+    /// memory is zeroed before being passed to the callback `F`, so any
+    /// part of it left unwritten is zero rather than garbage.
+    pub(crate) fn new_zeroed<F>(len: usize, init: F) -> Self
+    where
+        F: FnOnce(&mut Self),
+    {
+        Self::new(len, |b| {
+            b.as_mut_slice().zero();
+            init(b);
+        })
+    }
+
     /// Instantiates a new [`Box`] whose backing memory is zeroed.
     pub(crate) fn zero(len: usize) -> Self {
-        Self::new(len, |b| b.as_mut_slice().zero())
+        Self::new_zeroed(len, |_| {})
     }
 }
 
@@ -419,43 +861,59 @@ impl<T: Bytes> Drop for Box<T> {
                 "secrets: dropped secret was still accessible");
         }
 
-        unsafe { sodium::free(self.ptr.as_mut()) }
+        // The backend zeroes the memory as it's freed, but we scrub it
+        // ourselves first in case that ever fails to happen. This is
+        // only best-effort: if the memory can't be made writable, we
+        // still rely on the backend's own zeroing.
+        if try_mprotect(self.backend(), self.ptr.as_ptr(), Prot::ReadWrite) {
+            unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len).zero() }
+        }
+
+        unsafe { self.backend().free(self.ptr.as_ptr().cast()) }
+
+        if self.is_mlocked() {
+            stats::record_unlock(self.size());
+        }
     }
 }
 
 impl<T: Bytes> Debug for Box<T> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "{{ {} bytes redacted }}", self.size())
+        self.fmt_redacted(fmt, self.size())
     }
 }
 
 impl<T: Bytes> Clone for Box<T> {
     fn clone(&self) -> Self {
-        Self::new(self.len, |b| {
-            b.as_mut_slice().copy_from_slice(self.unlock().as_slice());
-            self.lock();
-        })
+        let mut boxed = Self::new(self.len, |b| {
+            b.as_mut_slice().copy_from_slice(&self.unlocked());
+        });
+
+        boxed.set_debug_size(self.debug_size);
+        boxed
     }
 }
 
 impl<T: Bytes + ConstantEq> PartialEq for Box<T> {
     fn eq(&self, other: &Self) -> bool {
+        // a [`Box`] is trivially equal to itself, and we can avoid
+        // unlocking it just to compare its contents; this reveals only
+        // that both sides are the same allocation, not anything about
+        // their contents
+        if self.ptr == other.ptr {
+            return true;
+        }
+
         if self.len != other.len {
             return false;
         }
 
-        let lhs = self.unlock().as_slice();
-        let rhs = other.unlock().as_slice();
-
-        let ret = lhs.constant_eq(rhs);
-
-        self.lock();
-        other.lock();
-
-        ret
+        self.unlocked().constant_eq(&other.unlocked())
     }
 }
 
+impl<T: Bytes + Eq> Eq for Box<T> {}
+
 impl<T: Bytes + Zeroable> From<&mut T> for Box<T> {
     fn from(data: &mut T) -> Self {
         // this is safe since the secret and data can never overlap
@@ -476,24 +934,51 @@ impl<T: Bytes + Zeroable> From<&mut [T]> for Box<T> {
     }
 }
 
+impl<T: Bytes> Deref for Unlocked<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.boxed.as_slice()
+    }
+}
+
+impl<T: Bytes> Drop for Unlocked<'_, T> {
+    fn drop(&mut self) {
+        self.boxed.lock();
+    }
+}
+
 unsafe impl<T: Bytes + Send> Send for Box<T> {}
 
-/// Immediately changes the page protection level on `ptr` to `prot`.
-fn mprotect<T>(ptr: *mut T, prot: Prot) {
-    if !match prot {
-        Prot::NoAccess  => unsafe { sodium::mprotect_noaccess(ptr)  },
-        Prot::ReadOnly  => unsafe { sodium::mprotect_readonly(ptr)  },
-        Prot::ReadWrite => unsafe { sodium::mprotect_readwrite(ptr) },
-    } {
-        panic!("secrets: error setting memory protection to {:?}", prot);
+/// Immediately changes the page protection level on `ptr` to `prot`,
+/// using the `backend` that allocated it, returning whether or not it
+/// succeeded.
+fn try_mprotect<T>(backend: &dyn Protect, ptr: *mut T, prot: Prot) -> bool {
+    #[cfg(test)]
+    if prot == Prot::ReadOnly {
+        READONLY_MPROTECTS.with(|c| c.set(c.get() + 1));
+    }
+
+    match prot {
+        Prot::NoAccess  => unsafe { backend.mprotect_noaccess(ptr.cast())  },
+        Prot::ReadOnly  => unsafe { backend.mprotect_readonly(ptr.cast())  },
+        Prot::ReadWrite => unsafe { backend.mprotect_readwrite(ptr.cast()) },
     }
 }
 
+/// Returns the number of times memory has been made read-only by the
+/// current thread.
+#[cfg(test)]
+pub(crate) fn readonly_mprotects() -> usize {
+    READONLY_MPROTECTS.with(Cell::get)
+}
+
 // LCOV_EXCL_START
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::panic::{self, AssertUnwindSafe};
 
     #[test]
     fn it_allows_custom_initialization() {
@@ -538,6 +1023,16 @@ mod tests {
         boxed.lock();
     }
 
+    #[test]
+    fn it_initializes_with_zero_before_custom_initialization() {
+        let boxed = Box::<u8>::new_zeroed(4, |secret| {
+            secret.as_mut_slice()[1] = 0x04;
+        });
+
+        assert_eq!(boxed.unlock().as_slice(), [0x00, 0x04, 0x00, 0x00]);
+        boxed.lock();
+    }
+
     #[test]
     fn it_initializes_from_values() {
         let mut value = [4_u64];
@@ -576,6 +1071,181 @@ mod tests {
         assert_ne!(boxed_2, boxed_1);
     }
 
+    #[test]
+    #[allow(clippy::eq_op)]
+    fn it_compares_equality_to_itself_without_unlocking() {
+        let mut boxed = Box::<u8>::random(16);
+
+        // an immutable unlock here would conflict with the outstanding
+        // mutable one
+        let _ = boxed.unlock_mut();
+        assert!(boxed == boxed);
+        assert_eq!(1, boxed.refs.get());
+
+        boxed.lock();
+    }
+
+    #[test]
+    fn it_reinterprets_its_contents() {
+        let boxed = Box::<[u8; 4]>::from(&mut [[1, 2, 3, 4], [5, 6, 7, 8]][..]);
+        let bytes = stats::thread_locked_bytes();
+        let words = boxed.reinterpret::<u16>().expect("reinterpretation failed");
+
+        assert_eq!(words.len(),                  4);
+        assert_eq!(words.refs(),                 0);
+        assert_eq!(stats::thread_locked_bytes(), bytes);
+
+        assert_eq!(words.unlock().as_slice()[0].to_ne_bytes(), [1, 2]);
+
+        words.lock();
+    }
+
+    #[test]
+    fn it_doesnt_reinterpret_indivisible_sizes() {
+        assert!(Box::<[u8; 6]>::zero(1).reinterpret::<u32>().is_none());
+        assert!(Box::<u8>::zero(3).reinterpret::<u16>().is_none());
+        assert!(Box::<u8>::zero(3).reinterpret::<()>().is_none());
+    }
+
+    #[test]
+    fn it_clones_while_borrowed() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        // the clone's unlock must coalesce with the one already
+        // outstanding, and must not lock the original out from under it
+        let unlocked = boxed.unlock().as_slice();
+        let clone    = boxed.clone();
+
+        assert_eq!(boxed.refs(), 1);
+        assert_eq!(unlocked,     [1, 2, 3]);
+
+        boxed.lock();
+
+        assert_eq!(boxed.refs(),              0);
+        assert_eq!(clone.refs(),              0);
+        assert_eq!(clone.unlock().as_slice(), [1, 2, 3]);
+
+        clone.lock();
+    }
+
+    #[test]
+    fn it_clones_fallibly() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+        let clone = boxed.try_clone().expect("failed to clone");
+
+        assert_eq!(boxed,        clone);
+        assert_eq!(boxed.refs(), 0);
+        assert_eq!(clone.refs(), 0);
+    }
+
+    #[test]
+    fn it_reports_mprotect_failures_when_cloning_fallibly() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        sodium::fail();
+
+        assert_eq!(boxed.try_clone().err(), Some(SecretError::Allocation));
+        assert_eq!(boxed.refs(),            0);
+        assert!(boxed.is_locked());
+    }
+
+    #[test]
+    fn it_remains_consistent_when_unlocking_panics() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        sodium::fail();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _ = boxed.unlock();
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(boxed.refs(), 0);
+        assert!(boxed.is_locked());
+
+        assert_eq!(*boxed.unlocked(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn it_remains_consistent_when_relocking_fails_while_panicking() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _unlocked = boxed.unlocked();
+
+            sodium::fail();
+            panic!("interrupted while unlocked");
+        }));
+
+        // the panic was caught rather than aborting the process, and
+        // the release was still counted
+        assert!(result.is_err());
+        assert_eq!(boxed.refs(), 0);
+
+        assert_eq!(*boxed.unlocked(), [1, 2, 3]);
+        assert!(boxed.is_locked());
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: secret of 4096 bytes exceeds the 1024 bytes of lockable memory remaining")]
+    fn it_refuses_allocations_exceeding_the_memlock_limit() {
+        let limit = stats::thread_locked_bytes() + 1024;
+
+        let _ = stats::with_memlock_limit(limit, || Box::<u64>::zero(512));
+    }
+
+    #[test]
+    fn it_reports_allocations_exceeding_the_memlock_limit() {
+        let limit = stats::thread_locked_bytes() + 1024;
+
+        assert_eq!(
+            stats::with_memlock_limit(limit, || Box::<u64>::try_new_unlocked(512)).err(),
+            Some(AllocError::Lock(SecretError::MemlockBudgetExceeded { requested: 4096, available: 1024 })),
+        );
+    }
+
+    #[test]
+    fn it_reports_clones_exceeding_the_memlock_limit() {
+        let boxed = Box::<u8>::zero(768);
+
+        assert!(matches!(
+            stats::with_memlock_limit(0, || boxed.try_clone()),
+            Err(SecretError::MemlockBudgetExceeded { available: 0, .. }),
+        ));
+
+        assert_eq!(boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_reports_init_failures_when_cloning_fallibly() {
+        let boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        // with the source already unlocked, the first fallible call is
+        // made while allocating the clone
+        let _ = boxed.unlock();
+
+        sodium::fail();
+
+        assert_eq!(boxed.try_clone().err(), Some(SecretError::Allocation));
+        assert_eq!(boxed.refs(),            1);
+
+        boxed.lock();
+    }
+
+    #[test]
+    fn it_compares_equality_while_borrowed() {
+        let boxed_1 = Box::<u8>::from(&mut [1, 2, 3][..]);
+        let boxed_2 = boxed_1.clone();
+
+        let _ = boxed_1.unlock();
+
+        assert_eq!(boxed_1,        boxed_2);
+        assert_eq!(boxed_1.refs(), 1);
+        assert_eq!(boxed_2.refs(), 0);
+
+        boxed_1.lock();
+    }
+
     #[test]
     fn it_initializes_with_zero_refs() {
         let boxed = Box::<u8>::zero(10);
@@ -602,6 +1272,73 @@ mod tests {
         assert_eq!(0, boxed.refs.get());
     }
 
+    #[test]
+    fn it_splits_mutable_unlocks() {
+        let mut boxed = Box::<u8>::zero(4);
+
+        let (b, slice) = boxed.unlock_mut_split();
+
+        slice.copy_from_slice(&[1, 2, 3, 4]);
+
+        assert_eq!(Prot::ReadWrite, b.prot.get());
+        assert_eq!(1,               b.refs.get());
+
+        b.lock();
+
+        assert_eq!(boxed.unlock().as_slice(), [1, 2, 3, 4]);
+        boxed.lock();
+    }
+
+    #[test]
+    fn it_grows_preserving_contents() {
+        let mut boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        boxed.grow(5, |tail| tail.copy_from_slice(&[4, 5]));
+
+        assert_eq!(5,              boxed.len());
+        assert_eq!(0,              boxed.refs.get());
+        assert_eq!(Prot::NoAccess, boxed.prot.get());
+        assert_eq!(boxed.unlock().as_slice(), [1, 2, 3, 4, 5]);
+        boxed.lock();
+    }
+
+    #[test]
+    fn it_shrinks_preserving_contents() {
+        let mut boxed = Box::<u8>::from(&mut [1, 2, 3][..]);
+
+        boxed.grow(2, |tail| assert!(tail.is_empty()));
+
+        assert_eq!(boxed.unlock().as_slice(), [1, 2]);
+        boxed.lock();
+    }
+
+    #[test]
+    fn it_zeroes_the_old_allocation_when_growing() {
+        let mut old = Box::<u8>::from(&mut [1, 2, 3][..]);
+        let     new = old.transfer_into(4, |tail| tail[0] = 4);
+
+        assert_eq!(0,              old.refs.get());
+        assert_eq!(Prot::NoAccess, old.prot.get());
+
+        assert_eq!(old.unlock().as_slice(), [0, 0, 0]);
+        assert_eq!(new.unlock().as_slice(), [1, 2, 3, 4]);
+
+        old.lock();
+        new.lock();
+    }
+
+    #[test]
+    fn it_carries_flags_over_when_growing() {
+        let mut boxed = Box::<u8>::zero(1);
+
+        boxed.poison();
+        boxed.set_debug_size(false);
+        boxed.grow(2, |tail| tail.zero());
+
+        assert!(boxed.is_poisoned());
+        assert!(!boxed.debug_size());
+    }
+
     #[test]
     fn it_doesnt_overflow_early() {
         let boxed = Box::<u64>::zero(4);
@@ -610,6 +1347,12 @@ mod tests {
             let _ = boxed.unlock();
         }
 
+        boxed.skip_refs(true);
+
+        assert_eq!(boxed.refs(), RefCount::MAX);
+
+        boxed.skip_refs(false);
+
         for _ in 0..u8::max_value() {
             boxed.lock();
         }
@@ -663,10 +1406,16 @@ mod tests {
     fn it_doesnt_allow_overflowing_readers() {
         let boxed = Box::<[u64; 8]>::zero(4);
 
-        for _ in 0..=u8::max_value() {
+        for _ in 0..u8::max_value() {
             let _ = boxed.unlock();
         }
 
+        boxed.skip_refs(true);
+
+        let _ = boxed.unlock();
+
+        boxed.skip_refs(false);
+
         // this ensures that we *don't* inadvertently panic if we
         // somehow made it through the above statement
         for _ in 0..boxed.refs.get() {
@@ -674,6 +1423,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_reports_overflowing_readers_fallibly() {
+        let boxed = Box::<[u64; 8]>::zero(4);
+
+        for _ in 0..u8::MAX {
+            let _ = boxed.unlock();
+        }
+
+        boxed.skip_refs(true);
+
+        assert_eq!(boxed.try_unlock().err(), Some(SecretError::TooManyBorrows));
+        assert_eq!(boxed.refs(),             RefCount::MAX);
+
+        boxed.skip_refs(false);
+
+        for _ in 0..boxed.refs.get() {
+            boxed.lock()
+        }
+
+        assert!(boxed.try_unlock().is_ok());
+
+        boxed.lock();
+    }
+
     #[test]
     #[should_panic(expected = "secrets: out-of-order retain/release detected")]
     fn it_detects_out_of_order_retains_and_releases_that_underflow() {
@@ -694,12 +1467,81 @@ mod tests {
         let _ = Box::<u8>::zero(0);
     }
 
+    #[test]
+    fn it_retries_transient_mlock_failures() {
+        let boxed = protect::with_backend(&protect::Heap, || {
+            protect::fail_heap_mlocks(1);
+
+            Box::<u64>::zero(4)
+        });
+
+        assert!(boxed.is_mlocked());
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: unable to mlock memory for a secret")]
+    fn it_refuses_to_allocate_unlockable_memory() {
+        let _ = protect::with_backend(&protect::Heap, || {
+            protect::fail_heap_mlocks(usize::MAX);
+
+            Box::<u64>::zero(4)
+        });
+    }
+
+    #[test]
+    fn it_reports_persistent_mlock_failures() {
+        let before      = stats::thread_locked_bytes();
+        let allocations = protect::heap_allocations();
+
+        let result = protect::with_backend(&protect::Heap, || {
+            protect::fail_heap_mlocks(usize::MAX);
+
+            Box::<u64>::try_new_unlocked(4)
+        });
+
+        protect::fail_heap_mlocks(0);
+
+        assert_eq!(result.err(),                 Some(AllocError::Lock(SecretError::Mlock)));
+        assert_eq!(stats::thread_locked_bytes(), before);
+        assert_eq!(protect::heap_allocations(),  allocations);
+    }
+
+    #[test]
+    fn it_reports_mlock_failures_when_cloning_fallibly() {
+        let boxed = protect::with_backend(&protect::Heap, || Box::<u64>::zero(4));
+
+        let result = protect::with_backend(&protect::Heap, || {
+            protect::fail_heap_mlocks(usize::MAX);
+
+            boxed.try_clone()
+        });
+
+        protect::fail_heap_mlocks(0);
+
+        assert_eq!(result.err(), Some(SecretError::Mlock));
+        assert_eq!(boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_scrubs_memory_before_freeing_it() {
+        let before = protect::heap_unscrubbed_frees();
+
+        protect::with_backend(&protect::Heap, || {
+            drop(Box::<u64>::from(&mut [u64::MAX; 8][..]));
+        });
+
+        assert_eq!(protect::heap_unscrubbed_frees(), before);
+    }
+
 
     #[test]
     #[should_panic(expected = "secrets: error setting memory protection to NoAccess")]
     fn it_detects_sodium_mprotect_failure() {
+        let boxed = Box::<u8>::zero(1);
+        let _     = boxed.unlock();
+
         sodium::fail();
-        mprotect(std::ptr::null_mut::<u8>(), Prot::NoAccess);
+        boxed.lock();
     }
 }
 
@@ -710,7 +1552,8 @@ mod tests_sigsegv {
     use super::*;
     use std::process;
 
-    fn assert_sigsegv<F>(f: F)
+    /// Runs `f` in a child process, returning the signal that killed it.
+    fn killing_signal<F>(f: F) -> libc::c_int
     where
         F: FnOnce(),
     {
@@ -729,18 +1572,30 @@ mod tests_sigsegv {
                     // assert that the process terminated due to a signal
                     assert!(libc::WIFSIGNALED(stat));
 
-                    // assert that we received a SIGBUS or SIGSEGV,
-                    // either of which can be sent by an attempt to
-                    // access protected memory regions
-                    assert!(
-                        libc::WTERMSIG(stat) == libc::SIGBUS ||
-                        libc::WTERMSIG(stat) == libc::SIGSEGV
-                    );
+                    libc::WTERMSIG(stat)
                 }
             }
         }
     }
 
+    fn assert_sigsegv<F>(f: F)
+    where
+        F: FnOnce(),
+    {
+        let signal = killing_signal(f);
+
+        // assert that we received a SIGBUS or SIGSEGV, either of which
+        // can be sent by an attempt to access protected memory regions
+        assert!(signal == libc::SIGBUS || signal == libc::SIGSEGV);
+    }
+
+    fn assert_sigabrt<F>(f: F)
+    where
+        F: FnOnce(),
+    {
+        assert_eq!(killing_signal(f), libc::SIGABRT);
+    }
+
     #[test]
     fn it_kills_attempts_to_read_while_locked() {
         assert_sigsegv(|| {
@@ -751,6 +1606,60 @@ mod tests_sigsegv {
         });
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_kills_attempts_to_read_past_huge_page_allocations() {
+        if !protect::huge_pages_available() {
+            return;
+        }
+
+        assert_sigsegv(|| {
+            protect::with_backend(&protect::HugePages, || {
+                let boxed = Box::<u8>::zero(1);
+                let ptr   = boxed.unlock().ptr.as_ptr();
+
+                // the contents end flush against the trailing guard page
+                let _ = unsafe { ptr.add(1).read_volatile() };
+            });
+        });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_aborts_on_underflows_of_huge_page_allocations() {
+        if !protect::huge_pages_available() {
+            return;
+        }
+
+        assert_sigabrt(|| {
+            protect::with_backend(&protect::HugePages, || {
+                let mut boxed = Box::<u8>::zero(1);
+                let     ptr   = boxed.unlock_mut().ptr.as_ptr();
+
+                // overwriting the canary is detected when it's freed
+                unsafe { ptr.sub(1).write(!ptr.sub(1).read()) };
+
+                boxed.lock();
+            });
+        });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_kills_attempts_to_read_locked_huge_page_allocations() {
+        if !protect::huge_pages_available() {
+            return;
+        }
+
+        assert_sigsegv(|| {
+            protect::with_backend(&protect::HugePages, || {
+                let val = unsafe { Box::<u32>::zero(1).ptr.as_ptr().read() };
+
+                let _ = sodium::memcmp(val.as_bytes(), val.as_bytes());
+            });
+        });
+    }
+
     #[test]
     fn it_kills_attempts_to_write_while_locked() {
         assert_sigsegv(|| {
@@ -774,6 +1683,23 @@ mod tests_sigsegv {
             );
         });
     }
+
+    #[test]
+    fn it_kills_attempts_to_read_after_being_freed() {
+        // libsodium unmaps freed allocations entirely (after zeroing
+        // them), so there are no scrubbed bytes left behind to inspect
+        assert_sigsegv(|| {
+            let boxed = Box::<u64>::random(4);
+            let ptr   = boxed.ptr.as_ptr();
+
+            drop(boxed);
+
+            let val = unsafe { ptr.read() };
+
+            // TODO: replace with [`test::black_box`] when stable
+            let _ = sodium::memcmp(val.as_bytes(), val.as_bytes());
+        });
+    }
 }
 
 #[cfg(all(test, profile = "debug"))]
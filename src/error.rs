@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// Errors that may be returned by the fallible operations on secrets.
+///
+/// Most operations in this crate panic when they are unable to enforce
+/// their advertised guarantees. Variants of this type are only returned
+/// from methods that explicitly opt in to reporting their failures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(variant_size_differences)] // errors are rare, and small anyway
+pub enum SecretError {
+    /// A panic occurred while the secret was mutably borrowed, so its
+    /// contents may have been left partially written.
+    Poisoned,
+
+    /// The secret did not end with validly-formed padding.
+    InvalidPadding,
+
+    /// The secret would have exceeded the maximum allowed length.
+    LengthExceeded,
+
+    /// The encoded form of the secret was malformed.
+    InvalidEncoding,
+
+    /// Two secrets were required to be the same length, but were not.
+    LengthMismatch,
+
+    /// A password could not be hashed, either because the requested
+    /// limits were out of range or because too little memory was
+    /// available.
+    HashFailed,
+
+    /// The memory underlying a secret could not be locked with
+    /// `mlock(2)`, even after retrying.
+    Mlock,
+
+    /// The memory for a secret could not be allocated, or its
+    /// protection level could not be changed.
+    Allocation,
+
+    /// Locking the memory for a secret would have exceeded the limit
+    /// on locked memory imposed on the process (`RLIMIT_MEMLOCK`).
+    MemlockBudgetExceeded {
+        /// the number of bytes that needed to be locked
+        requested: usize,
+
+        /// the number of bytes that could still be locked
+        available: usize,
+    },
+
+    /// A sealed secret could not be decrypted, either because it was
+    /// sealed under a different key or because it was tampered with.
+    Decryption,
+
+    /// The secret was already immutably borrowed as many times as can
+    /// be tracked at once.
+    TooManyBorrows,
+
+    /// An I/O error occurred while reading or writing the secret.
+    Io(io::ErrorKind),
+}
+
+impl Display for SecretError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Poisoned        => write!(f, "secret was poisoned by a panic during a mutable borrow"),
+            Self::InvalidPadding  => write!(f, "secret has invalid padding"),
+            Self::LengthExceeded  => write!(f, "secret exceeded its maximum length"),
+            Self::InvalidEncoding => write!(f, "secret has an invalid encoding"),
+            Self::LengthMismatch  => write!(f, "secrets differ in length"),
+            Self::HashFailed      => write!(f, "password hashing failed"),
+            Self::Mlock           => write!(f, "unable to mlock memory for a secret"),
+            Self::Allocation      => write!(f, "unable to allocate protected memory for a secret"),
+            Self::Decryption      => write!(f, "secret could not be decrypted"),
+            Self::MemlockBudgetExceeded { requested, available } => write!(f,
+                "secret of {} bytes exceeds the {} bytes of lockable memory remaining (see RLIMIT_MEMLOCK)",
+                requested, available,
+            ),
+            Self::TooManyBorrows  => write!(f, "secret was borrowed too many times"),
+            Self::Io(kind)        => write!(f, "i/o error: {}", io::Error::from(*kind)),
+        }
+    }
+}
+
+impl Error for SecretError {}
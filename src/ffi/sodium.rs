@@ -5,18 +5,63 @@
 use std::mem;
 use std::sync::Once;
 
+#[cfg(feature = "test-deterministic")]
+use std::cell::UnsafeCell;
+
+#[cfg(feature = "test-deterministic")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use libc::{self, size_t};
 
 #[cfg(not(feature = "use-libsodium-sys"))]
-use libc::{c_void, c_int};
+use libc::{c_void, c_char, c_int, c_uchar, c_ulonglong};
 
 #[cfg(feature = "use-libsodium-sys")]
 use libsodium_sys::{
-    randombytes_buf, sodium_allocarray, sodium_free, sodium_init,
-    sodium_memcmp, sodium_memzero, sodium_mlock, sodium_mprotect_noaccess,
-    sodium_mprotect_readonly, sodium_mprotect_readwrite, sodium_munlock,
+    crypto_auth, crypto_auth_verify, crypto_hash_sha256, crypto_pwhash_str,
+    crypto_pwhash_str_verify, randombytes_buf, sodium_allocarray,
+    sodium_free, sodium_init, sodium_memcmp, sodium_memzero, sodium_mlock,
+    sodium_mprotect_noaccess, sodium_mprotect_readonly,
+    sodium_mprotect_readwrite, sodium_munlock,
 };
 
+#[cfg(all(feature = "use-libsodium-sys", feature = "test-deterministic"))]
+use libsodium_sys::randombytes_buf_deterministic;
+
+#[cfg(all(feature = "use-libsodium-sys", feature = "serde", feature = "crypto"))]
+use libsodium_sys::{crypto_secretbox_easy, crypto_secretbox_open_easy};
+
+/// The size in bytes of a key used by [`sodium::auth`].
+pub(crate) const AUTH_KEYBYTES: usize = 32;
+
+/// The size in bytes of a tag produced by [`sodium::auth`].
+pub(crate) const AUTH_BYTES: usize = 32;
+
+/// The size in bytes of a digest produced by [`sodium::sha256_verify`].
+pub(crate) const SHA256_BYTES: usize = 32;
+
+/// The size in bytes of a key used by [`sodium::secretbox_seal`].
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub(crate) const SECRETBOX_KEYBYTES: usize = 32;
+
+/// The size in bytes of a nonce used by [`sodium::secretbox_seal`].
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub(crate) const SECRETBOX_NONCEBYTES: usize = 24;
+
+/// The number of bytes by which [`sodium::secretbox_seal`] expands its
+/// input, for the authentication tag.
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub(crate) const SECRETBOX_MACBYTES: usize = 16;
+
+/// The maximum size in bytes of an encoded password hash, including its
+/// trailing NUL, as used by [`sodium::pwhash_str`] and
+/// [`sodium::pwhash_str_verify`].
+pub(crate) const PWHASH_STRBYTES: usize = 128;
+
+/// The size in bytes of a seed accepted by [`set_random_seed`].
+#[cfg(feature = "test-deterministic")]
+pub(crate) const RANDOM_SEEDBYTES: usize = 32;
+
 /// The global [`sync::Once`] that ensures we only perform
 /// library initialization one time.
 static INIT: Once = Once::new();
@@ -27,9 +72,41 @@ static mut INITIALIZED: bool = false;
 
 #[cfg(test)]
 thread_local! {
-    static FAIL: std::cell::Cell<bool> = std::cell::Cell::new(false);
+    static FAIL: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    static MEMCMPS: std::cell::Cell<usize> = std::cell::Cell::new(0);
 }
 
+/// The seed for deterministic randomness, if one has been set, along
+/// with the number of times it has been drawn from.
+#[cfg(feature = "test-deterministic")]
+type Seed = Option<([u8; RANDOM_SEEDBYTES], u64)>;
+
+/// A [`Seed`] guarded by a spinlock, since `Mutex::new` can't be used
+/// in a `static` on every supported version of Rust.
+#[cfg(feature = "test-deterministic")]
+struct SeedLock {
+    /// whether or not the seed is currently being accessed
+    locked: AtomicBool,
+
+    /// whether or not a seed has been set, so that drawing random bytes
+    /// doesn't need to take the lock when none has
+    seeded: AtomicBool,
+
+    /// the seed itself, which may only be accessed while locked
+    seed: UnsafeCell<Seed>,
+}
+
+// the seed is only ever accessed while holding the lock
+#[cfg(feature = "test-deterministic")]
+unsafe impl Sync for SeedLock {}
+
+#[cfg(feature = "test-deterministic")]
+static SEED: SeedLock = SeedLock {
+    locked: AtomicBool::new(false),
+    seeded: AtomicBool::new(false),
+    seed:   UnsafeCell::new(None),
+};
+
 #[cfg(not(feature = "use-libsodium-sys"))]
 extern "C" {
     fn sodium_init() -> c_int;
@@ -48,11 +125,84 @@ extern "C" {
     fn sodium_memzero(ptr: *mut c_void, len: size_t);
 
     fn randombytes_buf(ptr: *mut c_void, len: size_t);
+
+    #[cfg(feature = "test-deterministic")]
+    fn randombytes_buf_deterministic(
+        ptr:  *mut c_void,
+        len:  size_t,
+        seed: *const c_uchar,
+    );
+
+    fn crypto_auth(
+        out:   *mut c_uchar,
+        input: *const c_uchar,
+        inlen: c_ulonglong,
+        k:     *const c_uchar,
+    ) -> c_int;
+
+    fn crypto_auth_verify(
+        h:     *const c_uchar,
+        input: *const c_uchar,
+        inlen: c_ulonglong,
+        k:     *const c_uchar,
+    ) -> c_int;
+
+    fn crypto_hash_sha256(
+        out:   *mut c_uchar,
+        input: *const c_uchar,
+        inlen: c_ulonglong,
+    ) -> c_int;
+
+    #[cfg(all(feature = "serde", feature = "crypto"))]
+    fn crypto_secretbox_easy(
+        c:    *mut c_uchar,
+        m:    *const c_uchar,
+        mlen: c_ulonglong,
+        n:    *const c_uchar,
+        k:    *const c_uchar,
+    ) -> c_int;
+
+    #[cfg(all(feature = "serde", feature = "crypto"))]
+    fn crypto_secretbox_open_easy(
+        m:    *mut c_uchar,
+        c:    *const c_uchar,
+        clen: c_ulonglong,
+        n:    *const c_uchar,
+        k:    *const c_uchar,
+    ) -> c_int;
+
+    fn crypto_pwhash_str(
+        out:       *mut c_char,
+        passwd:    *const c_char,
+        passwdlen: c_ulonglong,
+        opslimit:  c_ulonglong,
+        memlimit:  size_t,
+    ) -> c_int;
+
+    fn crypto_pwhash_str_verify(
+        hash:      *const c_char,
+        passwd:    *const c_char,
+        passwdlen: c_ulonglong,
+    ) -> c_int;
 }
 
 #[cfg(test)]
 pub(crate) fn fail() {
-    FAIL.with(|f| f.set(true))
+    fail_times(1)
+}
+
+/// Causes the next `n` fallible calls on this thread to fail.
+#[cfg(test)]
+pub(crate) fn fail_times(n: usize) {
+    FAIL.with(|f| f.set(n))
+}
+
+/// Returns the number of calls made through to `sodium_memcmp` on this
+/// thread, so tests can verify that comparisons of equal-length secrets
+/// never bypass it.
+#[cfg(test)]
+pub(crate) fn memcmps() -> usize {
+    MEMCMPS.with(std::cell::Cell::get)
 }
 
 /// Initialized libsodium. This function *must* be called at least once
@@ -65,7 +215,7 @@ pub(crate) fn fail() {
 pub(crate) fn init() -> bool {
     unsafe {
         #[cfg(test)]
-        { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+        { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
         INIT.call_once(|| {
             // NOTE: Calls to transmute fail to compile if the source
@@ -114,9 +264,11 @@ pub(crate) unsafe fn allocarray<T>(count: usize) -> *mut T {
     sodium_allocarray(count, mem::size_of::<T>()).cast()
 }
 
-/// Releases memory acquired with [`sodium::allocarray`]. This function
-/// may panic if it detects that certain soundness and safety guarantees
-/// have been violated (e.g., an underflowing write).
+/// Releases memory acquired with [`sodium::allocarray`], zeroing it
+/// first. The pages are then unmapped, along with their guard pages,
+/// so any later access to them faults. This function may panic if it
+/// detects that certain soundness and safety guarantees have been
+/// violated (e.g., an underflowing write).
 pub(crate) unsafe fn free<T>(ptr: *mut T) {
     sodium_free(ptr.cast());
 }
@@ -124,15 +276,24 @@ pub(crate) unsafe fn free<T>(ptr: *mut T) {
 /// Calls the platform's underlying `mlock(2)` implementation.
 pub(crate) unsafe fn mlock<T>(ptr: *mut T) -> bool {
     #[cfg(test)]
-    { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
     sodium_mlock(ptr.cast(), mem::size_of::<T>()) == 0
 }
 
+/// Calls the platform's underlying `mlock(2)` implementation on `len`
+/// bytes at `ptr`.
+pub(crate) unsafe fn mlock_len(ptr: *mut u8, len: usize) -> bool {
+    #[cfg(test)]
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
+
+    sodium_mlock(ptr.cast(), len) == 0
+}
+
 /// Calls the platform's underlying `munlock(2)` implementation.
 pub(crate) unsafe fn munlock<T>(ptr: *mut T) -> bool {
     #[cfg(test)]
-    { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
     sodium_munlock(ptr.cast(), mem::size_of::<T>()) == 0
 }
@@ -143,7 +304,7 @@ pub(crate) unsafe fn munlock<T>(ptr: *mut T) -> bool {
 /// by libsodium.
 pub(crate) unsafe fn mprotect_noaccess<T>(ptr: *mut T) -> bool {
     #[cfg(test)]
-    { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
     sodium_mprotect_noaccess(ptr.cast()) == 0
 }
@@ -154,7 +315,7 @@ pub(crate) unsafe fn mprotect_noaccess<T>(ptr: *mut T) -> bool {
 /// by libsodium.
 pub(crate) unsafe fn mprotect_readonly<T>(ptr: *mut T) -> bool {
     #[cfg(test)]
-    { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
     sodium_mprotect_readonly(ptr.cast()) == 0
 }
@@ -165,18 +326,27 @@ pub(crate) unsafe fn mprotect_readonly<T>(ptr: *mut T) -> bool {
 /// by libsodium.
 pub(crate) unsafe fn mprotect_readwrite<T>(ptr: *mut T) -> bool {
     #[cfg(test)]
-    { if FAIL.with(|f| f.replace(false)) { return false }; let _x = 0; };
+    { if FAIL.with(|f| f.replace(f.get().saturating_sub(1)) > 0) { return false }; let _x = 0; };
 
     sodium_mprotect_readwrite(ptr.cast()) == 0
 }
 
 /// Compares `l` and `r` for equality in constant time, preventing
 /// side-channel attacks when comparing equality of secret data.
+///
+/// The only early return is on the lengths of the inputs, which aren't
+/// considered secret. Inputs of equal length are always passed to
+/// `sodium_memcmp`, and since that is an opaque call into C, the
+/// optimizer can neither inline it nor replace it with a comparison
+/// that short-circuits on the first differing byte.
 pub(crate) fn memcmp(l: &[u8], r: &[u8]) -> bool {
     if l.len() != r.len() {
         return false;
     }
 
+    #[cfg(test)]
+    MEMCMPS.with(|m| m.set(m.get() + 1));
+
     unsafe {
         sodium_memcmp(
             l.as_ptr().cast(),
@@ -217,10 +387,320 @@ pub(crate) fn memzero(bytes: &mut [u8]) {
 }
 
 /// Fills `bytes` with random bytes.
+///
+/// When the `test-deterministic` feature is enabled and a seed has been
+/// set with [`set_random_seed`], the bytes are instead drawn from a
+/// ChaCha20-based generator keyed by that seed.
 pub(crate) fn memrandom(bytes: &mut [u8]) {
+    #[cfg(feature = "test-deterministic")]
+    {
+        if memrandom_deterministic(bytes) {
+            return;
+        }
+    }
+
     unsafe { randombytes_buf(bytes.as_mut_ptr().cast(), bytes.len()) }
 }
 
+/// Fills `bytes` deterministically from the seed set with
+/// [`set_random_seed`], returning false without modifying them if no
+/// seed has been set.
+#[cfg(feature = "test-deterministic")]
+fn memrandom_deterministic(bytes: &mut [u8]) -> bool {
+    if !SEED.seeded.load(Ordering::Acquire) {
+        return false;
+    }
+
+    with_seed(|state| {
+        let (seed, draws) = match state.as_mut() {
+            Some(state) => state,
+            None        => return false,
+        };
+
+        // each draw is keyed by the seed mixed with the number of
+        // earlier draws, so that successive draws produce independent
+        // streams rather than repeating the same one
+        let mut key = *seed;
+
+        for (k, d) in key[RANDOM_SEEDBYTES - 8..].iter_mut().zip(draws.to_le_bytes().iter()) {
+            *k ^= d;
+        }
+
+        *draws = draws.wrapping_add(1);
+
+        unsafe {
+            randombytes_buf_deterministic(bytes.as_mut_ptr().cast(), bytes.len(), key.as_ptr());
+        }
+
+        memzero(&mut key);
+
+        true
+    })
+}
+
+/// Seeds a deterministic generator that replaces libsodium's CSPRNG
+/// for every secret randomized afterward (e.g., by
+/// [`SecretBox::random`](crate::SecretBox::random)), or restores the
+/// CSPRNG if `seed` is `None`. This makes test vectors built from
+/// randomized secrets reproducible.
+///
+/// The generator is ChaCha20-based, and a given seed always produces
+/// the same sequence of values. The seed is shared by every thread in
+/// the process, so tests that run in parallel and draw random values
+/// will disturb each other's sequences. This is available only with the
+/// `test-deterministic` feature, which must never be enabled outside of
+/// tests.
+///
+/// ```
+/// # use secrets::SecretBox;
+/// secrets::set_random_seed(Some([7; 32]));
+/// let first = SecretBox::<[u8; 16]>::random();
+///
+/// secrets::set_random_seed(Some([7; 32]));
+/// let second = SecretBox::<[u8; 16]>::random();
+///
+/// secrets::set_random_seed(None);
+///
+/// assert_eq!(first, second);
+/// ```
+#[cfg(feature = "test-deterministic")]
+pub fn set_random_seed(seed: Option<[u8; RANDOM_SEEDBYTES]>) {
+    with_seed(|state| {
+        *state = seed.map(|seed| (seed, 0));
+
+        SEED.seeded.store(state.is_some(), Ordering::Release);
+    });
+}
+
+/// Calls `f` with exclusive access to the deterministic seed.
+#[cfg(feature = "test-deterministic")]
+fn with_seed<R, F: FnOnce(&mut Seed) -> R>(f: F) -> R {
+    /// Releases the lock, even if `f` panics. The seed is always left
+    /// consistent, so there is no need to poison it.
+    struct Unlock;
+
+    impl Drop for Unlock {
+        fn drop(&mut self) {
+            SEED.locked.store(false, Ordering::Release);
+        }
+    }
+
+    while SEED.locked
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::hint::spin_loop();
+    }
+
+    let _unlock = Unlock;
+
+    f(unsafe { &mut *SEED.seed.get() })
+}
+
+/// Calls `f` in a child process with the deterministic generator seeded
+/// by `seed`, and asserts that it completes without panicking. Tests run
+/// in parallel within a single process, so this keeps draws made by
+/// other tests from disturbing the seeded sequence.
+#[cfg(all(test, feature = "test-deterministic", target_family = "unix"))]
+pub(crate) fn with_random_seed<F: FnOnce()>(seed: [u8; RANDOM_SEEDBYTES], f: F) {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::process;
+
+    unsafe {
+        let     pid  = libc::fork();
+        let mut stat = 0;
+
+        match pid {
+            -1 => panic!("`fork(2)` failed"),
+            0  => {
+                set_random_seed(Some(seed));
+
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+
+                process::exit(if result.is_ok() { 0 } else { 1 })
+            },
+            _  => {
+                if libc::waitpid(pid, &mut stat, 0) == -1 {
+                    panic!("`waitpid(2)` failed");
+                };
+
+                assert!(libc::WIFEXITED(stat) && libc::WEXITSTATUS(stat) == 0,
+                    "secrets: seeded test failed in its child process");
+            }
+        }
+    }
+}
+
+/// Computes an authentication tag for `data` under `key` using
+/// libsodium's `crypto_auth` (HMAC-SHA-512-256).
+pub(crate) fn auth(data: &[u8], key: &[u8; AUTH_KEYBYTES]) -> [u8; AUTH_BYTES] {
+    let mut tag = [0; AUTH_BYTES];
+
+    // `crypto_auth` can only fail if `data` is too long to have its
+    // length represented, which is impossible on supported platforms
+    let _ = unsafe {
+        crypto_auth(
+            tag.as_mut_ptr(),
+            data.as_ptr(),
+            data.len() as _,
+            key.as_ptr(),
+        )
+    };
+
+    tag
+}
+
+/// Verifies in constant time that `tag` is a valid authentication tag
+/// for `data` under `key`.
+pub(crate) fn auth_verify(
+    tag:  &[u8; AUTH_BYTES],
+    data: &[u8],
+    key:  &[u8; AUTH_KEYBYTES],
+) -> bool {
+    unsafe {
+        crypto_auth_verify(
+            tag.as_ptr(),
+            data.as_ptr(),
+            data.len() as _,
+            key.as_ptr(),
+        ) == 0
+    }
+}
+
+/// Verifies in constant time that the SHA-256 digest of `data` is
+/// `expected`. The digest is computed into a scratch buffer, which is
+/// zeroed before returning.
+pub(crate) fn sha256_verify(data: &[u8], expected: &[u8; SHA256_BYTES]) -> bool {
+    let mut digest = [0; SHA256_BYTES];
+
+    // `crypto_hash_sha256` can only fail if `data` is too long to have
+    // its length represented, which is impossible on supported platforms
+    let _ = unsafe {
+        crypto_hash_sha256(
+            digest.as_mut_ptr(),
+            data.as_ptr(),
+            data.len() as _,
+        )
+    };
+
+    let matches = memcmp(&digest, expected);
+
+    memzero(&mut digest);
+
+    matches
+}
+
+/// Encrypts and authenticates `src` into `dst` under `nonce` and `key`
+/// with libsodium's `crypto_secretbox_easy` (XSalsa20-Poly1305). `dst`
+/// must be exactly [`SECRETBOX_MACBYTES`] longer than `src`.
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub(crate) fn secretbox_seal(
+    dst:   &mut [u8],
+    src:   &[u8],
+    nonce: &[u8; SECRETBOX_NONCEBYTES],
+    key:   &[u8; SECRETBOX_KEYBYTES],
+) {
+    assert!(dst.len() == src.len() + SECRETBOX_MACBYTES,
+        "secrets: ciphertext buffer is the wrong length");
+
+    // `crypto_secretbox_easy` can only fail if `src` is too long to have
+    // its length represented, which is impossible on supported platforms
+    let _ = unsafe {
+        crypto_secretbox_easy(
+            dst.as_mut_ptr(),
+            src.as_ptr(),
+            src.len() as _,
+            nonce.as_ptr(),
+            key.as_ptr(),
+        )
+    };
+}
+
+/// Verifies and decrypts `src`, as produced by [`secretbox_seal`], into
+/// `dst`. Returns false if `src` was sealed under a different `nonce` or
+/// `key` or has been tampered with, or if `dst` is not exactly
+/// [`SECRETBOX_MACBYTES`] shorter than `src`.
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub(crate) fn secretbox_open(
+    dst:   &mut [u8],
+    src:   &[u8],
+    nonce: &[u8; SECRETBOX_NONCEBYTES],
+    key:   &[u8; SECRETBOX_KEYBYTES],
+) -> bool {
+    if src.len() != dst.len() + SECRETBOX_MACBYTES {
+        return false;
+    }
+
+    unsafe {
+        crypto_secretbox_open_easy(
+            dst.as_mut_ptr(),
+            src.as_ptr(),
+            src.len() as _,
+            nonce.as_ptr(),
+            key.as_ptr(),
+        ) == 0
+    }
+}
+
+/// Hashes `password` with libsodium's `crypto_pwhash_str`, returning
+/// the encoded hash (which includes the algorithm, its parameters, and a
+/// random salt). Returns `None` if the limits are out of range or the
+/// required memory could not be allocated.
+pub(crate) fn pwhash_str(password: &[u8], ops_limit: u64, mem_limit: usize) -> Option<String> {
+    let mut buf = [0_u8; PWHASH_STRBYTES];
+
+    let ret = unsafe {
+        crypto_pwhash_str(
+            buf.as_mut_ptr().cast(),
+            password.as_ptr().cast(),
+            password.len() as _,
+            ops_limit,
+            mem_limit,
+        )
+    };
+
+    if ret != 0 {
+        return None;
+    }
+
+    // libsodium always writes a NUL-terminated ASCII string
+    let len = buf.iter().position(|&b| b == 0)?;
+
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Verifies that `password` matches the encoded password hash `hash`,
+/// as produced by libsodium's `crypto_pwhash_str`. Hashes which are
+/// malformed, or which are too long to have been produced by libsodium,
+/// never match.
+pub(crate) fn pwhash_str_verify(hash: &str, password: &[u8]) -> bool {
+    // libsodium expects a NUL-terminated string no longer than
+    // `PWHASH_STRBYTES`, so copy it into a buffer of that size; the
+    // hash itself isn't secret, so it's fine for this to be on the stack
+    let mut buf = [0_u8; PWHASH_STRBYTES];
+
+    if hash.len() >= buf.len() || hash.as_bytes().contains(&0) {
+        return false;
+    }
+
+    buf[..hash.len()].copy_from_slice(hash.as_bytes());
+
+    unsafe {
+        crypto_pwhash_str_verify(
+            buf.as_ptr().cast(),
+            password.as_ptr().cast(),
+            password.len() as _,
+        ) == 0
+    }
+}
+
+/// The SHA-256 digest of `b"abc"`, from FIPS 180-2.
+#[cfg(test)]
+pub(crate) const SHA256_ABC: [u8; SHA256_BYTES] = [
+    0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+];
+
 // LCOV_EXCL_START
 
 #[cfg(test)]
@@ -246,6 +726,31 @@ mod test {
         assert!(!memcmp(&a, &c));
         assert!(!memcmp(&c, &a));
     }
+
+    #[test]
+    fn memcmp_always_compares_equal_lengths_in_constant_time() {
+        let a = [0x4f, 0x11, 0x9c, 0x2e];
+
+        for b in &[[0x4f, 0x11, 0x9c, 0x2e], [0x00, 0x11, 0x9c, 0x2e], [0x4f, 0x11, 0x9c, 0x00]] {
+            let before = memcmps();
+
+            let _ = memcmp(&a, b);
+
+            assert_eq!(memcmps(), before + 1);
+        }
+    }
+
+    #[test]
+    fn sha256_verify_compares_digests() {
+        let mut tampered = SHA256_ABC;
+
+        tampered[31] ^= 1;
+
+        assert!( sha256_verify(b"abc",  &SHA256_ABC));
+        assert!(!sha256_verify(b"abd",  &SHA256_ABC));
+        assert!(!sha256_verify(b"abc",  &tampered));
+        assert!(!sha256_verify(b"abcd", &SHA256_ABC));
+    }
 }
 
 // LCOV_EXCL_STOP
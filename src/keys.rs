@@ -0,0 +1,47 @@
+//! Type aliases for secret keys of common sizes.
+//!
+//! Each alias is a [`SecretBox`] holding a byte array of the key's
+//! length, so keys of these sizes can be named without writing out the
+//! array type, while retaining every method of [`SecretBox`] (such as
+//! [`random`](SecretBox::random) and [`zero`](SecretBox::zero)).
+//!
+//! Example: generate a random 256-bit key
+//!
+//! ```rust
+//! # use secrets::keys::SecretKey256;
+//! let key = SecretKey256::random();
+//!
+//! assert_eq!(key.size(), 32);
+//! ```
+
+use crate::SecretBox;
+
+/// A 128-bit (16-byte) secret key.
+pub type SecretKey128 = SecretBox<[u8; 16]>;
+
+/// A 256-bit (32-byte) secret key.
+pub type SecretKey256 = SecretBox<[u8; 32]>;
+
+/// A 512-bit (64-byte) secret key.
+pub type SecretKey512 = SecretBox<[u8; 64]>;
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_generates_keys_of_each_size() {
+        assert_eq!(SecretKey128::random().size(), 16);
+        assert_eq!(SecretKey256::random().size(), 32);
+        assert_eq!(SecretKey512::random().size(), 64);
+    }
+
+    #[test]
+    fn it_zeroes_keys() {
+        assert_eq!(*SecretKey256::zero().borrow(), [0; 32]);
+    }
+}
+
+// LCOV_EXCL_STOP
@@ -18,6 +18,76 @@
 //! from it. You can still opt-in on allowing code dumps with
 //! `allow-coredumps` feature flag.
 //!
+//! # Hashing
+//!
+//! Secrets do not implement [`Hash`](std::hash::Hash) by default, since
+//! a hash of a secret's contents would leak information about it. Some
+//! derive macros nevertheless require every field of a struct to
+//! implement [`Hash`](std::hash::Hash). The `constant-hash` feature
+//! flag implements it for [`SecretBox`] and [`SecretVec`] by hashing
+//! only a fixed constant, so every secret has the same hash and any
+//! comparison is left entirely to [`PartialEq`]. These hashes are
+//! useless for distinguishing secrets, so secrets should never be used
+//! as keys in a `HashMap` or `HashSet`.
+//!
+//! # Constant-time selection
+//!
+//! The `subtle` feature flag adds `SecretVec::select_ct`, which uses a
+//! [`subtle::Choice`](https://docs.rs/subtle) to select between two
+//! secrets without branching on the choice.
+//!
+//! # Fuzzing
+//!
+//! The `arbitrary` feature flag implements
+//! [`Arbitrary`](https://docs.rs/arbitrary) for [`SecretBox`] and
+//! [`SecretVec`], so that fuzzing harnesses can generate secrets as
+//! inputs. The fuzzer's bytes are copied directly into protected memory.
+//! This is intended only for fuzzing, and should never be enabled in
+//! production builds.
+//!
+//! # Deterministic randomness
+//!
+//! The `test-deterministic` feature flag adds `set_random_seed`,
+//! which replaces libsodium's CSPRNG with a seeded, ChaCha20-based
+//! generator so that test vectors built from randomized secrets are
+//! reproducible. Without a seed, the CSPRNG is used as normal. This is
+//! intended only for testing, and should never be enabled in production
+//! builds.
+//!
+//! # Auditing
+//!
+//! [`SecretBox::expose_for`] and [`SecretVec::expose_for`] expose a
+//! secret's contents to a closure along with a reason for doing so.
+//! With the `tracing` feature flag, every such exposure emits a
+//! [`tracing`](https://docs.rs/tracing) event under the
+//! `secrets::audit` target, recording the reason and how long the
+//! contents were exposed (but never the contents themselves).
+//!
+//! # Sealed serialization
+//!
+//! With the `serde` and `crypto` feature flags, the `sealed_serde`
+//! module serializes a [`SecretBox`] or [`SecretVec`] in encrypted form,
+//! so that it can be persisted without its contents being stored in
+//! plaintext. It may be used directly, or on individual fields with
+//! `#[serde(with = "secrets::sealed_serde")]`.
+//!
+//! # Asynchronous readers
+//!
+//! The `tokio` feature flag adds `SecretVec::from_async_reader` and
+//! `SecretVec::from_async_reader_exact`, which read secrets from a
+//! [`tokio`](https://docs.rs/tokio) `AsyncRead` directly into protected
+//! memory. That memory is locked again whenever the read has to wait.
+//! Note that `tokio` itself requires a newer compiler than the rest of
+//! this crate, so this feature raises the minimum supported Rust version
+//! to whatever `tokio` requires.
+//!
+//! # Huge pages
+//!
+//! On Linux, [`set_huge_page_threshold`] opts in to backing large
+//! heap-allocated secrets with huge pages, reducing the cost of locking
+//! and protecting them. Secrets fall back to normal pages whenever huge
+//! pages are unavailable.
+//!
 //! # Example: generating crytographic keys
 //!
 //! ```
@@ -186,6 +256,10 @@
 // disabled due to https://github.com/rust-lang/rust/issues/69952
 #![cfg_attr(feature = "cargo-clippy", allow(clippy::wildcard_imports))]
 
+// `const` initializers for thread-locals are not usable on min supported
+// Rust
+#![allow(clippy::missing_const_for_thread_local)]
+
 /// Macros for ensuring code correctness inspired by [sqlite].
 ///
 /// [sqlite]: https://www.sqlite.org/assert.html
@@ -332,20 +406,55 @@ mod ffi {
     pub(crate) mod sodium;
 }
 
+/// Container for exposure auditing.
+mod audit;
+
 /// Container for `Box`.
 mod boxed;
 
+/// Container for `SecretError`.
+mod error;
+
+pub mod keys;
+
+/// Container for the allocation backends underlying `Box`.
+mod protect;
+
+/// Container for `SealedBox`.
+mod sealed_box;
+
+#[cfg(all(feature = "serde", feature = "crypto"))]
+pub mod sealed_serde;
+
 /// Container for `Secret`.
 mod secret;
 
 /// Container for `SecretBox`.
 mod secret_box;
 
+/// Container for `SecretString`.
+mod secret_string;
+
 /// Container for `SecretVec`.
 mod secret_vec;
 
+/// Container for `SharedSecretVec`.
+mod shared_secret_vec;
+
+/// Container for statistics about locked memory.
+mod stats;
+
 pub mod traits;
 
-pub use secret::Secret;
+pub use error::SecretError;
+pub use sealed_box::SealedBox;
+pub use secret::{set_mlock_retries, Secret};
 pub use secret_box::SecretBox;
+pub use secret_string::SecretString;
 pub use secret_vec::SecretVec;
+pub use shared_secret_vec::SharedSecretVec;
+pub use protect::set_huge_page_threshold;
+pub use stats::locked_bytes;
+
+#[cfg(feature = "test-deterministic")]
+pub use ffi::sodium::set_random_seed;
@@ -0,0 +1,606 @@
+#![allow(unsafe_code)]
+
+use crate::ffi::sodium;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(test)]
+use std::cell::Cell;
+
+/// The minimum size in bytes of an allocation that is attempted with
+/// [`HugePages`]. See [`set_huge_page_threshold`].
+static HUGE_PAGE_THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// The size of the system's huge pages, cached once it has been read.
+/// Zero if it hasn't been read yet.
+#[cfg(target_os = "linux")]
+static HUGE_PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+/// The length in bytes of the canary that [`HugePages`] places
+/// immediately before each allocation.
+#[cfg(target_os = "linux")]
+const HUGE_PAGE_CANARY: usize = 16;
+
+/// A backend responsible for allocating the memory underlying a
+/// [`Box`](crate::boxed::Box) and for changing its page protection
+/// level.
+///
+/// Outside of tests, every [`Box`](crate::boxed::Box) is backed by
+/// [`Sodium`], unless it is large enough to be backed by [`HugePages`]
+/// (see [`set_huge_page_threshold`]). Tests may swap in [`Heap`] with
+/// [`with_backend`] in order to exercise higher-level logic in
+/// environments where locking and protecting memory is unavailable.
+pub(crate) trait Protect: Sync {
+    /// Allocates memory that can store `count` objects of `size` bytes
+    /// each, returning a null pointer on failure. The memory must allow
+    /// r/w access. Callers must ensure that they call
+    /// [`free`](Protect::free) when this memory is no longer used.
+    unsafe fn allocarray(&self, count: usize, size: usize) -> *mut u8;
+
+    /// Releases memory acquired with [`allocarray`](Protect::allocarray),
+    /// zeroing it first.
+    unsafe fn free(&self, ptr: *mut u8);
+
+    /// Locks the first `len` bytes of the memory at `ptr` into RAM,
+    /// returning whether it succeeded. Backends attempt to lock their
+    /// allocations as they make them, but may not report failures.
+    unsafe fn mlock(&self, ptr: *mut u8, len: usize) -> bool;
+
+    /// Sets the protection level of the memory at `ptr` to `PROT_NONE`.
+    unsafe fn mprotect_noaccess(&self, ptr: *mut u8) -> bool;
+
+    /// Sets the protection level of the memory at `ptr` to `PROT_READ`.
+    unsafe fn mprotect_readonly(&self, ptr: *mut u8) -> bool;
+
+    /// Sets the protection level of the memory at `ptr` to `PROT_WRITE`.
+    unsafe fn mprotect_readwrite(&self, ptr: *mut u8) -> bool;
+}
+
+/// The default backend, which allocates guarded, `mlock(2)`ed memory
+/// through libsodium and protects it with `mprotect(2)`.
+pub(crate) struct Sodium;
+
+impl Protect for Sodium {
+    unsafe fn allocarray(&self, count: usize, size: usize) -> *mut u8 {
+        // libsodium would also fail an overflowing allocation
+        match count.checked_mul(size) {
+            Some(len) => sodium::allocarray::<u8>(len),
+            None      => std::ptr::null_mut(),
+        }
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        sodium::free(ptr);
+    }
+
+    unsafe fn mlock(&self, ptr: *mut u8, len: usize) -> bool {
+        // libsodium already attempted this during allocation, but
+        // doesn't report whether it succeeded
+        sodium::mlock_len(ptr, len)
+    }
+
+    unsafe fn mprotect_noaccess(&self, ptr: *mut u8) -> bool {
+        sodium::mprotect_noaccess(ptr)
+    }
+
+    unsafe fn mprotect_readonly(&self, ptr: *mut u8) -> bool {
+        sodium::mprotect_readonly(ptr)
+    }
+
+    unsafe fn mprotect_readwrite(&self, ptr: *mut u8) -> bool {
+        sodium::mprotect_readwrite(ptr)
+    }
+}
+
+/// A backend which allocates memory backed by huge pages, by mapping it
+/// with `mmap(2)`'s `MAP_HUGETLB` flag. For large allocations, this
+/// drastically reduces the number of pages that must be locked and
+/// protected. libsodium's allocator has no way to request huge pages,
+/// so this backend manages its own mappings.
+///
+/// Each allocation is laid out like libsodium's, with its contents
+/// preceded and followed by inaccessible guard pages. Since huge pages
+/// can only be protected as a whole, the allocation is rounded up to a
+/// multiple of the huge page size and the contents are placed at its
+/// end, flush against the trailing guard page. A random canary is
+/// placed immediately before the contents and checked when they are
+/// freed, so that underflows are detected as well. The bookkeeping for
+/// each allocation, including the expected canary, is kept in a
+/// read-only page before the leading guard page.
+///
+/// Allocation simply fails if the system has no huge pages available,
+/// so callers are expected to fall back to [`Sodium`].
+#[cfg(target_os = "linux")]
+pub(crate) struct HugePages;
+
+/// The bookkeeping that [`HugePages`] records for each allocation.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+struct Mapping {
+    /// the start of the entire reserved region, including guard pages
+    base: *mut u8,
+
+    /// the length of the entire reserved region
+    total: usize,
+
+    /// the start of the huge-page-backed region holding the contents
+    data: *mut u8,
+
+    /// the length of the huge-page-backed region holding the contents
+    len: usize,
+
+    /// the canary expected immediately before the contents
+    canary: [u8; HUGE_PAGE_CANARY],
+}
+
+#[cfg(target_os = "linux")]
+impl HugePages {
+    /// Returns the size of a normal page.
+    fn page_size() -> usize {
+        std::convert::TryFrom::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap_or(4096)
+    }
+
+    /// Returns the size of the huge pages that `MAP_HUGETLB` allocates
+    /// by default, if the system supports them and they are large
+    /// enough to be surrounded by normal guard pages.
+    fn huge_page_size() -> Option<usize> {
+        match HUGE_PAGE_SIZE.load(Ordering::Relaxed) {
+            0    => {}
+            huge => return Some(huge),
+        }
+
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let line    = meminfo.lines().find(|l| l.starts_with("Hugepagesize:"))?;
+        let kib     = line.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+        let huge    = kib.checked_mul(1024)?;
+
+        if huge <= 2 * Self::page_size() || !huge.is_power_of_two() {
+            return None;
+        }
+
+        HUGE_PAGE_SIZE.store(huge, Ordering::Relaxed);
+        Some(huge)
+    }
+
+    /// Returns the bookkeeping for the allocation at `ptr`.
+    unsafe fn mapping(ptr: *mut u8) -> Mapping {
+        // the canary is always within the first huge page of the
+        // contents, and an allocation can only exist once the huge page
+        // size has been cached
+        let huge = HUGE_PAGE_SIZE.load(Ordering::Relaxed);
+        let data = (ptr as usize - HUGE_PAGE_CANARY) & !(huge - 1);
+
+        (data as *mut u8).sub(2 * Self::page_size()).cast::<Mapping>().read()
+    }
+
+    /// Sets the protection level of the allocation at `ptr`.
+    unsafe fn mprotect(ptr: *mut u8, prot: libc::c_int) -> bool {
+        let mapping = Self::mapping(ptr);
+
+        libc::mprotect(mapping.data.cast(), mapping.len, prot) == 0
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Protect for HugePages {
+    unsafe fn allocarray(&self, count: usize, size: usize) -> *mut u8 {
+        let page = Self::page_size();
+        let huge = match Self::huge_page_size() {
+            Some(huge) => huge,
+            None       => return std::ptr::null_mut(),
+        };
+
+        let bytes = match count.checked_mul(size) {
+            Some(bytes) => bytes,
+            None        => return std::ptr::null_mut(),
+        };
+
+        // the contents and their canary are rounded up to whole huge
+        // pages, and enough room is reserved around them to align them
+        // and to surround them with guard pages
+        let (len, total) = match bytes.checked_add(HUGE_PAGE_CANARY + huge - 1).map(|l| l & !(huge - 1))
+            .and_then(|len| Some((len, len.checked_add(2 * huge)?)))
+        {
+            Some(lens) => lens,
+            None       => return std::ptr::null_mut(),
+        };
+
+        // reserve the entire region as inaccessible, so that whatever
+        // isn't used for the contents or bookkeeping serves as guards
+        let base = libc::mmap(
+            std::ptr::null_mut(),
+            total,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+            -1,
+            0,
+        );
+
+        if base == libc::MAP_FAILED {
+            return std::ptr::null_mut();
+        }
+
+        let base = base.cast::<u8>();
+        let data = base.add(((base as usize + 2 * page + huge - 1) & !(huge - 1)) - base as usize);
+        let meta = data.sub(2 * page);
+
+        // the contents end flush against the trailing guard page; since
+        // their length is a multiple of the size of their elements, this
+        // leaves them suitably aligned
+        let ptr = data.add(len - bytes);
+
+        let mut canary = [0; HUGE_PAGE_CANARY];
+
+        sodium::memrandom(&mut canary);
+
+        let mapped = libc::mmap(
+            data.cast(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED | libc::MAP_HUGETLB,
+            -1,
+            0,
+        );
+
+        let ok = mapped != libc::MAP_FAILED
+            && libc::mprotect(meta.cast(), page, libc::PROT_READ | libc::PROT_WRITE) == 0
+            && libc::mlock(data.cast(), len) == 0;
+
+        if ok {
+            meta.cast::<Mapping>().write(Mapping { base, total, data, len, canary });
+        }
+
+        if !ok || libc::mprotect(meta.cast(), page, libc::PROT_READ) != 0 {
+            let _ = libc::munmap(base.cast(), total);
+            return std::ptr::null_mut();
+        }
+
+        ptr.sub(HUGE_PAGE_CANARY).copy_from_nonoverlapping(canary.as_ptr(), HUGE_PAGE_CANARY);
+
+        // fill the memory with garbage, as libsodium does
+        ptr.write_bytes(0xdb, bytes);
+        ptr
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        let mapping = Self::mapping(ptr);
+
+        // the memory may have been left inaccessible
+        let _ = Self::mprotect(ptr, libc::PROT_READ | libc::PROT_WRITE);
+
+        // like libsodium, abort if the canary has been overwritten,
+        // since the contents can no longer be trusted
+        if !sodium::memcmp(
+            std::slice::from_raw_parts(ptr.sub(HUGE_PAGE_CANARY), HUGE_PAGE_CANARY),
+            &mapping.canary,
+        ) {
+            std::process::abort();
+        }
+
+        sodium::memzero(std::slice::from_raw_parts_mut(mapping.data, mapping.len));
+
+        let _ = libc::munlock(mapping.data.cast(), mapping.len);
+        let _ = libc::munmap(mapping.base.cast(), mapping.total);
+    }
+
+    unsafe fn mlock(&self, _: *mut u8, _: usize) -> bool {
+        // allocation fails unless the contents could be locked
+        true
+    }
+
+    unsafe fn mprotect_noaccess(&self, ptr: *mut u8) -> bool {
+        Self::mprotect(ptr, libc::PROT_NONE)
+    }
+
+    unsafe fn mprotect_readonly(&self, ptr: *mut u8) -> bool {
+        Self::mprotect(ptr, libc::PROT_READ)
+    }
+
+    unsafe fn mprotect_readwrite(&self, ptr: *mut u8) -> bool {
+        Self::mprotect(ptr, libc::PROT_READ | libc::PROT_WRITE)
+    }
+}
+
+/// A backend for tests, which allocates plain heap memory and neither
+/// locks nor protects it. Any access to its memory will succeed, so it
+/// provides none of the guarantees of [`Sodium`].
+#[cfg(test)]
+pub(crate) struct Heap;
+
+/// The size and alignment of the header that [`Heap`] places in front
+/// of each allocation to record its size. This is also the alignment of
+/// the allocation itself, which is enough for every
+/// [`Bytes`](crate::traits::Bytes) type the tests use; [`Box`] asserts
+/// as much whenever it allocates memory.
+///
+/// [`Box`]: crate::boxed::Box
+#[cfg(test)]
+pub(crate) const HEAP_HEADER: usize = 64;
+
+// tests run in parallel, so they need a view of the allocations and
+// backend of their own thread in order to make precise assertions
+#[cfg(test)]
+thread_local! {
+    static BACKEND: Cell<Option<&'static dyn Protect>> = Cell::new(None);
+    static HEAP_ALLOCATIONS: Cell<usize> = Cell::new(0);
+    static HEAP_UNSCRUBBED_FREES: Cell<usize> = Cell::new(0);
+    static HEAP_MLOCK_FAILURES: Cell<usize> = Cell::new(0);
+}
+
+#[cfg(test)]
+impl Heap {
+    /// Returns the layout of an allocation holding `len` bytes after
+    /// its header.
+    fn layout(len: usize) -> Option<std::alloc::Layout> {
+        std::alloc::Layout::from_size_align(len.checked_add(HEAP_HEADER)?, HEAP_HEADER).ok()
+    }
+}
+
+#[cfg(test)]
+impl Protect for Heap {
+    unsafe fn allocarray(&self, count: usize, size: usize) -> *mut u8 {
+        let (len, layout) = match count.checked_mul(size).and_then(|len| Some((len, Self::layout(len)?))) {
+            Some(allocation) => allocation,
+            None             => return std::ptr::null_mut(),
+        };
+
+        let base = std::alloc::alloc(layout);
+
+        if base.is_null() {
+            return base;
+        }
+
+        HEAP_ALLOCATIONS.with(|a| a.set(a.get() + 1));
+
+        // fill the memory with garbage, as libsodium does
+        base.cast::<usize>().write(len);
+        base.add(HEAP_HEADER).write_bytes(0xdb, len);
+        base.add(HEAP_HEADER)
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        let base = ptr.sub(HEAP_HEADER);
+        let len  = base.cast::<usize>().read();
+        let data = std::slice::from_raw_parts_mut(ptr, len);
+
+        if data.iter().any(|b| *b != 0) {
+            HEAP_UNSCRUBBED_FREES.with(|f| f.set(f.get() + 1));
+        }
+
+        sodium::memzero(data);
+        std::alloc::dealloc(base, Self::layout(len).expect("secrets: invalid heap allocation"));
+
+        HEAP_ALLOCATIONS.with(|a| a.set(a.get() - 1));
+    }
+
+    unsafe fn mlock(&self, _: *mut u8, _: usize) -> bool {
+        HEAP_MLOCK_FAILURES.with(|f| f.replace(f.get().saturating_sub(1)) == 0)
+    }
+
+    unsafe fn mprotect_noaccess(&self, _: *mut u8) -> bool {
+        true
+    }
+
+    unsafe fn mprotect_readonly(&self, _: *mut u8) -> bool {
+        true
+    }
+
+    unsafe fn mprotect_readwrite(&self, _: *mut u8) -> bool {
+        true
+    }
+}
+
+/// Allocates memory for a newly-created [`Box`](crate::boxed::Box)
+/// that can store `count` objects of `size` bytes each, returning the
+/// backend that allocated it alongside a pointer to it (which is null
+/// on failure).
+///
+/// Allocations of at least the size set by [`set_huge_page_threshold`]
+/// are first attempted with [`HugePages`], falling back to [`Sodium`]
+/// if they can't be allocated that way.
+pub(crate) fn allocarray(count: usize, size: usize) -> (&'static dyn Protect, *mut u8) {
+    #[cfg(test)]
+    if let Some(backend) = BACKEND.with(Cell::get) {
+        return (backend, unsafe { backend.allocarray(count, size) });
+    }
+
+    #[cfg(target_os = "linux")]
+    if count.saturating_mul(size) >= HUGE_PAGE_THRESHOLD.load(Ordering::Relaxed) {
+        let ptr = unsafe { HugePages.allocarray(count, size) };
+
+        if !ptr.is_null() {
+            return (&HugePages, ptr);
+        }
+    }
+
+    (&Sodium, unsafe { Sodium.allocarray(count, size) })
+}
+
+/// Opts in to backing heap-allocated secrets of at least `threshold`
+/// bytes with huge pages, or opts back out if `threshold` is [`None`].
+/// Huge pages are not used by default.
+///
+/// Locking and protecting a multi-megabyte secret, such as a large
+/// lookup table, with normal pages requires the kernel to operate on
+/// thousands of them. Backing it with huge pages (typically 2 MiB each)
+/// instead can make this far cheaper. Those allocations are still
+/// locked, are still surrounded by guard pages, are still preceded by
+/// a canary, and are still zeroed when freed. However, since huge pages
+/// can only be protected as a whole, the guard page before a secret
+/// precedes the start of its first huge page rather than its first
+/// byte, so underflows past the canary may go undetected.
+///
+/// Huge pages are only supported on Linux, and only when the system has
+/// reserved some (e.g., through `/proc/sys/vm/nr_hugepages`). When they
+/// are unavailable or exhausted, secrets are silently allocated with
+/// normal pages instead. This setting has no effect on any other
+/// platform, or on [`Secret`](crate::Secret)s, which are allocated on
+/// the stack.
+///
+/// ```
+/// // use huge pages for secrets of 4 MiB or larger
+/// secrets::set_huge_page_threshold(Some(4 * 1024 * 1024));
+/// ```
+pub fn set_huge_page_threshold(threshold: Option<usize>) {
+    HUGE_PAGE_THRESHOLD.store(threshold.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// Calls `f` with every [`Box`](crate::boxed::Box) created on the
+/// current thread allocated from `backend`. Each [`Box`] is freed with
+/// the backend that allocated it, regardless of when it is dropped.
+#[cfg(test)]
+pub(crate) fn with_backend<R, F: FnOnce() -> R>(backend: &'static dyn Protect, f: F) -> R {
+    /// Restores the previous backend, even if `f` panics.
+    struct Restore(Option<&'static dyn Protect>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            BACKEND.with(|b| b.set(self.0));
+        }
+    }
+
+    let _restore = Restore(BACKEND.with(|b| b.replace(Some(backend))));
+
+    f()
+}
+
+/// Returns the number of live allocations made from [`Heap`] by the
+/// current thread.
+#[cfg(test)]
+pub(crate) fn heap_allocations() -> usize {
+    HEAP_ALLOCATIONS.with(Cell::get)
+}
+
+/// Returns the number of allocations made from [`Heap`] by the current
+/// thread that still held nonzero bytes when they were freed.
+#[cfg(test)]
+pub(crate) fn heap_unscrubbed_frees() -> usize {
+    HEAP_UNSCRUBBED_FREES.with(Cell::get)
+}
+
+/// Causes the next `n` calls to [`Heap`]'s `mlock` on the current
+/// thread to fail.
+#[cfg(test)]
+pub(crate) fn fail_heap_mlocks(n: usize) {
+    HEAP_MLOCK_FAILURES.with(|f| f.set(n));
+}
+
+/// Returns true if [`HugePages`] is able to allocate memory, which
+/// requires the system to have reserved some huge pages.
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) fn huge_pages_available() -> bool {
+    let ptr = unsafe { HugePages.allocarray(1, 1) };
+
+    if ptr.is_null() {
+        return false;
+    }
+
+    unsafe { HugePages.free(ptr) };
+    true
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{SecretBox, SecretVec};
+
+    #[test]
+    fn it_allocates_from_the_heap_backend() {
+        let before = heap_allocations();
+
+        let secret = with_backend(&Heap, || SecretVec::<u64>::random(8));
+
+        assert_eq!(heap_allocations(), before + 1);
+
+        drop(secret);
+
+        assert_eq!(heap_allocations(), before);
+    }
+
+    #[test]
+    fn it_counts_allocations_freed_without_being_scrubbed() {
+        let before = heap_unscrubbed_frees();
+
+        // freshly-allocated memory is filled with garbage
+        unsafe { Heap.free(Heap.allocarray(4, 8)) };
+
+        assert_eq!(heap_unscrubbed_frees(), before + 1);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_allocates_from_huge_pages() {
+        if !huge_pages_available() {
+            return;
+        }
+
+        let huge = HugePages::huge_page_size().unwrap();
+
+        with_backend(&HugePages, || {
+            let mut secret = SecretVec::<u64>::zero(huge / 8 + 1);
+
+            secret.borrow_mut()[huge / 8] = 0x0bad_cafe;
+
+            let copy = secret.clone();
+            let end  = secret.borrow().as_ptr() as usize + secret.size();
+
+            assert_eq!(end % huge,              0);
+            assert_eq!(copy.borrow()[huge / 8], 0x0bad_cafe);
+            assert_eq!(copy,                    secret);
+        });
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn it_refuses_to_allocate_unrepresentable_huge_page_sizes() {
+        assert!(unsafe { HugePages.allocarray(usize::MAX, 2) }.is_null());
+        assert!(unsafe { HugePages.allocarray(1, usize::MAX) }.is_null());
+    }
+
+    #[test]
+    fn it_restores_the_default_backend() {
+        let before = heap_allocations();
+
+        with_backend(&Heap, || {});
+
+        let _secret = SecretVec::<u8>::zero(4);
+
+        assert_eq!(heap_allocations(), before);
+    }
+
+    #[test]
+    fn it_runs_secret_vec_operations_against_the_heap_backend() {
+        with_backend(&Heap, || {
+            let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+            let     suffix = SecretVec::<u8>::from(&mut [5, 6][..]);
+
+            secret.borrow_mut()[0] = 9;
+            secret.extend_from_secret(&suffix);
+            secret.retain_mut(|x| *x % 2 == 0);
+
+            let copy = secret.clone();
+
+            assert_eq!(*secret.borrow(), [2, 4, 6]);
+            assert_eq!(secret,           copy);
+            assert_ne!(secret,           suffix);
+        });
+    }
+
+    #[test]
+    fn it_runs_secret_box_operations_against_the_heap_backend() {
+        with_backend(&Heap, || {
+            let mut secret = SecretBox::<[u8; 4]>::zero();
+
+            secret.borrow_mut()[2] = 0xff;
+
+            let words = secret.clone().reinterpret_vec::<u16>().unwrap();
+
+            assert_eq!(*secret.borrow(), [0, 0, 0xff, 0]);
+            assert_eq!(words.len(),      2);
+        });
+    }
+}
+
+// LCOV_EXCL_STOP
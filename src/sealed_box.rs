@@ -0,0 +1,119 @@
+use crate::secret_box::{Ref, RefMut, SecretBox};
+use crate::traits::*;
+
+use std::fmt::{self, Debug, Formatter};
+
+/// A [`SecretBox`] that refuses to be [`Clone`]d.
+///
+/// A [`SecretBox`] can be cloned into a second protected allocation,
+/// which is convenient but means that there may be more copies of a
+/// secret in memory than intended. A [`SealedBox`], created with
+/// [`SecretBox::sealed`], still implements [`Clone`] so that it may be
+/// stored in types which derive it, but will panic if that
+/// implementation is ever actually called. This mirrors the behavior of
+/// the stack-allocated [`Secret`](crate::Secret).
+///
+/// Other than this, a [`SealedBox`] provides the same protections and
+/// the same borrowing API as a [`SecretBox`].
+///
+/// # Example: attempting to clone a [`SealedBox`]
+///
+/// ```should_panic
+/// # use secrets::SecretBox;
+/// let sealed = SecretBox::<[u8; 32]>::random().sealed();
+///
+/// // panics with "secrets: a SealedBox may not be cloned"
+/// let _ = sealed.clone();
+/// ```
+#[derive(Eq)]
+pub struct SealedBox<T: Bytes> {
+    /// The [`SecretBox`] being protected from cloning.
+    secret: SecretBox<T>,
+}
+
+impl<T: Bytes> SealedBox<T> {
+    /// Instantiates a new [`SealedBox`].
+    pub(crate) fn new(secret: SecretBox<T>) -> Self {
+        Self { secret }
+    }
+
+    /// Returns the size in bytes of the [`SealedBox`].
+    pub fn size(&self) -> usize {
+        self.secret.size()
+    }
+
+    /// Immutably borrows the contents of the [`SealedBox`]. Has
+    /// equivalent semantics to [`SecretBox::borrow`].
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.secret.borrow()
+    }
+
+    /// Mutably borrows the contents of the [`SealedBox`]. Has
+    /// equivalent semantics to [`SecretBox::borrow_mut`].
+    pub fn borrow_mut(&mut self) -> RefMut<'_, T> {
+        self.secret.borrow_mut()
+    }
+}
+
+impl<T: Bytes> Clone for SealedBox<T> {
+    fn clone(&self) -> Self {
+        panic!("secrets: a SealedBox may not be cloned")
+    }
+}
+
+impl<T: Bytes> Debug for SealedBox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.secret.fmt(f)
+    }
+}
+
+impl<T: Bytes + ConstantEq> PartialEq for SealedBox<T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.secret.eq(&rhs.secret)
+    }
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_allows_borrowing() {
+        let mut sealed = SecretBox::<u32>::zero().sealed();
+
+        *sealed.borrow_mut() = 0x0bad_cafe;
+
+        assert_eq!(*sealed.borrow(), 0x0bad_cafe);
+        assert_eq!(sealed.size(),    4);
+    }
+
+    #[test]
+    fn it_compares_equality() {
+        let secret   = SecretBox::<u32>::random();
+        let sealed_1 = secret.clone().sealed();
+        let sealed_2 = secret.sealed();
+
+        assert_eq!(sealed_1, sealed_2);
+    }
+
+    #[test]
+    fn it_preserves_secrecy() {
+        let sealed = SecretBox::<u64>::random().sealed();
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 8),
+            format!("{:?}", sealed),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: a SealedBox may not be cloned")]
+    fn it_panics_when_cloned() {
+        #[allow(clippy::redundant_clone)]
+        let _ = SecretBox::<u16>::zero().sealed().clone();
+    }
+}
+
+// LCOV_EXCL_STOP
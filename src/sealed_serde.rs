@@ -0,0 +1,469 @@
+//! Serialization of secrets in encrypted form.
+//!
+//! Serializing a [`SecretBox`] or [`SecretVec`] through
+//! [`SealedSerialize`] encrypts and authenticates its contents under a
+//! key with libsodium's [`crypto_secretbox`][secretbox]
+//! (XSalsa20-Poly1305), and emits the bytes of a freshly-generated random
+//! nonce followed by the ciphertext. [`SealedDeserialize`] reverses
+//! this, decrypting directly into a new protected allocation so that the
+//! plaintext never passes through unprotected memory.
+//!
+//! The plaintext length of a sealed secret is not hidden, since the
+//! ciphertext is exactly as long as the plaintext plus a fixed-size
+//! authentication tag. Conversely, the number of elements is not
+//! recorded, so a [`SecretVec`] of a zero-sized type can be sealed but
+//! not unsealed.
+//!
+//! These wrappers take their key explicitly. Since `#[serde(with)]`
+//! offers no way to pass one, the [`serialize`] and [`deserialize`]
+//! functions in this module instead use the key provided to
+//! [`with_key`] on the current thread:
+//!
+//! ```
+//! use secrets::{SecretBox, SecretVec, sealed_serde};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Record {
+//!     name: String,
+//!
+//!     #[serde(with = "sealed_serde")]
+//!     token: SecretVec<u8>,
+//! }
+//!
+//! let key    = SecretBox::<[u8; 32]>::random();
+//! let record = Record {
+//!     name:  "example".into(),
+//!     token: SecretVec::from(&mut b"hunter2".to_owned()[..]),
+//! };
+//!
+//! let json = sealed_serde::with_key(&key, || serde_json::to_string(&record))?;
+//! let copy = sealed_serde::with_key(&key, || serde_json::from_str::<Record>(&json))?;
+//!
+//! assert_eq!(copy.token, record.token);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! This module requires both the `serde` and `crypto` feature flags.
+//!
+//! [secretbox]: https://doc.libsodium.org/secret-key_cryptography/secretbox
+
+use crate::boxed::Box;
+use crate::error::SecretError;
+use crate::ffi::sodium::{self, SECRETBOX_KEYBYTES, SECRETBOX_MACBYTES, SECRETBOX_NONCEBYTES};
+use crate::secret_box::SecretBox;
+use crate::secret_vec::SecretVec;
+use crate::traits::*;
+
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+/// A key used to seal and unseal secrets.
+pub type SealingKey = SecretBox<[u8; SECRETBOX_KEYBYTES]>;
+
+thread_local! {
+    /// The key set by [`with_key`] for the current thread, if any.
+    static KEY: RefCell<Option<SealingKey>> = RefCell::new(None);
+}
+
+/// A wrapper which serializes a [`SecretBox`] or [`SecretVec`] by
+/// encrypting its contents under a key. See the [module
+/// documentation](self) for details.
+pub struct SealedSerialize<'a, S> {
+    /// the key to seal the secret under
+    key: &'a SealingKey,
+
+    /// the secret to be sealed
+    secret: &'a S,
+}
+
+/// A [`DeserializeSeed`] which deserializes a [`SecretBox`] or
+/// [`SecretVec`] serialized with [`SealedSerialize`], by decrypting it
+/// under a key. See the [module documentation](self) for details.
+pub struct SealedDeserialize<'a, S> {
+    /// the key to unseal the secret with
+    key: &'a SealingKey,
+
+    /// the type of secret to produce
+    secret: PhantomData<S>,
+}
+
+impl<'a, S> SealedSerialize<'a, S> {
+    /// Wraps `secret` so that it is sealed under `key` when serialized.
+    pub fn new(key: &'a SealingKey, secret: &'a S) -> Self {
+        Self { key, secret }
+    }
+}
+
+impl<'a, S> SealedDeserialize<'a, S> {
+    /// Instantiates a [`DeserializeSeed`] which unseals a secret with
+    /// `key`.
+    pub fn new(key: &'a SealingKey) -> Self {
+        Self { key, secret: PhantomData }
+    }
+}
+
+impl<S> Debug for SealedSerialize<'_, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{{ sealed }}")
+    }
+}
+
+impl<S> Debug for SealedDeserialize<'_, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{{ sealed }}")
+    }
+}
+
+impl<T: Bytes> Serialize for SealedSerialize<'_, SecretBox<T>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&seal(self.key, (*self.secret.borrow()).as_bytes()))
+    }
+}
+
+impl<T: Bytes> Serialize for SealedSerialize<'_, SecretVec<T>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&seal(self.key, self.secret.borrow().as_bytes()))
+    }
+}
+
+impl<'de, T: Bytes> DeserializeSeed<'de> for SealedDeserialize<'_, SecretBox<T>> {
+    type Value = SecretBox<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let sealed = deserializer.deserialize_bytes(SealedVisitor)?;
+
+        if unsealed_len(&sealed) != Some(T::size()) {
+            return Err(de::Error::custom(SecretError::InvalidEncoding));
+        }
+
+        unseal::<T, D::Error>(self.key, &sealed, 1).map(SecretBox::from_boxed)
+    }
+}
+
+impl<'de, T: Bytes> DeserializeSeed<'de> for SealedDeserialize<'_, SecretVec<T>> {
+    type Value = SecretVec<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let sealed = deserializer.deserialize_bytes(SealedVisitor)?;
+
+        // the number of zero-sized elements can't be recovered from the
+        // length of their plaintext, so they're never unsealed
+        let len = match unsealed_len(&sealed) {
+            Some(len) if T::size() != 0 && len % T::size() == 0 => len / T::size(),
+            _ => return Err(de::Error::custom(SecretError::InvalidEncoding)),
+        };
+
+        unseal::<T, D::Error>(self.key, &sealed, len).map(SecretVec::from_boxed)
+    }
+}
+
+/// Calls `f` with `key` set as the key used by [`serialize`] and
+/// [`deserialize`] on the current thread, restoring any previous key
+/// afterward. A protected copy of `key` is held for the duration of the
+/// call.
+pub fn with_key<R, F: FnOnce() -> R>(key: &SealingKey, f: F) -> R {
+    let _restore = RestoreKey(KEY.with(|k| k.borrow_mut().replace(key.clone())));
+
+    f()
+}
+
+/// Calls `f` with the key set by [`with_key`] on the current thread, if
+/// any. The key is taken out of the thread-local for the duration of
+/// the call rather than borrowed from it, so that `f` (which may run
+/// arbitrary serializer code) can itself call [`with_key`].
+fn with_current_key<R, F: FnOnce(Option<&SealingKey>) -> R>(f: F) -> R {
+    let restore = RestoreKey(KEY.with(|k| k.borrow_mut().take()));
+
+    f(restore.0.as_ref())
+}
+
+/// Restores a key to the thread-local when dropped, even if the code
+/// that replaced it panics.
+struct RestoreKey(Option<SealingKey>);
+
+impl Drop for RestoreKey {
+    fn drop(&mut self) {
+        KEY.with(|k| *k.borrow_mut() = self.0.take());
+    }
+}
+
+/// Serializes `secret` sealed under the key set by [`with_key`], for use
+/// with `#[serde(with = "secrets::sealed_serde")]`.
+///
+/// # Errors
+///
+/// Returns an error if no key has been set with [`with_key`], or if the
+/// underlying serializer does.
+pub fn serialize<V, S>(secret: &V, serializer: S) -> Result<S::Ok, S::Error>
+where
+    for<'a> SealedSerialize<'a, V>: Serialize,
+    S: Serializer,
+{
+    with_current_key(|key| match key {
+        Some(key) => SealedSerialize::new(key, secret).serialize(serializer),
+        None      => Err(ser::Error::custom("secrets: no sealing key has been set")),
+    })
+}
+
+/// Deserializes a secret sealed under the key set by [`with_key`], for
+/// use with `#[serde(with = "secrets::sealed_serde")]`.
+///
+/// # Errors
+///
+/// Returns an error if no key has been set with [`with_key`], if the
+/// secret was sealed under a different key or has been tampered with,
+/// if it is a [`SecretVec`] of a zero-sized type, or if the underlying
+/// deserializer returns one.
+pub fn deserialize<'de, V, D>(deserializer: D) -> Result<V, D::Error>
+where
+    for<'a> SealedDeserialize<'a, V>: DeserializeSeed<'de, Value = V>,
+    D: Deserializer<'de>,
+{
+    with_current_key(|key| match key {
+        Some(key) => SealedDeserialize::new(key).deserialize(deserializer),
+        None      => Err(de::Error::custom("secrets: no sealing key has been set")),
+    })
+}
+
+/// Visits the nonce and ciphertext of a sealed secret. Neither is
+/// secret, so they may be collected into unprotected memory.
+struct SealedVisitor;
+
+impl<'de> Visitor<'de> for SealedVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a sealed secret")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // the size hint comes from the input, so it isn't trusted to
+        // preallocate more than a modest amount
+        let mut sealed = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+
+        while let Some(byte) = seq.next_element()? {
+            sealed.push(byte);
+        }
+
+        Ok(sealed)
+    }
+}
+
+/// Encrypts `plaintext` under `key` with a random nonce, returning the
+/// nonce followed by the ciphertext.
+fn seal(key: &SealingKey, plaintext: &[u8]) -> Vec<u8> {
+    let mut sealed = vec![0; SECRETBOX_NONCEBYTES + SECRETBOX_MACBYTES + plaintext.len()];
+    let mut nonce  = [0; SECRETBOX_NONCEBYTES];
+
+    sodium::memrandom(&mut nonce);
+    sealed[..SECRETBOX_NONCEBYTES].copy_from_slice(&nonce);
+    sodium::secretbox_seal(&mut sealed[SECRETBOX_NONCEBYTES..], plaintext, &nonce, &key.borrow());
+
+    sealed
+}
+
+/// Returns the length in bytes of the plaintext of `sealed`, or [`None`]
+/// if it's too short to have been produced by [`seal`].
+fn unsealed_len(sealed: &[u8]) -> Option<usize> {
+    sealed.len().checked_sub(SECRETBOX_NONCEBYTES + SECRETBOX_MACBYTES)
+}
+
+/// Decrypts `sealed` under `key` directly into a new [`Box`] of `len`
+/// elements, which must be the exact length of its plaintext.
+fn unseal<T: Bytes, E: de::Error>(key: &SealingKey, sealed: &[u8], len: usize) -> Result<Box<T>, E> {
+    let (nonce, ciphertext) = sealed.split_at(SECRETBOX_NONCEBYTES);
+    let mut n               = [0; SECRETBOX_NONCEBYTES];
+
+    n.copy_from_slice(nonce);
+
+    // if decryption fails, anything written to the new allocation is
+    // zeroed as it is freed
+    Box::try_new(len, |b| {
+        if sodium::secretbox_open(b.as_mut_slice().as_mut_bytes(), ciphertext, &n, &key.borrow()) {
+            Ok(())
+        } else {
+            Err(de::Error::custom(SecretError::Decryption))
+        }
+    })
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_secret_vecs() {
+        let key    = SealingKey::random();
+        let secret = SecretVec::<u16>::random(17);
+
+        let json = serde_json::to_string(&SealedSerialize::new(&key, &secret)).unwrap();
+        let copy = SealedDeserialize::<SecretVec<u16>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+
+        assert_eq!(copy, secret);
+    }
+
+    #[test]
+    fn it_round_trips_secret_boxes() {
+        let key    = SealingKey::random();
+        let secret = SecretBox::<[u64; 4]>::random();
+
+        let json = serde_json::to_string(&SealedSerialize::new(&key, &secret)).unwrap();
+        let copy = SealedDeserialize::<SecretBox<[u64; 4]>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap();
+
+        assert_eq!(copy, secret);
+    }
+
+    #[test]
+    fn it_doesnt_serialize_plaintext() {
+        let key    = SealingKey::random();
+        let secret = SecretVec::<u8>::from(&mut [0x42; 32][..]);
+
+        let json = serde_json::to_vec(&SealedSerialize::new(&key, &secret)).unwrap();
+
+        assert!(!json.windows(b"66,66,66".len()).any(|w| w == b"66,66,66"));
+    }
+
+    #[test]
+    fn it_refuses_to_unseal_with_the_wrong_key() {
+        let key    = SealingKey::random();
+        let other  = SealingKey::random();
+        let secret = SecretVec::<u8>::random(32);
+
+        let json = serde_json::to_string(&SealedSerialize::new(&key, &secret)).unwrap();
+        let err  = SealedDeserialize::<SecretVec<u8>>::new(&other)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), SecretError::Decryption.to_string());
+    }
+
+    #[test]
+    fn it_refuses_to_unseal_into_the_wrong_size() {
+        let key    = SealingKey::random();
+        let secret = SecretVec::<u8>::random(3);
+
+        let json = serde_json::to_string(&SealedSerialize::new(&key, &secret)).unwrap();
+
+        assert!(SealedDeserialize::<SecretBox<u32>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .is_err());
+
+        assert!(SealedDeserialize::<SecretVec<u16>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .is_err());
+
+        assert!(SealedDeserialize::<SecretVec<u8>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str("[1, 2, 3]"))
+            .is_err());
+    }
+
+    #[test]
+    fn it_refuses_to_unseal_zero_sized_elements() {
+        let key    = SealingKey::random();
+        let secret = SecretVec::<()>::zero(4);
+
+        let json = serde_json::to_string(&SealedSerialize::new(&key, &secret)).unwrap();
+        let err  = SealedDeserialize::<SecretVec<()>>::new(&key)
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), SecretError::InvalidEncoding.to_string());
+    }
+
+    #[test]
+    fn it_seals_fields_with_the_thread_key() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Record {
+            #[serde(with = "crate::sealed_serde")]
+            key: SecretBox<[u8; 16]>,
+        }
+
+        let key    = SealingKey::random();
+        let record = Record { key: SecretBox::random() };
+
+        let json = with_key(&key, || serde_json::to_string(&record)).unwrap();
+        let copy = with_key(&key, || serde_json::from_str::<Record>(&json)).unwrap();
+
+        assert_eq!(copy.key, record.key);
+        assert!(serde_json::to_string(&record).is_err());
+        assert!(serde_json::from_str::<Record>(&json).is_err());
+    }
+
+    #[test]
+    fn it_allows_setting_keys_while_serializing() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Record {
+            #[serde(with = "crate::sealed_serde")]
+            key: SecretBox<[u8; 16]>,
+        }
+
+        /// A writer which sets a different key whenever it's written to.
+        struct NestedWriter(Vec<u8>, SealingKey);
+
+        impl std::io::Write for NestedWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let NestedWriter(ref mut out, ref other) = *self;
+
+                with_key(other, || out.extend_from_slice(buf));
+
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let key    = SealingKey::random();
+        let record = Record { key: SecretBox::random() };
+        let mut w  = NestedWriter(Vec::new(), SealingKey::random());
+
+        let copy = with_key(&key, || {
+            serde_json::to_writer(&mut w, &record).unwrap();
+            serde_json::from_slice::<Record>(&w.0).unwrap()
+        });
+
+        assert_eq!(copy.key, record.key);
+    }
+
+    #[test]
+    fn it_doesnt_trust_sequence_size_hints() {
+        /// An empty sequence which claims to hold far more elements.
+        struct Lying;
+
+        impl<'de> SeqAccess<'de> for Lying {
+            type Error = de::value::Error;
+
+            fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, _: T) -> Result<Option<T::Value>, Self::Error> {
+                Ok(None)
+            }
+
+            fn size_hint(&self) -> Option<usize> {
+                Some(usize::MAX)
+            }
+        }
+
+        assert!(SealedVisitor.visit_seq(Lying).unwrap().is_empty());
+    }
+}
+
+// LCOV_EXCL_STOP
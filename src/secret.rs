@@ -1,13 +1,26 @@
 #![allow(missing_debug_implementations)]
 #![allow(unsafe_code)]
 
-use crate::ffi::sodium;
+use crate::error::SecretError;
+use crate::ffi::sodium::{self, SHA256_BYTES};
+use crate::stats;
 use crate::traits::*;
 
 use std::borrow::BorrowMut;
 use std::fmt::{self, Debug, Formatter};
+use std::mem::MaybeUninit;
 use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
+use std::time::Duration;
+
+/// The number of times a failed `mlock(2)` is retried by default before
+/// giving up.
+const DEFAULT_MLOCK_RETRIES: usize = 3;
+
+/// The number of times a failed `mlock(2)` is retried before giving
+/// up. See [`set_mlock_retries`].
+static MLOCK_RETRIES: AtomicUsize = AtomicUsize::new(DEFAULT_MLOCK_RETRIES);
 
 /// A type for protecting secrets allocated on the stack.
 ///
@@ -77,7 +90,29 @@ use std::thread;
 /// assert_eq!(value, [0, 0, 0, 0]);
 /// ```
 ///
+/// # Memory layout
+///
+/// Calls to `munlock(2)` unlock every page that overlaps the memory
+/// provided, regardless of whether anything else on those pages is
+/// still meant to be locked. If [`Secret`]s could share a page,
+/// dropping one (for instance from within the callback of another)
+/// would silently unlock the other. Each [`Secret`] is therefore
+/// aligned and padded to a multiple of 4 KiB (16 KiB on Apple Silicon),
+/// the page size of most systems, which costs at least that much stack
+/// per [`Secret`] but prevents other data from sharing its pages. The
+/// alignment is fixed at compile time, so on systems configured with
+/// larger pages (such as some aarch64 and ppc64 Linux kernels, which
+/// use 64 KiB pages) nested [`Secret`]s may still share a page.
+///
 /// [mlock]: http://man7.org/linux/man-pages/man2/mlock.2.html
+#[cfg_attr(
+    all(target_vendor = "apple", target_arch = "aarch64"),
+    repr(C, align(16384))
+)]
+#[cfg_attr(
+    not(all(target_vendor = "apple", target_arch = "aarch64")),
+    repr(C, align(4096))
+)]
 pub struct Secret<T: Bytes> {
     /// The internal protected memory for the [`Secret`].
     data: T,
@@ -98,6 +133,15 @@ impl<T: Bytes> Secret<T> {
     /// with a well-defined, arbitrary byte pattern, and should be
     /// initialized to something meaningful before actual use.
     ///
+    /// The callback initializes the secret in place, writing directly
+    /// into the memory that has already been locked. Large secrets
+    /// therefore never need to be constructed elsewhere and copied in,
+    /// which would leave unprotected copies behind. Note that `T` must
+    /// still implement [`Bytes`], and is therefore [`Copy`]; care must
+    /// be taken not to copy the contents back out of the [`Secret`]. See
+    /// [`build`](Secret::build) for initializing the contents through a
+    /// [`MaybeUninit`] instead.
+    ///
     /// # Panics
     ///
     /// This function will panic if the underlying call to `mlock(2)`
@@ -114,6 +158,133 @@ impl<T: Bytes> Secret<T> {
     /// ```
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
     pub fn new<F, U>(f: F) -> U
+    where
+        F: FnOnce(RefMut<'_, T>) -> U,
+    {
+        match Self::try_new(f) {
+            Ok(u)  => u,
+            Err(_) => panic!("secrets: unable to mlock memory for a Secret"),
+        }
+    }
+
+    /// Creates a new [`Secret`] and invokes the provided callback with
+    /// a wrapper to the protected memory. Has equivalent semantics to
+    /// [`new`](Secret::new), but the callback may not return anything.
+    ///
+    /// The result of a callback passed to [`new`](Secret::new) can't
+    /// borrow from the [`Secret`] either, since its type is chosen
+    /// before the callback is given a wrapper of any particular
+    /// lifetime. This variant simply makes it explicit that nothing
+    /// computed from the secret escapes the callback, other than
+    /// through its side effects.
+    ///
+    /// ```
+    /// # use secrets::Secret;
+    /// Secret::<[u8; 4]>::new_scoped(|mut s| {
+    ///     s.copy_from_slice(&[1, 2, 3, 4]);
+    ///
+    ///     assert_eq!(s[..], [1, 2, 3, 4]);
+    /// });
+    /// ```
+    ///
+    /// Smuggling a borrow out of the callback is rejected at compile
+    /// time:
+    ///
+    /// ```compile_fail,E0521
+    /// # use secrets::Secret;
+    /// let mut out = None;
+    ///
+    /// Secret::<[u8; 4]>::new_scoped(|s| out = Some(&s[..]));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the underlying call to `mlock(2)`
+    /// (`VirtualLock` on windows) fails.
+    pub fn new_scoped<F>(f: F)
+    where
+        F: for<'a> FnOnce(RefMut<'a, T>),
+    {
+        Self::new(f)
+    }
+
+    /// Creates a new [`Secret`], initializes it in place with `init`,
+    /// and then invokes the callback `f` with a wrapper to the protected
+    /// memory. Has equivalent semantics to [`new`](Secret::new).
+    ///
+    /// `init` is given the locked memory of the [`Secret`] itself as a
+    /// [`MaybeUninit`], so large secrets can be initialized piece by
+    /// piece through [`MaybeUninit::as_mut_ptr`] without ever existing
+    /// as a value that could be moved or copied out of protected memory.
+    ///
+    /// ```
+    /// # use secrets::Secret;
+    /// let sum = unsafe {
+    ///     Secret::<[u32; 1024]>::build(
+    ///         |s| {
+    ///             for i in 0..1024 {
+    ///                 s.as_mut_ptr().cast::<u32>().add(i).write(1);
+    ///             }
+    ///         },
+    ///         |s| s.iter().sum::<u32>(),
+    ///     )
+    /// };
+    ///
+    /// assert_eq!(sum, 1024);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The memory passed to `init` starts out filled with the same
+    /// arbitrary byte pattern as [`new`](Secret::new), and so is already
+    /// a valid `T`. `init` must not leave any of its bytes uninitialized,
+    /// for instance by writing [`MaybeUninit::uninit`] over it.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the underlying call to `mlock(2)`
+    /// (`VirtualLock` on windows) fails.
+    pub unsafe fn build<I, F, U>(init: I, f: F) -> U
+    where
+        I: FnOnce(&mut MaybeUninit<T>),
+        F: FnOnce(RefMut<'_, T>) -> U,
+    {
+        Self::new(|mut s| {
+            let data: *mut T = &mut *s;
+
+            // `MaybeUninit<T>` is guaranteed to have the same layout as
+            // `T`, and the caller guarantees that it remains initialized
+            init(&mut *data.cast::<MaybeUninit<T>>());
+            f(s)
+        })
+    }
+
+    /// Creates a new [`Secret`] and invokes the provided callback with
+    /// a wrapper to the protected memory. Has equivalent semantics to
+    /// [`new`](Secret::new), but returns an error instead of panicking
+    /// if the memory cannot be locked.
+    ///
+    /// A failed `mlock(2)` may be transient, for instance when the
+    /// system is under memory pressure, so it is retried a few times
+    /// (see [`set_mlock_retries`]) with an exponentially-increasing
+    /// delay before giving up. The callback is only invoked once the
+    /// memory has been successfully locked.
+    ///
+    /// ```
+    /// # use secrets::Secret;
+    /// let sum = Secret::<[u8; 2]>::try_new(|mut s| {
+    ///     s.copy_from_slice(&[1, 2]);
+    ///     s[0] + s[1]
+    /// });
+    ///
+    /// assert_eq!(sum, Ok(3));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::Mlock`] if the memory could not be locked
+    /// after every retry has been exhausted.
+    pub fn try_new<F, U>(f: F) -> Result<U, SecretError>
     where
         F: FnOnce(RefMut<'_, T>) -> U,
     {
@@ -123,12 +294,15 @@ impl<T: Bytes> Secret<T> {
             data: T::uninitialized(),
         };
 
-        assert!(
-            unsafe { sodium::mlock(&mut secret.data) },
-            "secrets: unable to mlock memory for a Secret"
-        );
+        // this is recorded even if locking fails, since it will be
+        // balanced when `secret` is dropped regardless
+        stats::record_lock(T::size());
+
+        if !with_mlock_retries(|| unsafe { sodium::mlock(&mut secret.data) }) {
+            return Err(SecretError::Mlock);
+        }
 
-        f(RefMut::new(&mut secret.data))
+        Ok(f(RefMut::new(&mut secret.data)))
     }
 }
 
@@ -199,14 +373,60 @@ impl<T: Bytes + Randomizable> Secret<T> {
     }
 }
 
+/// Sets the number of times a failed `mlock(2)` is retried when
+/// creating a secret, after the initial attempt, before giving up.
+/// Each retry waits twice as long as the one before it, starting at one
+/// millisecond. Defaults to 3.
+///
+/// A secret that still can't be locked is never created. This is
+/// reported as [`SecretError::Mlock`] by fallible constructors like
+/// [`Secret::try_new`] and
+/// [`SecretVec::try_clone`](crate::SecretVec::try_clone), and every
+/// other constructor panics.
+///
+/// This applies process-wide to every secret created afterward, rather
+/// than being an argument to each constructor. Transient failures are a
+/// consequence of the system's memory pressure rather than of any
+/// particular secret, so how long it's worth waiting for them to pass
+/// is a decision for the application as a whole. Set this to zero if
+/// creating secrets must never sleep.
+///
+/// ```
+/// secrets::set_mlock_retries(5);
+/// ```
+pub fn set_mlock_retries(retries: usize) {
+    MLOCK_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// Calls `mlock` until it succeeds, retrying with exponential backoff
+/// up to the number of times set by [`set_mlock_retries`]. Returns true
+/// if it ultimately succeeded.
+pub(crate) fn with_mlock_retries<F: FnMut() -> bool>(mut mlock: F) -> bool {
+    let retries = MLOCK_RETRIES.load(Ordering::Relaxed);
+
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            // the shift is capped so that very high retry counts don't
+            // overflow, and only wait for about a second at most
+            thread::sleep(Duration::from_millis(1 << (attempt - 1).min(10)));
+        }
+
+        if mlock() {
+            return true;
+        }
+    }
+
+    false
+}
+
 impl<T: Bytes> Drop for Secret<T> {
     /// Ensures that the [`Secret`]'s underlying memory is `munlock`ed
     /// and zeroed when it leaves scope.
     fn drop(&mut self) {
         // When we call sodium_munlock on some data, it actually unlocks the entire page that
-        // contains the memory. If two locked items were on the same page, then the second one
-        // fails because it was already unlocked. On Linux, this does now throw an error. On
-        // Windows, it does. We'll ignore it for now, and provide a better fix later.
+        // contains the memory. Secrets are page-aligned so they never share a page with one
+        // another, but on Windows an unrelated `VirtualLock` of the same page may already have
+        // been undone, which is reported as an error there (but not on Linux). We ignore it.
         if unsafe { !sodium::munlock(&mut self.data) }
             && !(cfg!(target_family = "windows")
                 && std::io::Error::last_os_error().raw_os_error().map_or(false, |c| c == 158)) {
@@ -217,6 +437,8 @@ impl<T: Bytes> Drop for Secret<T> {
                 "secrets: unable to munlock memory for a Secret"
             );
         };
+
+        stats::record_unlock(T::size());
     }
 }
 
@@ -225,6 +447,57 @@ impl<'a, T: Bytes> RefMut<'a, T> {
     pub(crate) fn new(data: &'a mut T) -> Self {
         Self { data }
     }
+
+    /// Copies `src` into the [`Secret`], treating its contents as a
+    /// flat sequence of bytes. This fills any `T` the same way, whether
+    /// or not it is an array.
+    ///
+    /// Note that `src` is only copied, and is not zeroed. It's the
+    /// caller's responsibility to dispose of it safely.
+    ///
+    /// ```
+    /// # use secrets::Secret;
+    /// Secret::<u64>::new(|mut s| {
+    ///     s.copy_from_bytes(&[0xff; 8]).unwrap();
+    ///
+    ///     assert_eq!(*s, u64::max_value());
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::LengthMismatch`], leaving the [`Secret`]
+    /// unchanged, if `src` is not exactly [`size`](Bytes::size) bytes
+    /// long.
+    pub fn copy_from_bytes(&mut self, src: &[u8]) -> Result<(), SecretError> {
+        if src.len() != self.data.size() {
+            return Err(SecretError::LengthMismatch);
+        }
+
+        self.data.as_mut_bytes().copy_from_slice(src);
+
+        Ok(())
+    }
+
+    /// Returns true if the SHA-256 digest of the [`Secret`]'s contents
+    /// is `expected`, comparing them in constant time. The digest is
+    /// computed into a scratch buffer that is zeroed afterward, while
+    /// `expected` is assumed not to be secret.
+    ///
+    /// ```
+    /// # use secrets::Secret;
+    /// const DIGEST: [u8; 32] = [
+    ///     0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    ///     0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    /// ];
+    ///
+    /// Secret::from(&mut b"abc".to_owned(), |s| {
+    ///     assert!(s.matches_digest_ct(&DIGEST));
+    /// });
+    /// ```
+    pub fn matches_digest_ct(&self, expected: &[u8; SHA256_BYTES]) -> bool {
+        sodium::sha256_verify(self.data.as_bytes(), expected)
+    }
 }
 
 impl<T: Bytes + Clone> Clone for RefMut<'_, T> {
@@ -258,6 +531,16 @@ impl<T: Bytes> PartialEq for RefMut<'_, T> {
     }
 }
 
+impl<T: Bytes> PartialEq<T> for RefMut<'_, T> {
+    /// Compares the [`Secret`] against a known value in constant time.
+    /// The value being compared against is assumed not to be secret
+    /// itself, since it is not afforded any of the protections of a
+    /// [`Secret`].
+    fn eq(&self, rhs: &T) -> bool {
+        self.data.constant_eq(rhs)
+    }
+}
+
 impl<T: Bytes> Eq for RefMut<'_, T> {}
 
 // LCOV_EXCL_START
@@ -265,6 +548,7 @@ impl<T: Bytes> Eq for RefMut<'_, T> {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::mem::align_of;
     use std::ptr;
 
     #[test]
@@ -272,6 +556,86 @@ mod tests {
         Secret::<u16>::new(|s| assert_eq!(*s, 0xdbdb));
     }
 
+    #[test]
+    fn it_invokes_scoped_callbacks() {
+        let mut called = false;
+
+        Secret::<u32>::new_scoped(|mut s| {
+            *s     = 0x1234_5678;
+            called = true;
+
+            assert_eq!(*s, 0x1234_5678);
+        });
+
+        assert!(called);
+    }
+
+    #[test]
+    fn it_copies_from_bytes() {
+        Secret::<[u16; 2]>::zero(|mut s| {
+            assert_eq!(s.copy_from_bytes(&[1, 0, 2, 0]), Ok(()));
+            assert_eq!(*s, [u16::from_le_bytes([1, 0]), u16::from_le_bytes([2, 0])]);
+        });
+    }
+
+    #[test]
+    fn it_doesnt_copy_from_bytes_of_the_wrong_length() {
+        Secret::<u32>::zero(|mut s| {
+            assert_eq!(s.copy_from_bytes(&[1, 2, 3]),       Err(SecretError::LengthMismatch));
+            assert_eq!(s.copy_from_bytes(&[1, 2, 3, 4, 5]), Err(SecretError::LengthMismatch));
+            assert_eq!(*s,                                  0);
+        });
+    }
+
+    #[test]
+    fn it_builds_large_secrets_in_place() {
+        let mut init_addr = 0;
+
+        let (addr, first, last) = unsafe {
+            Secret::<[u64; 512]>::build(
+                |s| {
+                    init_addr = s.as_ptr() as usize;
+
+                    for i in 0..512 {
+                        s.as_mut_ptr().cast::<u64>().add(i).write(i as u64);
+                    }
+                },
+                |s| (s.as_ptr() as usize, s[0], s[511]),
+            )
+        };
+
+        assert_eq!(addr,  init_addr);
+        assert_eq!(first, 0);
+        assert_eq!(last,  511);
+    }
+
+    #[test]
+    fn it_builds_secrets_in_locked_memory() {
+        let before = stats::thread_locked_bytes();
+
+        unsafe {
+            Secret::<[u8; 64]>::build(
+                |_| assert_eq!(stats::thread_locked_bytes(), before + 64),
+                |s| assert_eq!(*s, [0xdb; 64]),
+            );
+        }
+    }
+
+    #[test]
+    fn it_initializes_large_secrets_in_place() {
+        Secret::<[u64; 512]>::new(|mut s| {
+            let addr = s.as_ptr();
+
+            for (i, x) in s.iter_mut().enumerate() {
+                *x = i as u64;
+            }
+
+            assert_eq!(s.as_ptr(), addr);
+            assert_eq!(s[0],       0);
+            assert_eq!(s[511],     511);
+        });
+    }
+
     #[test]
     fn it_zeroes_when_leaving_scope() {
         unsafe {
@@ -340,6 +704,25 @@ mod tests {
         });
     }
 
+    #[test]
+    fn it_compares_equality_with_known_values() {
+        Secret::<u32>::from(&mut 0x0123_4567, |s| {
+            assert_eq!(s, 0x0123_4567);
+            assert_ne!(s, 0x7654_3210);
+        });
+    }
+
+    #[test]
+    fn it_matches_digests() {
+        Secret::from(&mut b"abc".to_owned(), |s| {
+            assert!(s.matches_digest_ct(&sodium::SHA256_ABC));
+        });
+
+        Secret::from(&mut b"abd".to_owned(), |s| {
+            assert!(!s.matches_digest_ct(&sodium::SHA256_ABC));
+        });
+    }
+
     #[test]
     fn it_preserves_secrecy() {
         Secret::<[u64; 2]>::zero(|s| {
@@ -362,15 +745,95 @@ mod tests {
     #[test]
     #[should_panic(expected = "secrets: unable to mlock memory for a Secret")]
     fn it_detects_sodium_mlock_failure() {
-        sodium::fail();
+        sodium::fail_times(DEFAULT_MLOCK_RETRIES + 1);
         Secret::<u8>::zero(|_| {});
     }
 
+    #[test]
+    fn it_retries_transient_sodium_mlock_failures() {
+        sodium::fail();
+
+        assert_eq!(Secret::<u8>::try_new(|mut s| { *s = 4; *s }), Ok(4));
+    }
+
+    #[test]
+    fn it_reports_persistent_sodium_mlock_failures() {
+        let mut called = false;
+
+        sodium::fail_times(DEFAULT_MLOCK_RETRIES + 1);
+
+        assert_eq!(Secret::<u8>::try_new(|_| called = true), Err(SecretError::Mlock));
+        assert!(!called);
+    }
+
     #[test]
     #[should_panic(expected = "secrets: unable to munlock memory for a Secret")]
     fn it_detects_sodium_munlock_failure() {
         Secret::<u8>::zero(|_| sodium::fail());
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_doesnt_share_pages_between_nested_secrets() {
+        #[allow(unsafe_code)]
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+        // pages larger than the alignment of a `Secret` can be shared
+        if page > align_of::<Secret<u64>>() {
+            return;
+        }
+
+        Secret::<u64>::zero(|outer| {
+            let outer_addr = ptr::addr_of!(*outer) as usize;
+
+            Secret::<u64>::zero(|inner| {
+                let inner_addr = ptr::addr_of!(*inner) as usize;
+
+                assert_ne!(outer_addr / page, inner_addr / page);
+            });
+        });
+    }
+
+    #[test]
+    fn it_keeps_outer_secrets_locked_after_inner_secrets_drop() {
+        Secret::<u64>::from(&mut 0x0bad_cafe, |outer| {
+            let addr = ptr::addr_of!(*outer) as usize;
+
+            Secret::<u64>::random(|_| {});
+
+            #[cfg(target_os = "linux")]
+            assert_ne!(locked_kb(addr), 0);
+
+            assert_eq!(*outer, 0x0bad_cafe);
+        });
+    }
+
+    /// Returns the number of kilobytes locked in the mapping that
+    /// contains `addr`, as reported by `/proc/self/smaps`.
+    #[cfg(target_os = "linux")]
+    fn locked_kb(addr: usize) -> usize {
+        let     smaps      = std::fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut in_mapping = false;
+
+        for line in smaps.lines() {
+            let range = line
+                .split(' ')
+                .next()
+                .map(|range| range.splitn(2, '-'))
+                .and_then(|mut bounds| Some((
+                    usize::from_str_radix(bounds.next()?, 16).ok()?,
+                    usize::from_str_radix(bounds.next()?, 16).ok()?,
+                )));
+
+            if let Some((lo, hi)) = range {
+                in_mapping = lo <= addr && addr < hi;
+            } else if let (true, Some(kb)) = (in_mapping, line.strip_prefix("Locked:")) {
+                return kb.trim().trim_end_matches("kB").trim().parse().unwrap();
+            }
+        }
+
+        panic!("secrets: no mapping contains {:#x}", addr);
+    }
 }
 
 // LCOV_EXCL_STOP
@@ -1,8 +1,18 @@
+use crate::audit::Exposure;
 use crate::boxed::Box;
+use crate::error::SecretError;
+use crate::ffi::sodium::{self, AUTH_BYTES, AUTH_KEYBYTES, SHA256_BYTES};
+use crate::sealed_box::SealedBox;
+use crate::secret;
+use crate::secret_vec::SecretVec;
 use crate::traits::*;
 
 use std::fmt::{self, Debug, Formatter};
+#[cfg(feature = "constant-hash")]
+use std::hash::{Hash, Hasher};
+use std::mem::{self, size_of};
 use std::ops::{Deref, DerefMut};
+use std::thread;
 
 ///
 /// A type for protecting fixed-length secrets allocated on the heap.
@@ -60,6 +70,12 @@ use std::ops::{Deref, DerefMut};
 /// Care must also be taken not to call any other methods on these types
 /// that introduce copying.
 ///
+/// These wrappers must also be allowed to drop normally. A wrapper that
+/// is leaked instead (e.g., with [`mem::forget`](std::mem::forget))
+/// leaves the memory unlocked indefinitely. As a development aid, debug
+/// builds detect this the next time the [`SecretBox`] is borrowed and panic;
+/// release builds perform no such check.
+///
 /// # Example: generate a cryptographically-random 128-bit [`SecretBox`]
 ///
 /// Initialize a [`SecretBox`] with cryptographically random data:
@@ -138,11 +154,16 @@ pub struct SecretBox<T: Bytes> {
 /// for convenience.
 ///
 /// When this wrapper is dropped, it ensures that the underlying memory
-/// is re-locked.
+/// is re-locked. If it is leaked instead, debug builds detect this the
+/// next time the [`SecretBox`] is borrowed, but release builds don't.
 pub struct Ref<'a, T: Bytes> {
     /// an imutably-unlocked reference to the protected memory of a
     /// [`SecretBox`].
     boxed: &'a Box<T>,
+
+    /// whether or not the borrow has been temporarily re-locked with
+    /// [`pause`](Ref::pause)
+    paused: bool,
 }
 
 /// A mutable wrapper around the internal contents of a
@@ -150,19 +171,72 @@ pub struct Ref<'a, T: Bytes> {
 /// for convenience.
 ///
 /// When this wrapper is dropped, it ensures that the underlying memory
-/// is re-locked.
+/// is re-locked. If it is leaked instead, debug builds detect this the
+/// next time the [`SecretBox`] is borrowed, but release builds don't.
 pub struct RefMut<'a, T: Bytes> {
     /// a mutably-unlocked reference to the protected memory of a
     /// [`SecretBox`].
     boxed: &'a mut Box<T>,
+
+    /// whether or not the borrow has been temporarily re-locked with
+    /// [`pause`](RefMut::pause)
+    paused: bool,
+
+    /// whether or not the thread was already panicking when the borrow
+    /// was created
+    panicking: bool,
+}
+
+/// An immutable wrapper around a single element of a [`SecretBox`] of
+/// a fixed-size array. This wrapper [`Deref`]s to the element for
+/// convenience.
+///
+/// When this wrapper is dropped, it ensures that the underlying memory
+/// is re-locked.
+pub struct ItemRef<'a, T: Bytes, const N: usize> {
+    /// an immutably-unlocked reference to the protected memory of a
+    /// [`SecretBox`].
+    boxed: &'a Box<[T; N]>,
+
+    /// the index of the element within the array
+    index: usize,
+}
+
+/// An iterator over immutable wrappers around each element of a
+/// [`SecretBox`] of a fixed-size array, returned by
+/// [`iter`](SecretBox::iter).
+///
+/// The [`SecretBox`] is only unlocked while a yielded element is alive,
+/// so the iterator itself is cheap to [`Clone`].
+#[derive(Clone, Debug)]
+pub struct IterRef<'a, T: Bytes, const N: usize> {
+    /// the [`SecretBox`] being iterated over
+    secret: &'a SecretBox<[T; N]>,
+
+    /// the index of the next element to yield
+    index: usize,
 }
 
 impl<T: Bytes> SecretBox<T> {
+    /// The size in bytes of the `T` contained in a [`SecretBox`]. Unlike
+    /// [`size`](SecretBox::size), this is available at compile time and
+    /// may be used to size other buffers.
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let buf = [0_u8; SecretBox::<u64>::BYTE_SIZE];
+    ///
+    /// assert_eq!(buf.len(), 8);
+    /// ```
+    pub const BYTE_SIZE: usize = size_of::<T>();
+
     /// Instantiates and returns a new [`SecretBox`].
     ///
     /// Accepts a callback function that is responsible for initializing
     /// its contents. The value yielded to the initialization callback
-    /// will be filled with garbage bytes.
+    /// will be filled with garbage bytes. If the callback panics, any
+    /// contents it has already written are zeroed out as the underlying
+    /// memory is freed during unwinding.
     ///
     /// Example:
     ///
@@ -181,6 +255,32 @@ impl<T: Bytes> SecretBox<T> {
         }
     }
 
+    /// Instantiates and returns a new [`SecretBox`]. Has equivalent
+    /// semantics to [`new`](SecretBox::new), but returns the value
+    /// produced by the callback alongside the [`SecretBox`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let (secret, written) = SecretBox::<[u8; 4]>::new_with(|s| {
+    ///     s[..2].copy_from_slice(&[0xab, 0xcd]);
+    ///     s[2..].copy_from_slice(&[0x00, 0x00]);
+    ///     2
+    /// });
+    ///
+    /// assert_eq!(written,         2);
+    /// assert_eq!(*secret.borrow(), [0xab, 0xcd, 0x00, 0x00]);
+    /// ```
+    pub fn new_with<R, F>(f: F) -> (Self, R)
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let (boxed, result) = Box::new_with(1, |b| f(b.as_mut()));
+
+        (Self { boxed }, result)
+    }
+
     /// Instantiates and returns a new [`SecretBox`]. Has equivalent
     /// semantics to [`new`](SecretBox::new), but allows the callback to
     /// return success or failure through a [`Result`].
@@ -196,15 +296,112 @@ impl<T: Bytes> SecretBox<T> {
             .map(|b| Self { boxed: b })
     }
 
+    /// Instantiates and returns a new [`SecretBox`], initializing it
+    /// through a raw pointer. This is intended for C APIs which write
+    /// into a caller-provided buffer and report separately whether they
+    /// succeeded.
+    ///
+    /// The memory is zeroed, rather than filled with garbage bytes,
+    /// before being passed to the callback. If the callback returns
+    /// `false`, whatever it wrote is zeroed, the [`SecretBox`] is
+    /// dropped, and [`None`] is returned.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u8; 4]>::new_uninit(|ptr| {
+    ///     unsafe { ptr.write([1, 2, 3, 4]) };
+    ///     true
+    /// });
+    ///
+    /// assert_eq!(*secret.unwrap().borrow(), [1, 2, 3, 4]);
+    /// ```
+    pub fn new_uninit<F>(f: F) -> Option<Self>
+    where
+        F: FnOnce(*mut T) -> bool,
+    {
+        Self::try_new(|s| {
+            s.zero();
+
+            if f(std::ptr::addr_of_mut!(*s)) {
+                Ok(())
+            } else {
+                s.zero();
+                Err(())
+            }
+        }).ok()
+    }
+
+    /// Wraps an existing [`Box`] of a single element in a [`SecretBox`].
+    #[cfg(all(feature = "serde", feature = "crypto"))]
+    pub(crate) fn from_boxed(boxed: Box<T>) -> Self {
+        proven!(boxed.len() == 1);
+
+        Self { boxed }
+    }
+
     /// Returns the size in bytes of the [`SecretBox`].
     pub fn size(&self) -> usize {
         self.boxed.size()
     }
 
+    /// Returns true if the memory underlying the [`SecretBox`] was
+    /// successfully `mlock`ed when it was allocated. Has equivalent
+    /// semantics to [`SecretVec::is_mlocked`].
+    pub fn is_mlocked(&self) -> bool {
+        self.boxed.is_mlocked()
+    }
+
+    /// Returns the size in bytes of the `T` contained in a
+    /// [`SecretBox`], without needing an instance of one.
+    ///
+    /// This is always equal to [`size`](SecretBox::size), but may be
+    /// clearer in generic contexts that also deal with the sizes of
+    /// [`SecretVec`](crate::SecretVec)s.
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// assert_eq!(SecretBox::<[u32; 4]>::element_size(), 16);
+    /// ```
+    pub fn element_size() -> usize {
+        Self::BYTE_SIZE
+    }
+
+    /// Configures whether or not the [`Debug`] representation of the
+    /// [`SecretBox`] and its borrows reveals the number of bytes being
+    /// redacted. The size is shown by default, as `{ N bytes redacted }`;
+    /// when hidden, only `{ redacted }` is shown.
+    ///
+    /// This setting is preserved by [`Clone`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u8; 32]>::zero();
+    ///
+    /// assert_eq!(format!("{:?}", secret), "{ 32 bytes redacted }");
+    ///
+    /// let secret = secret.debug_size(false);
+    ///
+    /// assert_eq!(format!("{:?}", secret), "{ redacted }");
+    /// ```
+    pub fn debug_size(mut self, show: bool) -> Self {
+        self.boxed.set_debug_size(show);
+        self
+    }
+
     /// Immutably borrows the contents of the [`SecretBox`]. Returns a
     /// wrapper that ensures the underlying memory is
     /// [`mprotect(2)`][mprotect]ed once all borrows exit scope.
     ///
+    /// Any number of immutable borrows may be held simultaneously.
+    /// These are reference-counted, so only the first borrow makes the
+    /// memory readable and only the last to be dropped protects it
+    /// again; additional borrows do not incur further calls to
+    /// [`mprotect(2)`][mprotect].
+    ///
     /// Example:
     ///
     /// ```
@@ -223,6 +420,33 @@ impl<T: Bytes> SecretBox<T> {
         Ref::new(&self.boxed)
     }
 
+    /// Immutably borrows the contents of the [`SecretBox`]. Has
+    /// equivalent semantics to [`borrow`](SecretBox::borrow), but
+    /// returns an error rather than panicking if the [`SecretBox`] is
+    /// already borrowed as many times as can be tracked at once (255,
+    /// or 4,294,967,295 with the `wide-refcount` feature).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretBox, SecretError};
+    /// let secret    = SecretBox::<u8>::from(&mut 127);
+    /// let secret_r1 = secret.try_borrow()?;
+    /// let secret_r2 = secret.try_borrow()?;
+    ///
+    /// assert_eq!(*secret_r1, 127);
+    /// assert_eq!(*secret_r2, 127);
+    /// # Ok::<(), SecretError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::TooManyBorrows`] if taking another borrow
+    /// would overflow the count of outstanding borrows.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, SecretError> {
+        Ref::try_new(&self.boxed)
+    }
+
     /// Mutably borrows the contents of the [`SecretBox`]. Returns a
     /// wrapper that ensures the underlying memory is
     /// [`mprotect(2)`][mprotect]ed once this borrow exits scope.
@@ -243,6 +467,301 @@ impl<T: Bytes> SecretBox<T> {
     pub fn borrow_mut(&mut self) -> RefMut<'_, T> {
         RefMut::new(&mut self.boxed)
     }
+
+    /// Calls `f` with an immutable reference to the contents of the
+    /// [`SecretBox`], returning its result. The underlying memory is
+    /// only unlocked for as long as `f` runs.
+    ///
+    /// This is an auditable alternative to [`borrow`](SecretBox::borrow).
+    /// With the `tracing` feature enabled, each exposure emits an event
+    /// recording `reason` and how long the contents were exposed. The
+    /// reason is logged verbatim, so it must never contain any part of
+    /// the secret.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<u8>::from(&mut 0x20);
+    ///
+    /// assert!(secret.expose_for("checking the key", |s| *s == 0x20));
+    /// ```
+    pub fn expose_for<R, F>(&self, reason: &'static str, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let _exposure = Exposure::new(reason);
+
+        f(&self.borrow())
+    }
+
+    /// Transforms the contents of the [`SecretBox`] in place, returning
+    /// any by-product of the transformation. The contents are mutably
+    /// borrowed only for as long as `f` runs.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let mut key = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 4]);
+    ///
+    /// let old = key.update(|k| {
+    ///     let old = k[0];
+    ///     k.rotate_left(1);
+    ///     old
+    /// });
+    ///
+    /// assert_eq!(old,          1);
+    /// assert_eq!(*key.borrow(), [2, 3, 4, 1]);
+    /// ```
+    pub fn update<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        f(&mut self.borrow_mut())
+    }
+
+    /// Replaces the [`SecretBox`] with `new`, returning the old
+    /// [`SecretBox`] so that it can still be used briefly, for instance
+    /// to decrypt data that is still in flight after rotating a key.
+    ///
+    /// Only the owning pointers are swapped, so neither allocation is
+    /// unlocked and both remain protected throughout. The old contents
+    /// are zeroed as usual once the returned [`SecretBox`] is dropped.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let mut key = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 4]);
+    /// let     old = key.replace(SecretBox::from(&mut [5, 6, 7, 8]));
+    ///
+    /// assert_eq!(*old.borrow(), [1, 2, 3, 4]);
+    /// assert_eq!(*key.borrow(), [5, 6, 7, 8]);
+    /// ```
+    pub fn replace(&mut self, new: Self) -> Self {
+        mem::replace(self, new)
+    }
+
+    /// Overwrites the contents of the [`SecretBox`] with those of
+    /// `other` if `choice` is set, and leaves them unchanged otherwise,
+    /// without branching on `choice`.
+    ///
+    /// Both [`SecretBox`]es are unlocked and every byte of each is read
+    /// and written regardless of `choice`, so neither the time taken nor
+    /// the memory accessed depends on it. This mirrors
+    /// [`subtle::ConditionallySelectable::conditional_assign`] and
+    /// requires the `subtle` feature flag.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// use subtle::Choice;
+    ///
+    /// let mut secret = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 4]);
+    /// let     other  = SecretBox::<[u8; 4]>::from(&mut [5, 6, 7, 8]);
+    ///
+    /// secret.conditional_assign(&other, Choice::from(0));
+    /// assert_eq!(*secret.borrow(), [1, 2, 3, 4]);
+    ///
+    /// secret.conditional_assign(&other, Choice::from(1));
+    /// assert_eq!(*secret.borrow(), [5, 6, 7, 8]);
+    /// ```
+    #[cfg(feature = "subtle")]
+    pub fn conditional_assign(&mut self, other: &Self, choice: subtle::Choice) {
+        use subtle::ConditionallySelectable;
+
+        let     src = other.borrow();
+        let mut dst = self.borrow_mut();
+
+        for (dst, src) in dst.as_mut_bytes().iter_mut().zip(src.as_bytes()) {
+            dst.conditional_assign(src, choice);
+        }
+    }
+
+    /// Mutably borrows the contents of the [`SecretBox`]. Has equivalent
+    /// semantics to [`borrow_mut`](SecretBox::borrow_mut), but refuses
+    /// to borrow a [`SecretBox`] that has been poisoned.
+    ///
+    /// Like a [`Mutex`][mutex], a [`SecretBox`] becomes poisoned when a
+    /// panic occurs while it is mutably borrowed. Such a panic may have
+    /// interrupted a partial write to its contents, so they should no
+    /// longer be trusted. Borrows taken while the thread is already
+    /// panicking, such as from a destructor, don't poison it.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// let mut secret = SecretBox::<u8>::zero();
+    ///
+    /// let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     let _s = secret.borrow_mut();
+    ///     panic!("interrupted while writing");
+    /// }));
+    ///
+    /// assert!(secret.try_borrow_mut().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::Poisoned`] if a panic previously occurred
+    /// while the [`SecretBox`] was mutably borrowed. Since a mutable
+    /// borrow is exclusive, it can never fail with
+    /// [`SecretError::TooManyBorrows`].
+    ///
+    /// [mutex]: std::sync::Mutex
+    pub fn try_borrow_mut(&mut self) -> Result<RefMut<'_, T>, SecretError> {
+        if self.is_poisoned() {
+            return Err(SecretError::Poisoned);
+        }
+
+        Ok(self.borrow_mut())
+    }
+
+    /// Returns true if a panic occurred while the [`SecretBox`] was
+    /// mutably borrowed.
+    pub fn is_poisoned(&self) -> bool {
+        self.boxed.is_poisoned()
+    }
+
+    /// Consumes the [`SecretBox`], reinterpreting its protected memory as
+    /// a [`SecretVec`] of elements of type `U`, without copying it.
+    ///
+    /// The resulting [`SecretVec`] holds `T::size() / U::size()`
+    /// elements. Returns [`None`] if the size of `T` is not a whole
+    /// multiple of the size of `U`, or if the memory isn't suitably
+    /// aligned for `U`; in that case, the [`SecretBox`] is dropped and
+    /// its contents zeroed as usual.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u8; 32]>::random();
+    /// let words  = secret.reinterpret_vec::<u32>().unwrap();
+    ///
+    /// assert_eq!(words.len(), 8);
+    /// ```
+    pub fn reinterpret_vec<U: Bytes>(self) -> Option<SecretVec<U>> {
+        self.boxed.reinterpret().map(SecretVec::from_boxed)
+    }
+
+    /// Returns true if the SHA-256 digest of the contents of the
+    /// [`SecretBox`] is `expected`, comparing them in constant time. The
+    /// digest is computed into a scratch buffer that is zeroed
+    /// afterward, while `expected` is assumed not to be secret.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// const DIGEST: [u8; 32] = [
+    ///     0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    ///     0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    /// ];
+    ///
+    /// let secret = SecretBox::<[u8; 3]>::from(&mut b"abc".to_owned());
+    ///
+    /// assert!(secret.matches_digest_ct(&DIGEST));
+    /// ```
+    pub fn matches_digest_ct(&self, expected: &[u8; SHA256_BYTES]) -> bool {
+        sodium::sha256_verify((*self.borrow()).as_bytes(), expected)
+    }
+
+    /// Clones the [`SecretBox`] into a new protected allocation, returning
+    /// an error rather than panicking if the memory can't be allocated
+    /// or protected. This is useful for callers that can't tolerate a
+    /// panic when cloning under memory pressure.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::Allocation`] if libsodium can't be
+    /// initialized, if the new memory can't be allocated, or if the
+    /// protection level of either allocation can't be changed. Returns
+    /// [`SecretError::MemlockBudgetExceeded`] if locking the new memory
+    /// would exceed the process's limit on locked memory, and
+    /// [`SecretError::Mlock`] if it still can't be locked after every
+    /// retry has been exhausted.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretBox, SecretError};
+    /// let secret = SecretBox::<[u8; 32]>::random();
+    /// let copy   = secret.try_clone()?;
+    ///
+    /// assert_eq!(secret, copy);
+    /// # Ok::<(), SecretError>(())
+    /// ```
+    pub fn try_clone(&self) -> Result<Self, SecretError> {
+        Ok(Self {
+            boxed: self.boxed.try_clone()?,
+        })
+    }
+
+    /// Converts the [`SecretBox`] into a [`SealedBox`], which panics
+    /// rather than duplicating its contents if it is ever [`Clone`]d.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let sealed = SecretBox::<u32>::from(&mut 0xcafe).sealed();
+    ///
+    /// assert_eq!(*sealed.borrow(), 0xcafe);
+    /// ```
+    pub fn sealed(self) -> SealedBox<T> {
+        SealedBox::new(self)
+    }
+}
+
+impl<T: Bytes, const N: usize> SecretBox<[T; N]> {
+    /// Immutably borrows the element at `index` of the array, returning
+    /// [`None`] if it is out of bounds. Returns a wrapper that ensures
+    /// the underlying memory is [`mprotect(2)`][mprotect]ed once all
+    /// borrows exit scope.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u16; 3]>::from(&mut [1, 2, 3]);
+    ///
+    /// assert_eq!(*secret.get(1).unwrap(), 2);
+    /// assert!(secret.get(3).is_none());
+    /// ```
+    ///
+    /// [mprotect]: http://man7.org/linux/man-pages/man2/mprotect.2.html
+    pub fn get(&self, index: usize) -> Option<ItemRef<'_, T, N>> {
+        if index >= N {
+            return None;
+        }
+
+        Some(ItemRef::new(&self.boxed, index))
+    }
+
+    /// Returns an iterator over immutable borrows of each element of the
+    /// array. Each element is borrowed as though through
+    /// [`get`](SecretBox::get).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u16; 3]>::from(&mut [1, 2, 3]);
+    ///
+    /// assert_eq!(secret.iter().map(|x| *x).sum::<u16>(), 6);
+    /// ```
+    pub fn iter(&self) -> IterRef<'_, T, N> {
+        IterRef {
+            secret: self,
+            index:  0,
+        }
+    }
 }
 
 impl<T: Bytes + Randomizable> SecretBox<T> {
@@ -262,6 +781,62 @@ impl<T: Bytes + Zeroable> SecretBox<T> {
             boxed: Box::zero(1),
         }
     }
+
+    /// Instantiates and returns a new [`SecretBox`]. Has equivalent
+    /// semantics to [`new`](SecretBox::new), but the value yielded to
+    /// the initialization callback is filled with zeroes instead of
+    /// garbage, so any bytes the callback doesn't write are left
+    /// zeroed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<[u8; 4]>::new_zeroed(|s| {
+    ///     s[..2].copy_from_slice(&[0xab, 0xcd]);
+    /// });
+    ///
+    /// assert_eq!(*secret.borrow(), [0xab, 0xcd, 0x00, 0x00]);
+    /// ```
+    pub fn new_zeroed<F>(f: F) -> Self
+    where
+        F: FnOnce(&mut T),
+    {
+        Self {
+            boxed: Box::new_zeroed(1, |b| f(b.as_mut())),
+        }
+    }
+}
+
+impl SecretBox<[u8; AUTH_KEYBYTES]> {
+    /// Computes a message authentication tag for `data`, using the
+    /// contents of the [`SecretBox`] as the key. The tag is computed
+    /// with libsodium's [`crypto_auth`][crypto_auth] (HMAC-SHA-512-256).
+    ///
+    /// The returned tag is not secret, and may be freely transmitted
+    /// alongside `data`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let key = SecretBox::<[u8; 32]>::random();
+    /// let tag = key.authenticate(b"attack at dawn");
+    ///
+    /// assert!( key.verify(&tag, b"attack at dawn"));
+    /// assert!(!key.verify(&tag, b"attack at dusk"));
+    /// ```
+    ///
+    /// [crypto_auth]: https://doc.libsodium.org/secret-key_cryptography/secret-key_authentication
+    pub fn authenticate(&self, data: &[u8]) -> [u8; AUTH_BYTES] {
+        sodium::auth(data, &self.borrow())
+    }
+
+    /// Verifies, in constant time, that `tag` is a valid authentication
+    /// tag for `data` under the key contained in the [`SecretBox`].
+    pub fn verify(&self, tag: &[u8; AUTH_BYTES], data: &[u8]) -> bool {
+        sodium::auth_verify(tag, data, &self.borrow())
+    }
 }
 
 impl<T: Bytes + Zeroable> From<&mut T> for SecretBox<T> {
@@ -272,9 +847,41 @@ impl<T: Bytes + Zeroable> From<&mut T> for SecretBox<T> {
     }
 }
 
-impl<T: Bytes> Debug for SecretBox<T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.boxed.fmt(f)
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Bytes> arbitrary::Arbitrary<'a> for SecretBox<T> {
+    /// Constructs a [`SecretBox`] from the next [`size`](Bytes::size)
+    /// bytes of the fuzzer's input, which are copied directly into
+    /// protected memory.
+    ///
+    /// This exists only for use in fuzzing harnesses, whose inputs are
+    /// not themselves protected.
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bytes = u.bytes(T::size())?;
+
+        Ok(Self::new(|s| s.as_mut_bytes().copy_from_slice(bytes)))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (T::size(), Some(T::size()))
+    }
+}
+
+#[cfg(feature = "constant-hash")]
+impl<T: Bytes> Hash for SecretBox<T> {
+    /// Hashes a fixed constant, rather than the contents of the
+    /// [`SecretBox`], so that every [`SecretBox`] has the same hash.
+    ///
+    /// This exists only to satisfy derived implementations of [`Hash`]
+    /// on types that contain secrets, and should not be relied upon to
+    /// distinguish one secret from another.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(0);
+    }
+}
+
+impl<T: Bytes> Debug for SecretBox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt(f)
     }
 }
 
@@ -290,23 +897,95 @@ impl<'a, T: Bytes> Ref<'a, T> {
         proven!(boxed.len() == 1,
             "secrets: attempted to dereference a box with zero length");
 
+        boxed.check_leaked_borrows(false);
+
         Self {
-            boxed: boxed.unlock(),
+            boxed:  boxed.unlock(),
+            paused: false,
         }
     }
+
+    /// Instantiates a new `Ref`, returning an error rather than
+    /// panicking if `boxed` is borrowed too many times.
+    fn try_new(boxed: &'a Box<T>) -> Result<Self, SecretError> {
+        proven!(boxed.len() == 1,
+            "secrets: attempted to dereference a box with zero length");
+
+        boxed.check_leaked_borrows(false);
+
+        Ok(Self {
+            boxed:  boxed.try_unlock()?,
+            paused: false,
+        })
+    }
+
+    /// Temporarily re-locks the memory underlying the borrow without
+    /// giving the borrow up, for instance across an `await` point where
+    /// the contents needn't be readable. The borrow must be
+    /// [`resume`](Ref::resume)d before it is dereferenced again. Has no
+    /// effect if the borrow is already paused.
+    ///
+    /// Note that the memory only becomes inaccessible if no other
+    /// borrows of the [`SecretBox`] are outstanding.
+    ///
+    /// # Panics
+    ///
+    /// Dereferencing the borrow while it is paused panics.
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+            self.paused = true;
+        }
+    }
+
+    /// Unlocks the memory underlying a borrow that was previously
+    /// [`pause`](Ref::pause)d, so that it may be dereferenced again.
+    /// Has no effect if the borrow isn't paused.
+    pub fn resume(&mut self) {
+        if self.paused {
+            let _ = self.boxed.unlock();
+            self.paused = false;
+        }
+    }
+
+    /// Returns the borrowed contents as a flat slice of bytes, e.g. for
+    /// passing a struct's `(ptr, len)` to a C API.
+    ///
+    /// The slice covers the full [`size`](SecretBox::size) of `T`,
+    /// including any padding between or after its fields, so its
+    /// layout depends on the representation of `T`. Only types with a
+    /// defined layout, such as those marked `#[repr(C)]`, should be
+    /// relied upon to produce a particular arrangement of bytes.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretBox;
+    /// let secret = SecretBox::<u32>::from(&mut 0x0102_0304);
+    ///
+    /// assert_eq!(secret.borrow().as_flat_bytes(), 0x0102_0304_u32.to_ne_bytes());
+    /// ```
+    pub fn as_flat_bytes(&self) -> &[u8] {
+        (**self).as_bytes()
+    }
 }
 
 impl<T: Bytes> Clone for Ref<'_, T> {
+    /// Clones the borrow. The clone is never paused, even if the
+    /// original is.
     fn clone(&self) -> Self {
         Self {
-            boxed: self.boxed.unlock(),
+            boxed:  self.boxed.unlock(),
+            paused: false,
         }
     }
 }
 
 impl<T: Bytes> Drop for Ref<'_, T> {
     fn drop(&mut self) {
-        self.boxed.lock();
+        if !self.paused {
+            self.boxed.lock();
+        }
     }
 }
 
@@ -314,6 +993,9 @@ impl<T: Bytes> Deref for Ref<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
         self.boxed.as_ref()
     }
 }
@@ -344,6 +1026,15 @@ impl<T: Bytes> PartialEq<RefMut<'_, T>> for Ref<'_, T> {
     }
 }
 
+impl<T: Bytes> PartialEq<secret::RefMut<'_, T>> for Ref<'_, T> {
+    /// Compares the [`SecretBox`] against a stack-allocated
+    /// [`Secret`](crate::Secret) in constant time, without copying
+    /// either of them.
+    fn eq(&self, rhs: &secret::RefMut<'_, T>) -> bool {
+        (**self).constant_eq(&**rhs)
+    }
+}
+
 impl<T: Bytes> Eq for Ref<'_, T> {}
 
 impl<'a, T: Bytes> RefMut<'a, T> {
@@ -352,15 +1043,48 @@ impl<'a, T: Bytes> RefMut<'a, T> {
         proven!(boxed.len() == 1,
             "secrets: attempted to dereference a box with zero length");
 
+        boxed.check_leaked_borrows(true);
+
         Self {
-            boxed: boxed.unlock_mut(),
+            boxed:     boxed.unlock_mut(),
+            paused:    false,
+            panicking: thread::panicking(),
+        }
+    }
+
+    /// Temporarily re-locks the memory underlying the borrow without
+    /// giving the borrow up. Has equivalent semantics to
+    /// [`Ref::pause`].
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+            self.paused = true;
+        }
+    }
+
+    /// Unlocks the memory underlying a borrow that was previously
+    /// [`pause`](RefMut::pause)d. Has equivalent semantics to
+    /// [`Ref::resume`].
+    pub fn resume(&mut self) {
+        if self.paused {
+            let _ = self.boxed.unlock_mut();
+            self.paused = false;
         }
     }
 }
 
 impl<T: Bytes> Drop for RefMut<'_, T> {
     fn drop(&mut self) {
-        self.boxed.lock();
+        // a panic while we hold the only writable reference may have
+        // left the contents partially written, but a borrow taken while
+        // already unwinding (e.g., by a destructor) was never interrupted
+        if thread::panicking() && !self.panicking {
+            self.boxed.poison();
+        }
+
+        if !self.paused {
+            self.boxed.lock();
+        }
     }
 }
 
@@ -368,12 +1092,18 @@ impl<T: Bytes> Deref for RefMut<'_, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
         self.boxed.as_ref()
     }
 }
 
 impl<T: Bytes> DerefMut for RefMut<'_, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
         self.boxed.as_mut()
     }
 }
@@ -406,11 +1136,81 @@ impl<T: Bytes> PartialEq<Ref<'_, T>> for RefMut<'_, T> {
 
 impl<T: Bytes> Eq for RefMut<'_, T> {}
 
+impl<'a, T: Bytes, const N: usize> ItemRef<'a, T, N> {
+    /// Instantiates a new `ItemRef`.
+    fn new(boxed: &'a Box<[T; N]>, index: usize) -> Self {
+        proven!(index < N,
+            "secrets: attempted to borrow an out-of-bounds element");
+
+        boxed.check_leaked_borrows(false);
+
+        Self {
+            boxed: boxed.unlock(),
+            index,
+        }
+    }
+}
+
+impl<T: Bytes, const N: usize> Clone for ItemRef<'_, T, N> {
+    fn clone(&self) -> Self {
+        Self::new(self.boxed, self.index)
+    }
+}
+
+impl<T: Bytes, const N: usize> Drop for ItemRef<'_, T, N> {
+    fn drop(&mut self) {
+        self.boxed.lock();
+    }
+}
+
+impl<T: Bytes, const N: usize> Deref for ItemRef<'_, T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.boxed.as_ref()[self.index]
+    }
+}
+
+impl<T: Bytes, const N: usize> Debug for ItemRef<'_, T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt_redacted(f, T::size())
+    }
+}
+
+impl<T: Bytes, const N: usize> PartialEq for ItemRef<'_, T, N> {
+    fn eq(&self, rhs: &Self) -> bool {
+        (**self).constant_eq(&**rhs)
+    }
+}
+
+impl<T: Bytes, const N: usize> Eq for ItemRef<'_, T, N> {}
+
+impl<'a, T: Bytes, const N: usize> Iterator for IterRef<'a, T, N> {
+    type Item = ItemRef<'a, T, N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.secret.get(self.index)?;
+
+        self.index += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = N - self.index;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Bytes, const N: usize> ExactSizeIterator for IterRef<'_, T, N> {}
+
 // LCOV_EXCL_START
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::panic::{self, AssertUnwindSafe};
 
     #[test]
     fn it_allows_custom_initialization() {
@@ -421,11 +1221,46 @@ mod test {
         });
     }
 
+    #[test]
+    fn it_returns_values_from_initialization() {
+        let (secret, len) = SecretBox::<[u8; 8]>::new_with(|s| {
+            s.copy_from_slice(b"abc\0\0\0\0\0");
+            s.iter().take_while(|b| **b != 0).count()
+        });
+
+        assert_eq!(len, 3);
+        assert_eq!(*secret.borrow(), *b"abc\0\0\0\0\0");
+    }
+
     #[test]
     fn it_allows_failing_initialization() {
         assert!(SecretBox::<u8>::try_new(|_| Ok::<(), ()>(())).is_ok());
     }
 
+    #[test]
+    fn it_allows_initialization_through_raw_pointers() {
+        let secret = SecretBox::<[u16; 2]>::new_uninit(|ptr| {
+            #[allow(unsafe_code)]
+            unsafe {
+                assert_eq!(ptr.read(), [0, 0]);
+                ptr.cast::<u16>().add(1).write(0xbeef);
+            }
+
+            true
+        });
+
+        assert_eq!(*secret.expect("failed to initialize").borrow(), [0, 0xbeef]);
+    }
+
+    #[test]
+    fn it_allows_failing_initialization_through_raw_pointers() {
+        let before = crate::stats::thread_locked_bytes();
+        let secret = SecretBox::<u64>::new_uninit(|_| false);
+
+        assert!(secret.is_none());
+        assert_eq!(crate::stats::thread_locked_bytes(), before);
+    }
+
     #[test]
     fn it_allows_borrowing_immutably() {
         let secret = SecretBox::<u64>::zero();
@@ -434,6 +1269,28 @@ mod test {
         assert_eq!(*s, 0);
     }
 
+    #[test]
+    fn it_coalesces_simultaneous_immutable_borrows() {
+        let secret = SecretBox::<u64>::random();
+        let before = crate::boxed::readonly_mprotects();
+
+        let secret_r1 = secret.borrow();
+        let secret_r2 = secret.borrow();
+        let secret_r3 = secret_r1.clone();
+
+        assert_eq!(secret.boxed.refs(),                         3);
+        assert_eq!(crate::boxed::readonly_mprotects() - before, 1);
+
+        drop(secret_r1);
+        drop(secret_r2);
+
+        assert_eq!(secret.boxed.refs(), 1);
+
+        drop(secret_r3);
+
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
     #[test]
     fn it_allows_borrowing_mutably() {
         let mut secret = SecretBox::<u64>::zero();
@@ -444,6 +1301,60 @@ mod test {
         assert_eq!(*s, 0x01ab_cdef);
     }
 
+    #[test]
+    fn it_borrows_structs_as_flat_bytes() {
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        struct Key {
+            id:    u32,
+            bytes: [u8; 16],
+        }
+
+        #[allow(unsafe_code)]
+        unsafe impl Bytes for Key {}
+
+        let secret = SecretBox::<Key>::new(|k| {
+            k.id    = 0x0bad_cafe;
+            k.bytes = [0x5a; 16];
+        });
+
+        let s     = secret.borrow();
+        let bytes = s.as_flat_bytes();
+
+        assert_eq!(bytes.len(), secret.size());
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(bytes[..4],  0x0bad_cafe_u32.to_ne_bytes());
+        assert_eq!(bytes[4..],  [0x5a; 16]);
+    }
+
+    #[test]
+    fn it_replaces_contents() {
+        let mut key = SecretBox::<u64>::from(&mut 0x0bad_cafe);
+        let     new = SecretBox::<u64>::from(&mut 0xdead_beef);
+        let     old = key.replace(new);
+
+        assert_eq!(*old.borrow(), 0x0bad_cafe);
+        assert_eq!(*key.borrow(), 0xdead_beef);
+    }
+
+    #[test]
+    fn it_allows_updating_in_place() {
+        let mut key = SecretBox::<[u8; 8]>::from(&mut [7; 8]);
+
+        let old = key.update(|k| {
+            let old = k[0];
+
+            for (i, b) in k.iter_mut().enumerate() {
+                *b = b.wrapping_mul(31).wrapping_add(i as u8);
+            }
+
+            old
+        });
+
+        assert_eq!(old,           7);
+        assert_eq!(*key.borrow(), [217, 218, 219, 220, 221, 222, 223, 224]);
+    }
+
     #[test]
     fn it_allows_storing_fixed_size_arrays() {
         let secret = SecretBox::<[u8; 2]>::new(|s| {
@@ -459,6 +1370,87 @@ mod test {
         assert_eq!(secret.size(), 64);
     }
 
+    #[test]
+    fn it_provides_its_element_size() {
+        assert_eq!(SecretBox::<u64>::element_size(), 8);
+        assert_eq!(SecretBox::<u64>::element_size(), SecretBox::<u64>::zero().size());
+    }
+
+    #[test]
+    fn it_provides_its_size_at_compile_time() {
+        let secret = SecretBox::<[u16; 3]>::zero();
+        let buf    = [0_u8; SecretBox::<[u16; 3]>::BYTE_SIZE];
+
+        assert_eq!(buf.len(), 6);
+        assert_eq!(buf.len(), secret.size());
+    }
+
+    #[test]
+    fn it_allows_borrowing_array_elements() {
+        let secret = SecretBox::<[u8; 4]>::from(&mut [4, 5, 6, 7]);
+
+        assert_eq!(*secret.get(0).expect("missing element"), 4);
+        assert_eq!(*secret.get(3).expect("missing element"), 7);
+        assert!(secret.get(4).is_none());
+    }
+
+    #[test]
+    fn it_iterates_over_array_elements() {
+        let     secret = SecretBox::<[u8; 4]>::from(&mut [4, 5, 6, 7]);
+        let mut iter   = secret.iter();
+
+        assert_eq!(iter.len(),              4);
+        assert_eq!(iter.next().map(|x| *x), Some(4));
+        assert_eq!(iter.len(),              3);
+        assert_eq!(iter.map(|x| *x).collect::<Vec<_>>(), [5, 6, 7]);
+    }
+
+    #[test]
+    fn it_preserves_secrecy_of_array_elements() {
+        let secret = SecretBox::<[u32; 2]>::random();
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 4),
+            format!("{:?}", secret.get(1).expect("missing element")),
+        );
+    }
+
+    #[test]
+    fn it_authenticates_data() {
+        let key = SecretBox::<[u8; 32]>::random();
+        let tag = key.authenticate(b"some data");
+
+        assert!(key.verify(&tag, b"some data"));
+    }
+
+    #[test]
+    fn it_rejects_tampered_tags() {
+        let     key = SecretBox::<[u8; 32]>::random();
+        let mut tag = key.authenticate(b"some data");
+
+        tag[0] ^= 1;
+
+        assert!(!key.verify(&tag, b"some data"));
+    }
+
+    #[test]
+    fn it_rejects_tags_from_other_keys() {
+        let key_1 = SecretBox::<[u8; 32]>::random();
+        let key_2 = SecretBox::<[u8; 32]>::random();
+        let tag   = key_1.authenticate(b"some data");
+
+        assert!(!key_2.verify(&tag, b"some data"));
+    }
+
+    #[test]
+    fn it_matches_digests() {
+        let secret = SecretBox::<[u8; 3]>::from(&mut b"abc".to_owned());
+        let other  = SecretBox::<[u8; 3]>::from(&mut b"abd".to_owned());
+
+        assert!( secret.matches_digest_ct(&sodium::SHA256_ABC));
+        assert!(!other.matches_digest_ct(&sodium::SHA256_ABC));
+    }
+
     #[test]
     fn it_preserves_secrecy() {
         let mut secret = SecretBox::<u64>::random();
@@ -479,6 +1471,230 @@ mod test {
         );
     }
 
+    #[test]
+    fn it_hides_sizes_in_debug_output_when_configured() {
+        let mut secret = SecretBox::<[u8; 16]>::zero();
+
+        assert_eq!(format!("{:?}", secret),              "{ 16 bytes redacted }");
+        assert_eq!(format!("{:?}", secret.borrow()),     "{ 16 bytes redacted }");
+
+        secret = secret.debug_size(false);
+
+        assert_eq!(format!("{:?}", secret),              "{ redacted }");
+        assert_eq!(format!("{:?}", secret.clone()),      "{ redacted }");
+        assert_eq!(format!("{:?}", secret.borrow()),     "{ redacted }");
+        assert_eq!(format!("{:?}", secret.borrow_mut()), "{ redacted }");
+
+        secret = secret.debug_size(true);
+
+        assert_eq!(format!("{:?}", secret),              "{ 16 bytes redacted }");
+    }
+
+    #[test]
+    #[cfg(feature = "subtle")]
+    fn it_assigns_conditionally() {
+        use subtle::Choice;
+
+        let mut secret = SecretBox::<[u16; 2]>::from(&mut [1, 2]);
+        let     other  = SecretBox::<[u16; 2]>::from(&mut [3, 4]);
+
+        secret.conditional_assign(&other, Choice::from(0));
+
+        assert_eq!(*secret.borrow(), [1, 2]);
+
+        secret.conditional_assign(&other, Choice::from(1));
+
+        assert_eq!(*secret.borrow(), [3, 4]);
+        assert_eq!(*other.borrow(),  [3, 4]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "test-deterministic", target_family = "unix"))]
+    fn it_randomizes_deterministically_when_seeded() {
+        sodium::with_random_seed([0; 32], || {
+            let secret_1 = SecretBox::<[u8; 8]>::random();
+            let secret_2 = SecretBox::<[u8; 8]>::random();
+
+            crate::set_random_seed(None);
+
+            let secret_3 = SecretBox::<[u8; 8]>::random();
+
+            assert_eq!(*secret_1.borrow(), [0xa1, 0x1f, 0x8f, 0x12, 0xd0, 0x87, 0x6f, 0x73]);
+            assert_eq!(*secret_2.borrow(), [0x2f, 0xe3, 0xd4, 0x8c, 0x19, 0x6f, 0xf1, 0xd9]);
+            assert_ne!(*secret_3.borrow(), *secret_2.borrow());
+        });
+    }
+
+    #[test]
+    #[cfg(all(feature = "test-deterministic", target_family = "unix"))]
+    #[should_panic(expected = "secrets: seeded test failed in its child process")]
+    fn it_reports_seeded_failures() {
+        sodium::with_random_seed([0; 32], || {
+            assert_eq!(*SecretBox::<[u8; 8]>::random().borrow(), [0; 8]);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn it_logs_the_reason_for_exposure() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+        impl Visit for Recorder {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0.lock().unwrap().push((field.name().into(), format!("{:?}", value)));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.lock().unwrap().push((field.name().into(), value.into()));
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+            fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                assert_eq!(event.metadata().target(), "secrets::audit");
+
+                event.record(&mut Recorder(Arc::clone(&self.0)));
+            }
+        }
+
+        let fields   = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Recorder(Arc::clone(&fields));
+        let secret   = SecretBox::<u32>::from(&mut 0x0bad_cafe);
+
+        let exposed = tracing::subscriber::with_default(recorder, || {
+            secret.expose_for("signing a message", |s| *s)
+        });
+
+        let fields = fields.lock().unwrap();
+        let names  = fields.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(exposed,     0x0bad_cafe);
+        assert_eq!(names,       ["message", "reason", "elapsed"]);
+        assert_eq!(fields[0].1, "secret exposed");
+        assert_eq!(fields[1].1, "signing a message");
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn it_is_constructed_arbitrarily() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut input = Unstructured::new(&[1, 2, 3, 4, 5]);
+        let     secret = SecretBox::<[u8; 4]>::arbitrary(&mut input).unwrap();
+
+        assert_eq!(*secret.borrow(), [1, 2, 3, 4]);
+        assert_eq!(input.len(),      1);
+
+        assert!(SecretBox::<[u8; 4]>::arbitrary(&mut input).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "constant-hash")]
+    fn it_hashes_to_a_constant() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let secret_1 = SecretBox::<u64>::from(&mut 1);
+        let secret_2 = SecretBox::<u64>::from(&mut 2);
+
+        let mut hasher_1 = DefaultHasher::new();
+        let mut hasher_2 = DefaultHasher::new();
+
+        secret_1.hash(&mut hasher_1);
+        secret_2.hash(&mut hasher_2);
+
+        assert_eq!(hasher_1.finish(), hasher_2.finish());
+        assert_ne!(secret_1,          secret_2);
+    }
+
+    #[test]
+    fn it_reinterprets_as_a_vec() {
+        let mut bytes = [0_u8; 32];
+
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+
+        let expected = bytes.chunks(4)
+            .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+            .collect::<Vec<_>>();
+
+        let secret = SecretBox::<[u8; 32]>::from(&mut bytes);
+        let words  = secret.reinterpret_vec::<u32>().unwrap();
+
+        assert_eq!(words.len(), 8);
+
+        for (word, expected) in words.iter().zip(expected) {
+            assert_eq!(*word, expected);
+        }
+    }
+
+    #[test]
+    fn it_doesnt_reinterpret_as_a_vec_of_indivisible_elements() {
+        let secret = SecretBox::<[u8; 6]>::zero();
+
+        assert!(secret.reinterpret_vec::<u32>().is_none());
+    }
+
+    #[test]
+    fn it_pauses_and_resumes_immutable_borrows() {
+        let     secret = SecretBox::<u32>::from(&mut 0x0bad_cafe);
+        let mut s      = secret.borrow();
+
+        s.pause();
+        assert_eq!(secret.boxed.refs(), 0);
+
+        s.resume();
+        assert_eq!(secret.boxed.refs(), 1);
+        assert_eq!(*s, 0x0bad_cafe);
+
+        drop(s);
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_pauses_and_resumes_mutable_borrows() {
+        let mut secret = SecretBox::<u32>::zero();
+        let mut s      = secret.borrow_mut();
+
+        s.pause();
+        s.pause();
+        assert_eq!(s.boxed.refs(), 0);
+
+        s.resume();
+        s.resume();
+        assert_eq!(s.boxed.refs(), 1);
+
+        *s = 4;
+
+        drop(s);
+        assert_eq!(secret.boxed.refs(), 0);
+        assert_eq!(*secret.borrow(),    4);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: attempted to dereference a paused borrow")]
+    fn it_doesnt_allow_dereferencing_paused_borrows() {
+        let     secret = SecretBox::<u32>::from(&mut 0x0bad_cafe);
+        let mut s      = secret.borrow();
+
+        s.pause();
+
+        let _ = *s;
+    }
+
     #[test]
     fn it_moves_safely() {
         let secret_1 = SecretBox::<u8>::zero();
@@ -487,6 +1703,23 @@ mod test {
         assert_eq!(*secret_2.borrow(), 0);
     }
 
+    #[test]
+    fn it_clones_fallibly() {
+        let secret = SecretBox::<u8>::random();
+        let copy   = secret.try_clone();
+
+        assert_eq!(copy.expect("failed to clone"), secret);
+    }
+
+    #[test]
+    fn it_reports_failures_when_cloning_fallibly() {
+        let secret = SecretBox::<u8>::random();
+
+        sodium::fail();
+
+        assert_eq!(secret.try_clone().err(), Some(SecretError::Allocation));
+    }
+
     #[test]
     fn it_safely_clones_immutable_references() {
         let secret   = SecretBox::<u8>::random();
@@ -512,6 +1745,22 @@ mod test {
         assert_ne!(secret_1, secret_2);
     }
 
+    #[test]
+    fn it_never_short_circuits_comparisons() {
+        let secret = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 4]);
+
+        // every comparison must reach the constant-time primitive,
+        // regardless of where (or whether) the contents differ
+        for other in &mut [[1, 2, 3, 4], [0, 2, 3, 4], [1, 2, 3, 0]] {
+            let other  = SecretBox::from(other);
+            let before = sodium::memcmps();
+
+            let _ = secret == other;
+
+            assert_eq!(sodium::memcmps(), before + 1);
+        }
+    }
+
     #[test]
     fn it_compares_equality_immutably_on_refs() {
         let secret_1 = SecretBox::<u8>::from(&mut 0xaf);
@@ -536,6 +1785,111 @@ mod test {
         assert_eq!(secret_1.borrow_mut(), secret_2.borrow());
         assert_eq!(secret_2.borrow_mut(), secret_1.borrow());
     }
+
+    #[test]
+    fn it_compares_equality_with_stack_secrets() {
+        let secret_1 = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 4]);
+        let secret_2 = SecretBox::<[u8; 4]>::from(&mut [1, 2, 3, 5]);
+
+        crate::Secret::<[u8; 4]>::from(&mut [1, 2, 3, 4], |s| {
+            assert_eq!(secret_1.borrow(), s);
+            assert_ne!(secret_2.borrow(), s);
+        });
+    }
+
+    #[test]
+    fn it_allows_fallibly_borrowing_mutably() {
+        let mut secret = SecretBox::<u64>::zero();
+
+        assert!(!secret.is_poisoned());
+        assert!(secret.try_borrow_mut().is_ok());
+    }
+
+    #[test]
+    fn it_refuses_to_overflow_borrows_fallibly() {
+        let secret  = SecretBox::<u64>::zero();
+        let borrows = (0..255).map(|_| secret.borrow()).collect::<Vec<_>>();
+
+        secret.boxed.skip_refs(true);
+
+        assert_eq!(secret.try_borrow().err(), Some(SecretError::TooManyBorrows));
+
+        secret.boxed.skip_refs(false);
+
+        drop(borrows);
+
+        assert_eq!(*secret.try_borrow().unwrap(), 0);
+    }
+
+    #[test]
+    fn it_poisons_when_panicking_while_mutably_borrowed() {
+        let mut secret = SecretBox::<u64>::zero();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut s = secret.borrow_mut();
+            *s = 0x1234;
+
+            panic!("interrupted while writing");
+        }));
+
+        assert!(result.is_err());
+        assert!(secret.is_poisoned());
+        assert_eq!(secret.try_borrow_mut().err(), Some(SecretError::Poisoned));
+    }
+
+    #[test]
+    fn it_poisons_when_panicking_inside_updates() {
+        let mut secret = SecretBox::<u64>::zero();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            secret.update(|s| {
+                *s = 0x1234;
+
+                panic!("interrupted while updating");
+            })
+        }));
+
+        assert!(result.is_err());
+        assert!(secret.is_poisoned());
+        assert_eq!(secret.try_borrow_mut().err(), Some(SecretError::Poisoned));
+    }
+
+    #[test]
+    fn it_doesnt_poison_when_mutably_borrowed_while_already_panicking() {
+        struct WriteOnDrop<'a>(&'a mut SecretBox<u64>);
+
+        impl Drop for WriteOnDrop<'_> {
+            fn drop(&mut self) {
+                *self.0.borrow_mut() = 0x1234;
+            }
+        }
+
+        let mut secret = SecretBox::<u64>::zero();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = WriteOnDrop(&mut secret);
+
+            panic!("interrupted before writing");
+        }));
+
+        assert!(result.is_err());
+        assert!(!secret.is_poisoned());
+        assert_eq!(*secret.borrow(), 0x1234);
+    }
+
+    #[test]
+    fn it_doesnt_poison_when_panicking_while_immutably_borrowed() {
+        let secret = SecretBox::<u64>::zero();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _s = secret.borrow();
+
+            panic!("interrupted while reading");
+        }));
+
+        assert!(result.is_err());
+        assert!(!secret.is_poisoned());
+    }
 }
 
 #[cfg(all(test, profile = "debug"))]
@@ -557,6 +1911,28 @@ mod tests_proven_statements {
         let mut boxed = Box::<u8>::zero(0);
         let     _     = boxed.as_mut();
     }
+
+    #[test]
+    #[allow(clippy::mem_forget)]
+    #[should_panic(expected = "secrets: a borrow was leaked (e.g., with mem::forget) and left the secret unlocked")]
+    fn it_detects_leaked_immutable_borrows() {
+        let mut secret = SecretBox::<u8>::zero();
+
+        mem::forget(secret.borrow());
+
+        let _ = secret.borrow_mut();
+    }
+
+    #[test]
+    #[allow(clippy::mem_forget)]
+    #[should_panic(expected = "secrets: a borrow was leaked (e.g., with mem::forget) and left the secret unlocked")]
+    fn it_detects_leaked_mutable_borrows() {
+        let mut secret = SecretBox::<u8>::zero();
+
+        mem::forget(secret.borrow_mut());
+
+        let _ = secret.borrow();
+    }
 }
 
 // LCOV_EXCL_STOP
@@ -0,0 +1,232 @@
+use crate::error::SecretError;
+use crate::secret_vec::{self, SecretVec};
+use crate::traits::*;
+
+use std::fmt::{self, Debug, Formatter};
+use std::ops::Deref;
+use std::str;
+
+/// A [`SecretVec`] of bytes that are guaranteed to be valid UTF-8, for
+/// holding secrets like passwords and tokens.
+///
+/// A [`SecretString`] provides the same protections as the
+/// [`SecretVec`] it wraps, but its borrows [`Deref`] to a [`str`]
+/// rather than a byte slice. As with the other wrappers in this crate,
+/// its contents are never printed by [`Debug`], and two instances are
+/// only ever compared in constant time.
+///
+/// # Example: moving a [`String`] into protected memory
+///
+/// ```
+/// # use secrets::SecretString;
+/// let password = String::from("correct horse battery staple");
+/// let secret   = SecretString::from_string(password);
+///
+/// assert_eq!(&*secret.borrow(), "correct horse battery staple");
+/// ```
+#[derive(Clone, Eq)]
+pub struct SecretString {
+    /// The UTF-8 encoded contents of the [`SecretString`].
+    secret: SecretVec<u8>,
+}
+
+/// An immutable wrapper around the contents of a [`SecretString`]. This
+/// wrapper [`Deref`]s to a [`str`] for convenience.
+///
+/// When this wrapper is dropped, it ensures that the underlying memory
+/// is re-locked.
+pub struct Ref<'a> {
+    /// the borrowed bytes, which are known to be valid UTF-8
+    bytes: secret_vec::Ref<'a, u8>,
+}
+
+impl SecretString {
+    /// Creates a new [`SecretString`] from an existing [`String`],
+    /// zeroing out the memory of the [`String`] as it is moved in.
+    ///
+    /// The entire capacity of the [`String`]'s buffer is zeroed before
+    /// it is freed, not just its current contents, since the spare
+    /// capacity may still hold data that was truncated or removed from
+    /// it. Any copies the buffer left behind when it was previously
+    /// reallocated are unaffected.
+    pub fn from_string(string: String) -> Self {
+        Self::from_bytes(&mut string.into_bytes())
+    }
+
+    /// Instantiates and returns a new [`SecretString`] of `len` bytes,
+    /// which are initialized by the callback. Returns an error if the
+    /// callback leaves the bytes holding anything other than valid
+    /// UTF-8.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretError, SecretString};
+    /// let secret  = SecretString::try_new(2, |s| s.copy_from_slice(b"hi"));
+    /// let invalid = SecretString::try_new(1, |s| s[0] = 0xff);
+    ///
+    /// assert_eq!(&*secret.unwrap().borrow(), "hi");
+    /// assert_eq!(invalid,                    Err(SecretError::InvalidEncoding));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::InvalidEncoding`] if the initialized
+    /// bytes are not valid UTF-8. Note that the time taken to validate
+    /// the bytes depends on their contents.
+    pub fn try_new<F>(len: usize, f: F) -> Result<Self, SecretError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let secret = SecretVec::new(len, f);
+
+        if str::from_utf8(&secret.borrow()).is_err() {
+            return Err(SecretError::InvalidEncoding);
+        }
+
+        Ok(Self { secret })
+    }
+
+    /// Wraps `secret` in a [`SecretString`] without validating it.
+    ///
+    /// # Safety
+    ///
+    /// The contents of `secret` must be valid UTF-8.
+    #[allow(unsafe_code)]
+    pub(crate) unsafe fn from_utf8_unchecked(secret: SecretVec<u8>) -> Self {
+        Self { secret }
+    }
+
+    /// Moves `bytes` into a new [`SecretString`], zeroing the entire
+    /// capacity of the buffer. The bytes must be valid UTF-8.
+    fn from_bytes(bytes: &mut Vec<u8>) -> Self {
+        let len = bytes.len();
+
+        // this can't reallocate, and makes the spare capacity
+        // accessible so that it can be zeroed
+        bytes.resize(bytes.capacity(), 0);
+
+        let secret = SecretVec::from(&mut bytes[..len]);
+
+        bytes[len..].zero();
+
+        Self { secret }
+    }
+
+    /// Returns the length in bytes of the [`SecretString`].
+    pub fn len(&self) -> usize {
+        self.secret.len()
+    }
+
+    /// Returns true if length of the [`SecretString`] is zero.
+    pub fn is_empty(&self) -> bool {
+        self.secret.is_empty()
+    }
+
+    /// Immutably borrows the contents of the [`SecretString`]. Has
+    /// equivalent semantics to [`SecretVec::borrow`].
+    pub fn borrow(&self) -> Ref<'_> {
+        Ref {
+            bytes: self.secret.borrow(),
+        }
+    }
+}
+
+impl Debug for SecretString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.secret.fmt(f)
+    }
+}
+
+impl PartialEq for SecretString {
+    /// Compares the [`SecretString`] against another in constant time.
+    fn eq(&self, rhs: &Self) -> bool {
+        self.secret == rhs.secret
+    }
+}
+
+impl Deref for Ref<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        // the contents of a `SecretString` are validated as UTF-8 when
+        // it is created, and can't be modified afterward
+        #[allow(unsafe_code)]
+        unsafe {
+            str::from_utf8_unchecked(&self.bytes)
+        }
+    }
+}
+
+impl Debug for Ref<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.bytes.fmt(f)
+    }
+}
+
+impl PartialEq for Ref<'_> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.bytes.constant_eq(&rhs.bytes)
+    }
+}
+
+impl Eq for Ref<'_> {}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_moves_strings_in() {
+        let secret = SecretString::from_string(String::from("hunter2"));
+
+        assert_eq!(&*secret.borrow(), "hunter2");
+        assert_eq!(secret.len(),      7);
+    }
+
+    #[test]
+    fn it_zeroes_the_entire_capacity_of_moved_strings() {
+        let mut string = String::from("hunter2, but longer");
+
+        string.truncate(7);
+
+        let mut bytes  = string.into_bytes();
+        let     secret = SecretString::from_bytes(&mut bytes);
+
+        assert_eq!(&*secret.borrow(), "hunter2");
+        assert_eq!(bytes.len(),       bytes.capacity());
+        assert!(bytes.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn it_validates_utf8() {
+        let secret  = SecretString::try_new(4, |s| s.copy_from_slice("🔑".as_bytes()));
+        let invalid = SecretString::try_new(2, |s| s.copy_from_slice(&[0xc3, 0x28]));
+
+        assert_eq!(&*secret.unwrap().borrow(), "🔑");
+        assert_eq!(invalid,                    Err(SecretError::InvalidEncoding));
+    }
+
+    #[test]
+    fn it_compares_equality() {
+        let secret_1 = SecretString::from_string(String::from("token"));
+        let secret_2 = secret_1.clone();
+        let secret_3 = SecretString::from_string(String::from("tokes"));
+
+        assert_eq!(secret_1,          secret_2);
+        assert_ne!(secret_1,          secret_3);
+        assert_eq!(secret_1.borrow(), secret_2.borrow());
+    }
+
+    #[test]
+    fn it_preserves_secrecy() {
+        let secret = SecretString::from_string(String::from("hunter2"));
+
+        assert_eq!(format!("{:?}", secret),          "{ 7 bytes redacted }");
+        assert_eq!(format!("{:?}", secret.borrow()), "{ 7 bytes redacted }");
+    }
+}
+
+// LCOV_EXCL_STOP
@@ -1,8 +1,24 @@
+use crate::audit::Exposure;
 use crate::boxed::Box;
+use crate::error::SecretError;
+use crate::ffi::sodium::{self, SHA256_BYTES};
+use crate::secret;
+use crate::secret_box::SecretBox;
+use crate::secret_string::SecretString;
+use crate::shared_secret_vec::SharedSecretVec;
 use crate::traits::*;
 
+use std::borrow;
+use std::convert::{Infallible, TryFrom};
 use std::fmt::{self, Debug, Formatter};
-use std::ops::{Deref, DerefMut};
+#[cfg(feature = "constant-hash")]
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, Read, Write};
+use std::mem::{self, size_of};
+use std::ops::{Bound, Deref, DerefMut, Range, RangeBounds};
+use std::rc::Rc;
+use std::slice;
+use std::str::{self, FromStr};
 
 /// A type for protecting variable-length secrets allocated on the heap.
 ///
@@ -59,6 +75,12 @@ use std::ops::{Deref, DerefMut};
 /// Care must also be taken not to call any other methods on these types
 /// that introduce copying.
 ///
+/// These wrappers must also be allowed to drop normally. A wrapper that
+/// is leaked instead (e.g., with [`mem::forget`](std::mem::forget))
+/// leaves the memory unlocked indefinitely. As a development aid, debug
+/// builds detect this the next time the [`SecretVec`] is borrowed and panic;
+/// release builds perform no such check.
+///
 /// # Example: generate a cryptographically-random 128-bit [`SecretVec`]
 ///
 /// Initialize a [`SecretVec`] with cryptographically random data:
@@ -123,6 +145,24 @@ use std::ops::{Deref, DerefMut};
 /// secret.borrow_mut();
 /// ```
 ///
+/// # Zero-sized types
+///
+/// A [`SecretVec`] of a zero-sized type (such as `()`) is supported,
+/// and behaves as a purely logical length: [`len`](SecretVec::len)
+/// reports the number of elements, while [`size`](SecretVec::size) is
+/// zero. Borrowing it yields a slice of that many elements without any
+/// secret data behind them. libsodium still reserves guard pages for
+/// the empty region, so such a [`SecretVec`] is not free to create.
+///
+/// ```
+/// # use secrets::SecretVec;
+/// let secret = SecretVec::<()>::zero(5);
+///
+/// assert_eq!(secret.len(),          5);
+/// assert_eq!(secret.size(),         0);
+/// assert_eq!(secret.borrow().len(), 5);
+/// ```
+///
 /// [mprotect]: http://man7.org/linux/man-pages/man2/mprotect.2.html
 /// [mlock]: http://man7.org/linux/man-pages/man2/mlock.2.html
 /// [refcell]: std::cell::RefCell
@@ -137,11 +177,20 @@ pub struct SecretVec<T: Bytes> {
 /// for convenience.
 ///
 /// When this wrapper is dropped, it ensures that the underlying memory
-/// is re-locked.
+/// is re-locked. If it is leaked instead, debug builds detect this the
+/// next time the [`SecretVec`] is borrowed, but release builds don't.
 pub struct Ref<'a, T: Bytes> {
     /// an imutably-unlocked reference to the protected memory of a
     /// [`SecretVec`].
     boxed: &'a Box<T>,
+
+    /// whether or not the borrow has been temporarily re-locked with
+    /// [`pause`](Ref::pause)
+    paused: bool,
+
+    /// the index at which the next call to [`Read::read`] begins
+    /// reading
+    cursor: usize,
 }
 
 /// A mutable wrapper around the internal contents of a
@@ -149,19 +198,199 @@ pub struct Ref<'a, T: Bytes> {
 /// for convenience.
 ///
 /// When this wrapper is dropped, it ensures that the underlying memory
-/// is re-locked.
+/// is re-locked. If it is leaked instead, debug builds detect this the
+/// next time the [`SecretVec`] is borrowed, but release builds don't.
 pub struct RefMut<'a, T: Bytes> {
     /// a mutably-unlocked reference to the protected memory of a
     /// [`SecretVec`].
     boxed: &'a mut Box<T>,
+
+    /// whether or not the borrow has been temporarily re-locked with
+    /// [`pause`](RefMut::pause)
+    paused: bool,
+
+    /// the index at which the next call to [`Write::write`] begins
+    /// writing
+    cursor: usize,
+}
+
+/// An immutable wrapper around a contiguous range of the contents of a
+/// [`SecretVec`]. This wrapper [`Deref`]s to its slice representation
+/// for convenience.
+///
+/// When this wrapper is dropped, it ensures that the underlying memory
+/// is re-locked.
+pub struct RangeRef<'a, T: Bytes> {
+    /// an immutably-unlocked reference to the protected memory of a
+    /// [`SecretVec`].
+    boxed: &'a Box<T>,
+
+    /// the range of elements within the [`SecretVec`]
+    range: Range<usize>,
+}
+
+/// An iterator over non-overlapping immutable chunks of a
+/// [`SecretVec`], starting from its end, returned by
+/// [`rchunks`](SecretVec::rchunks).
+///
+/// The [`SecretVec`] is only unlocked while a yielded chunk is alive,
+/// so the iterator itself is cheap to [`Clone`].
+#[derive(Clone, Debug)]
+pub struct RChunks<'a, T: Bytes> {
+    /// the [`SecretVec`] being iterated over
+    secret: &'a SecretVec<T>,
+
+    /// the number of elements in each chunk
+    size: usize,
+
+    /// the index just past the end of the next chunk to yield
+    end: usize,
+}
+
+/// A mutable wrapper around a contiguous range of the contents of a
+/// [`SecretVec`]. This wrapper [`Deref`]s to its slice representation
+/// for convenience.
+///
+/// Many of these wrappers may share a single mutable unlock of the
+/// [`SecretVec`]. When the last of them is dropped, it ensures that the
+/// underlying memory is re-locked.
+pub struct RangeRefMut<'a, T: Bytes> {
+    /// a mutably-unlocked range of the protected memory of a
+    /// [`SecretVec`].
+    data: &'a mut [T],
+
+    /// the mutable unlock shared with any other wrappers over ranges
+    /// of the same [`SecretVec`].
+    #[allow(dead_code)] // only held to delay re-locking until dropped
+    unlock: Rc<Unlock<'a, T>>,
+}
+
+/// An iterator over non-overlapping mutable chunks of a [`SecretVec`],
+/// returned by [`chunks_mut`](SecretVec::chunks_mut).
+pub struct ChunksMut<'a, T: Bytes> {
+    /// the chunks of the mutably-unlocked protected memory that have yet
+    /// to be yielded
+    chunks: slice::ChunksMut<'a, T>,
+
+    /// the mutable unlock shared with every yielded chunk
+    unlock: Rc<Unlock<'a, T>>,
+}
+
+/// An immutable wrapper around a single element of a [`SecretVec`].
+/// This wrapper [`Deref`]s to the element for convenience.
+///
+/// When this wrapper is dropped, it ensures that the underlying memory
+/// is re-locked.
+pub struct ItemRef<'a, T: Bytes> {
+    /// an immutably-unlocked reference to the protected memory of a
+    /// [`SecretVec`].
+    boxed: &'a Box<T>,
+
+    /// the index of the element within the [`SecretVec`]
+    index: usize,
+}
+
+/// An iterator over immutable wrappers around each element of a
+/// [`SecretVec`], returned by [`iter`](SecretVec::iter).
+///
+/// The [`SecretVec`] is only unlocked while a yielded element is alive,
+/// so the iterator itself is cheap to [`Clone`].
+#[derive(Clone, Debug)]
+pub struct IterRef<'a, T: Bytes> {
+    /// the [`SecretVec`] being iterated over
+    secret: &'a SecretVec<T>,
+
+    /// the index of the next element to yield
+    index: usize,
+}
+
+/// A mutable wrapper around a single element of a [`SecretVec`]. This
+/// wrapper [`Deref`]s to the element for convenience.
+///
+/// When this wrapper (and any others sharing its unlock) is dropped, it
+/// ensures that the underlying memory is re-locked.
+pub struct ItemRefMut<'a, T: Bytes> {
+    /// a mutably-unlocked element of the protected memory of a
+    /// [`SecretVec`].
+    data: &'a mut T,
+
+    /// the mutable unlock shared with any other wrappers over elements
+    /// of the same [`SecretVec`].
+    unlock: Rc<Unlock<'a, T>>,
+}
+
+/// An iterator over mutable wrappers around each element of a
+/// [`SecretVec`], returned by [`iter_mut`](SecretVec::iter_mut).
+///
+/// The [`SecretVec`] is mutably unlocked once for the lifetime of the
+/// iterator and every element it yields, and is re-locked once all of
+/// them have been dropped.
+pub struct IterMut<'a, T: Bytes> {
+    /// the elements of the mutably-unlocked protected memory that have
+    /// yet to be yielded
+    elements: slice::IterMut<'a, T>,
+
+    /// the mutable unlock shared with every yielded element
+    unlock: Rc<Unlock<'a, T>>,
+}
+
+/// An iterator that moves each element out of a [`SecretVec`] into its
+/// own [`SecretBox`], returned by [`into_iter`](SecretVec::into_iter).
+///
+/// Each element is transferred into a newly-allocated [`SecretBox`] and
+/// zeroed in the source as it is yielded. Any elements that are never
+/// yielded are zeroed along with the rest of the [`SecretVec`] when the
+/// iterator is dropped.
+pub struct IntoIter<T: Bytes + Zeroable> {
+    /// the [`SecretVec`] whose elements are being moved out
+    secret: SecretVec<T>,
+
+    /// the index of the next element to yield
+    index: usize,
+}
+
+/// An iterator that moves each chunk of `N` elements out of a
+/// [`SecretVec`] into its own [`SecretBox`], returned by
+/// [`into_box_chunks`](SecretVec::into_box_chunks).
+///
+/// Any chunks that are never yielded are zeroed along with the rest of
+/// the [`SecretVec`] when the iterator is dropped.
+pub struct IntoBoxChunks<T: Bytes + Zeroable, const N: usize> {
+    /// the [`SecretVec`] whose chunks are being moved out
+    secret: SecretVec<T>,
+
+    /// the index of the first element of the next chunk to yield
+    index: usize,
+}
+
+/// An outstanding mutable unlock of the protected memory of a
+/// [`SecretVec`], which is re-locked when dropped.
+struct Unlock<'a, T: Bytes> {
+    /// a mutably-unlocked reference to the protected memory of a
+    /// [`SecretVec`], retained only so that it may be locked again
+    boxed: &'a Box<T>,
 }
 
 impl<T: Bytes> SecretVec<T> {
+    /// The size in bytes of each element of a [`SecretVec`]. Unlike
+    /// [`size`](SecretVec::size), this is available at compile time and
+    /// may be used to size other buffers.
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let buf = [0_u8; SecretVec::<u32>::ELEMENT_BYTE_SIZE * 4];
+    ///
+    /// assert_eq!(buf.len(), 16);
+    /// ```
+    pub const ELEMENT_BYTE_SIZE: usize = size_of::<T>();
+
     /// Instantiates and returns a new `SecretVec`.
     ///
     /// Accepts a callback function that is responsible for initializing
     /// its contents. The value yielded to the initialization callback
-    /// will be filled with garbage bytes.
+    /// will be filled with garbage bytes. If the callback panics, any
+    /// contents it has already written are zeroed out as the underlying
+    /// memory is freed during unwinding.
     ///
     /// Example:
     ///
@@ -183,6 +412,31 @@ impl<T: Bytes> SecretVec<T> {
         }
     }
 
+    /// Instantiates and returns a new [`SecretVec`]. Has equivalent
+    /// semantics to [`new`](SecretVec::new), but returns the value
+    /// produced by the callback alongside the [`SecretVec`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let (secret, sum) = SecretVec::<u8>::new_with(3, |s| {
+    ///     s.copy_from_slice(&[1, 2, 3]);
+    ///     s.iter().sum::<u8>()
+    /// });
+    ///
+    /// assert_eq!(sum,              6);
+    /// assert_eq!(*secret.borrow(), [1, 2, 3]);
+    /// ```
+    pub fn new_with<R, F>(len: usize, f: F) -> (Self, R)
+    where
+        F: FnOnce(&mut [T]) -> R,
+    {
+        let (boxed, result) = Box::new_with(len, |b| f(b.as_mut_slice()));
+
+        (Self { boxed }, result)
+    }
+
     /// Instantiates and returns a new [`SecretVec`]. Has equivalent
     /// semantics to [`new`](SecretVec::new), but allows the callback to
     /// return success or failure through a [`Result`].
@@ -198,7 +452,22 @@ impl<T: Bytes> SecretVec<T> {
             .map(|b| Self { boxed: b })
     }
 
-    /// Returns the number of elements in the [`SecretVec`].
+    /// Wraps an existing [`Box`] in a [`SecretVec`].
+    pub(crate) fn from_boxed(boxed: Box<T>) -> Self {
+        Self { boxed }
+    }
+
+    /// Returns the number of elements in the [`SecretVec`]. This is only
+    /// the number of bytes when `T` is a single byte; use
+    /// [`byte_len`](SecretVec::byte_len) for the number of bytes.
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u16>::zero(4);
+    ///
+    /// assert_eq!(secret.len(),      4);
+    /// assert_eq!(secret.byte_len(), 8);
+    /// ```
     #[allow(clippy::missing_const_for_fn)] // not usable on min supported Rust
     pub fn len(&self) -> usize {
         self.boxed.len()
@@ -215,6 +484,59 @@ impl<T: Bytes> SecretVec<T> {
         self.boxed.size()
     }
 
+    /// Returns true if the memory underlying the [`SecretVec`] was
+    /// successfully `mlock`ed when it was allocated.
+    ///
+    /// Failures to lock memory are retried as configured by
+    /// [`set_mlock_retries`](crate::set_mlock_retries). If they persist,
+    /// no secret is created at all: [`try_clone`](SecretVec::try_clone)
+    /// returns [`SecretError::Mlock`], and every other constructor
+    /// panics. This therefore only returns false for memory which is
+    /// kept from being swapped by some other means.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::zero(32);
+    ///
+    /// assert!(secret.is_mlocked());
+    /// ```
+    pub fn is_mlocked(&self) -> bool {
+        self.boxed.is_mlocked()
+    }
+
+    /// Returns the length in bytes of the contents of the [`SecretVec`],
+    /// as opposed to [`len`](SecretVec::len), which returns the number
+    /// of elements. This is identical to [`size`](SecretVec::size).
+    pub fn byte_len(&self) -> usize {
+        self.size()
+    }
+
+    /// Configures whether or not the [`Debug`] representation of the
+    /// [`SecretVec`] and its borrows reveals the number of bytes being
+    /// redacted. The size is shown by default, as `{ N bytes redacted }`;
+    /// when hidden, only `{ redacted }` is shown.
+    ///
+    /// This setting is preserved by [`Clone`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::zero(16);
+    ///
+    /// assert_eq!(format!("{:?}", secret), "{ 16 bytes redacted }");
+    ///
+    /// let secret = secret.debug_size(false);
+    ///
+    /// assert_eq!(format!("{:?}", secret), "{ redacted }");
+    /// ```
+    pub fn debug_size(mut self, show: bool) -> Self {
+        self.boxed.set_debug_size(show);
+        self
+    }
+
     /// Immutably borrows the contents of the [`SecretVec`]. Returns a
     /// wrapper that ensures the underlying memory is
     /// [`mprotect(2)`][mprotect]ed once all borrows exit scope.
@@ -237,6 +559,86 @@ impl<T: Bytes> SecretVec<T> {
         Ref::new(&self.boxed)
     }
 
+    /// Immutably borrows the contents of the [`SecretVec`]. Has
+    /// equivalent semantics to [`borrow`](SecretVec::borrow), but
+    /// returns an error rather than panicking if the [`SecretVec`] is
+    /// already borrowed as many times as can be tracked at once (255,
+    /// or 4,294,967,295 with the `wide-refcount` feature).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretError, SecretVec};
+    /// let secret    = SecretVec::<u8>::from(&mut [1, 2][..]);
+    /// let secret_r1 = secret.try_borrow()?;
+    /// let secret_r2 = secret.try_borrow()?;
+    ///
+    /// assert_eq!(secret_r1, secret_r2);
+    /// # Ok::<(), SecretError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::TooManyBorrows`] if taking another borrow
+    /// would overflow the count of outstanding borrows.
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, SecretError> {
+        Ref::try_new(&self.boxed)
+    }
+
+    /// Immutably borrows the entire contents of the [`SecretVec`] as a
+    /// slice. This is an alias of [`borrow`](SecretVec::borrow), for
+    /// generic code that wants to make explicit that it is viewing the
+    /// whole of the secret.
+    ///
+    /// A [`SecretVec`] can't implement [`Index`](std::ops::Index)
+    /// itself, since indexing must return a plain reference and there
+    /// would be no guard left to re-lock the memory. Instead, borrow the
+    /// secret and index the returned [`Ref`]: any slice taken from it,
+    /// including `&secret.slice()[..]`, is tied to the [`Ref`]'s
+    /// lifetime and so can't outlive the unlock.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+    /// let slice  = secret.slice();
+    ///
+    /// assert_eq!(slice[..],   [1, 2, 3, 4]);
+    /// assert_eq!(slice[1..3], [2, 3]);
+    /// ```
+    pub fn slice(&self) -> Ref<'_, T> {
+        self.borrow()
+    }
+
+    /// Immutably borrows the first `n` elements of the [`SecretVec`],
+    /// returning [`None`] if it holds fewer than `n` elements. Returns a
+    /// wrapper that ensures the underlying memory is
+    /// [`mprotect(2)`][mprotect]ed once all borrows exit scope.
+    ///
+    /// This is useful when a [`SecretVec`] is allocated at a maximum
+    /// size but only a prefix of it is meaningful, since that prefix
+    /// can be exposed without reallocating to its exact length.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 0, 0][..]);
+    ///
+    /// assert_eq!(*secret.borrow_prefix(3).unwrap(), [1, 2, 3]);
+    /// assert!(secret.borrow_prefix(6).is_none());
+    /// ```
+    ///
+    /// [mprotect]: http://man7.org/linux/man-pages/man2/mprotect.2.html
+    pub fn borrow_prefix(&self, n: usize) -> Option<RangeRef<'_, T>> {
+        if n > self.len() {
+            return None;
+        }
+
+        Some(RangeRef::new(&self.boxed, 0..n))
+    }
+
     /// Mutably borrows the contents of the [`SecretVec`]. Returns a
     /// wrapper that ensures the underlying memory is
     /// [`mprotect(2)`][mprotect]ed once this borrow exits scope.
@@ -257,209 +659,4372 @@ impl<T: Bytes> SecretVec<T> {
     pub fn borrow_mut(&mut self) -> RefMut<'_, T> {
         RefMut::new(&mut self.boxed)
     }
-}
 
-impl<T: Bytes + Randomizable> SecretVec<T> {
-    /// Creates a new [`SecretVec`] with  `len` elements, filled with
-    /// cryptographically-random bytes.
-    pub fn random(len: usize) -> Self {
-        Self {
-            boxed: Box::random(len),
+    /// Immutably borrows the element at `index`, returning [`None`] if
+    /// it is out of bounds. Returns a wrapper that ensures the
+    /// underlying memory is [`mprotect(2)`][mprotect]ed once all borrows
+    /// exit scope.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    ///
+    /// assert_eq!(*secret.get(1).unwrap(), 2);
+    /// assert!(secret.get(3).is_none());
+    /// ```
+    ///
+    /// [mprotect]: http://man7.org/linux/man-pages/man2/mprotect.2.html
+    pub fn get(&self, index: usize) -> Option<ItemRef<'_, T>> {
+        if index >= self.len() {
+            return None;
         }
-    }
-}
 
-impl<T: Bytes + Zeroable> SecretVec<T> {
-    /// Creates a new [`SecretVec`] with  `len` elements, filled with
-    /// zeroes.
-    pub fn zero(len: usize) -> Self {
-        Self {
-            boxed: Box::zero(len),
-        }
+        Some(ItemRef::new(&self.boxed, index))
     }
-}
 
-impl<T: Bytes + Zeroable> From<&mut [T]> for SecretVec<T> {
-    /// Creates a new [`SecretVec`] from existing, unprotected data, and
-    /// immediately zeroes out the memory of the data being moved in.
-    fn from(data: &mut [T]) -> Self {
-        Self { boxed: data.into() }
+    /// Calls `f` with an immutable reference to the element at `index`,
+    /// returning its result, or [`None`] if `index` is out of bounds.
+    /// The underlying memory is only unlocked for as long as `f` runs.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    ///
+    /// assert_eq!(secret.with_at(1, |x| *x == 2), Some(true));
+    /// assert_eq!(secret.with_at(3, |x| *x == 2), None);
+    /// ```
+    pub fn with_at<R, F>(&self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.get(index).map(|item| f(&item))
     }
-}
 
-impl<T: Bytes> Debug for SecretVec<T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.boxed.fmt(f)
-    }
-}
+    /// Calls `f` with an immutable reference to the contents of the
+    /// [`SecretVec`], returning its result. Has equivalent semantics to
+    /// [`SecretBox::expose_for`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    ///
+    /// assert_eq!(secret.expose_for("summing", |s| s.iter().sum::<u8>()), 6);
+    /// ```
+    pub fn expose_for<R, F>(&self, reason: &'static str, f: F) -> R
+    where
+        F: FnOnce(&[T]) -> R,
+    {
+        let _exposure = Exposure::new(reason);
 
-impl<T: Bytes + ConstantEq> PartialEq for SecretVec<T> {
-    fn eq(&self, rhs: &Self) -> bool {
-        self.boxed.eq(&rhs.boxed)
+        f(&self.borrow())
     }
-}
 
-impl<'a, T: Bytes> Ref<'a, T> {
-    /// Instantiates a new `Ref`.
-    fn new(boxed: &'a Box<T>) -> Self {
-        Self {
-            boxed: boxed.unlock(),
+    /// Mutably borrows the element at `index`, returning [`None`] if it
+    /// is out of bounds. Returns a wrapper that ensures the underlying
+    /// memory is [`mprotect(2)`][mprotect]ed once this borrow exits
+    /// scope.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    ///
+    /// *secret.get_mut(1).unwrap() = 5;
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 5, 3]);
+    /// assert!(secret.get_mut(3).is_none());
+    /// ```
+    ///
+    /// Like [`borrow_mut`](SecretVec::borrow_mut), this takes `&mut
+    /// self`, so no two mutable borrows of elements may coexist:
+    ///
+    /// ```compile_fail
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u16>::zero(2);
+    /// let     item_0 = secret.get_mut(0);
+    ///
+    /// // error[E0499]: cannot borrow `secret` as mutable more than once
+    /// // at a time
+    /// let item_1 = secret.get_mut(1);
+    /// ```
+    ///
+    /// [mprotect]: http://man7.org/linux/man-pages/man2/mprotect.2.html
+    pub fn get_mut(&mut self, index: usize) -> Option<ItemRefMut<'_, T>> {
+        if index >= self.len() {
+            return None;
         }
-    }
-}
 
-impl<T: Bytes> Clone for Ref<'_, T> {
-    fn clone(&self) -> Self {
-        Self {
-            boxed: self.boxed.unlock(),
-        }
-    }
-}
+        self.boxed.check_leaked_borrows(true);
 
-impl<T: Bytes> Drop for Ref<'_, T> {
-    fn drop(&mut self) {
-        self.boxed.lock();
+        let (boxed, data) = self.boxed.unlock_mut_split();
+
+        Some(ItemRefMut {
+            data:   &mut data[index],
+            unlock: Rc::new(Unlock { boxed }),
+        })
     }
-}
 
-impl<T: Bytes> Deref for Ref<'_, T> {
-    type Target = [T];
+    /// Mutably borrows the element at `index`, first filling it with the
+    /// result of `f` if every one of its bytes is currently zero.
+    /// Returns [`None`] if `index` is out of bounds, in which case `f`
+    /// isn't called.
+    ///
+    /// This allows tables of secrets to be populated lazily, using an
+    /// all-zero element as a sentinel for one that hasn't yet been
+    /// initialized. The check against zero is made in constant time, so
+    /// it doesn't reveal anything about an element that is already
+    /// populated, though whether `f` is called is of course observable.
+    /// Note that the value returned by `f` is unprotected until it has
+    /// been written into the [`SecretVec`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut keys = SecretVec::<[u8; 4]>::zero(8);
+    ///
+    /// assert_eq!(*keys.get_or_init(3, || [1, 2, 3, 4]).unwrap(), [1, 2, 3, 4]);
+    /// assert_eq!(*keys.get_or_init(3, || [5, 6, 7, 8]).unwrap(), [1, 2, 3, 4]);
+    /// ```
+    pub fn get_or_init<F>(&mut self, index: usize, f: F) -> Option<ItemRefMut<'_, T>>
+    where
+        F: FnOnce() -> T,
+    {
+        let mut item = self.get_mut(index)?;
+        let mut zero = T::uninitialized();
+
+        zero.zero();
+
+        if (*item).constant_eq(&zero) {
+            *item = f();
+        }
+
+        Some(item)
+    }
+
+    /// Returns an iterator over mutable borrows of each element of the
+    /// [`SecretVec`]. The [`SecretVec`] is unlocked once for the
+    /// duration of the iteration, rather than once per element.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    ///
+    /// for mut x in secret.iter_mut() {
+    ///     *x *= 2;
+    /// }
+    ///
+    /// assert_eq!(*secret.borrow(), [2, 4, 6]);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.boxed.check_leaked_borrows(true);
+
+        let (boxed, data) = self.boxed.unlock_mut_split();
+
+        IterMut {
+            elements: data.iter_mut(),
+            unlock:   Rc::new(Unlock { boxed }),
+        }
+    }
+
+    /// Returns an iterator over immutable borrows of each element of the
+    /// [`SecretVec`]. Each element is borrowed as though through
+    /// [`get`](SecretVec::get).
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    ///
+    /// assert_eq!(secret.iter().map(|x| *x).sum::<u16>(), 6);
+    /// ```
+    pub fn iter(&self) -> IterRef<'_, T> {
+        IterRef {
+            secret: self,
+            index:  0,
+        }
+    }
+
+    /// Compares the [`SecretVec`] to `rhs` in constant time, in a way
+    /// that reveals neither their contents nor their lengths.
+    ///
+    /// The [`PartialEq`] implementation returns early when two secrets
+    /// differ in length, which leaks whether their lengths differ. In
+    /// contrast, this method treats both operands as though they were
+    /// zero-padded to `max` elements and always compares every byte of
+    /// that width, so the time taken depends only on `max`. Two secrets
+    /// of different lengths are never equal, even if the longer one
+    /// consists of the shorter followed by zeroes.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret_1 = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let secret_2 = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let secret_3 = SecretVec::<u8>::from(&mut [1, 2][..]);
+    ///
+    /// assert!( secret_1.ct_eq_total(&secret_2, 64));
+    /// assert!(!secret_1.ct_eq_total(&secret_3, 64));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if either [`SecretVec`] is longer than `max` elements.
+    pub fn ct_eq_total(&self, rhs: &Self, max: usize) -> bool {
+        assert!(self.len() <= max && rhs.len() <= max,
+            "secrets: compared secrets may not exceed {} elements", max);
+
+        let lhs = self.borrow();
+        let rhs = rhs.borrow();
+
+        ct_eq_padded(lhs.as_bytes(), rhs.as_bytes(), max * T::size())
+    }
+
+    /// Compares the [`SecretVec`] against another, possibly of a
+    /// different element type, in constant time. The two are compared
+    /// bytewise, and are never equal if they differ in size.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let bytes = SecretVec::<u8>::from([0x01, 0x01]);
+    /// let words = SecretVec::<u16>::from([0x0101]);
+    ///
+    /// assert!(bytes.eq_bytes(&words));
+    /// ```
+    pub fn eq_bytes<U: Bytes>(&self, rhs: &SecretVec<U>) -> bool {
+        if self.size() != rhs.size() {
+            return false;
+        }
+
+        (*self.borrow()).as_bytes().constant_eq((*rhs.borrow()).as_bytes())
+    }
+
+    /// Returns an iterator over mutable, non-overlapping chunks of
+    /// `size` elements of the [`SecretVec`]. If `size` does not evenly
+    /// divide its length, the final chunk will be shorter.
+    ///
+    /// The [`SecretVec`] is unlocked once for the lifetime of the
+    /// iterator, and is re-locked after both the iterator and every
+    /// chunk it has yielded have been dropped.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::zero(5);
+    ///
+    /// for (i, mut chunk) in secret.chunks_mut(2).enumerate() {
+    ///     chunk[0] = i as u8 + 1;
+    /// }
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 0, 2, 0, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunks_mut(&mut self, size: usize) -> ChunksMut<'_, T> {
+        assert!(size != 0, "secrets: chunk size must be non-zero");
+
+        self.boxed.check_leaked_borrows(true);
+
+        let (boxed, data) = self.boxed.unlock_mut_split();
+
+        ChunksMut {
+            chunks: data.chunks_mut(size),
+            unlock: Rc::new(Unlock { boxed }),
+        }
+    }
+
+    /// Returns an iterator over immutable, non-overlapping chunks of
+    /// `size` elements of the [`SecretVec`], starting at its end. This
+    /// mirrors [`slice::rchunks`]: every chunk is `size` elements long
+    /// except for the last one yielded (from the front of the
+    /// [`SecretVec`]), which will be shorter if `size` does not evenly
+    /// divide its length.
+    ///
+    /// The [`SecretVec`] is only unlocked while a yielded chunk is
+    /// alive.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5][..]);
+    /// let mut chunks = secret.rchunks(2);
+    ///
+    /// assert_eq!(*chunks.next().unwrap(), [4, 5]);
+    /// assert_eq!(*chunks.next().unwrap(), [2, 3]);
+    /// assert_eq!(*chunks.next().unwrap(), [1]);
+    /// assert!(chunks.next().is_none());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn rchunks(&self, size: usize) -> RChunks<'_, T> {
+        assert!(size != 0, "secrets: chunk size must be non-zero");
+
+        RChunks {
+            secret: self,
+            size,
+            end:    self.len(),
+        }
+    }
+
+    /// Appends a copy of the contents of `other` to the end of the
+    /// [`SecretVec`], leaving `other` unchanged.
+    ///
+    /// The elements of `other` are copied directly from its protected
+    /// memory into the newly-grown allocation of the [`SecretVec`], and
+    /// never pass through unprotected memory. The [`SecretVec`] is
+    /// reallocated only once, and its old allocation is zeroed when
+    /// freed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2][..]);
+    /// let     suffix = SecretVec::<u8>::from(&mut [3, 4][..]);
+    ///
+    /// secret.extend_from_secret(&suffix);
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 2, 3, 4]);
+    /// assert_eq!(*suffix.borrow(), [3, 4]);
+    /// ```
+    pub fn extend_from_secret(&mut self, other: &Self) {
+        let other = other.borrow();
+
+        self.boxed.grow(self.len() + other.len(), |tail| {
+            tail.copy_from_slice(&other);
+        });
+    }
+
+    /// Returns true if the SHA-256 digest of the contents of the
+    /// [`SecretVec`] is `expected`, comparing them in constant time. The
+    /// digest is computed into a scratch buffer that is zeroed
+    /// afterward, while `expected` is assumed not to be secret.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// const DIGEST: [u8; 32] = [
+    ///     0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    ///     0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    /// ];
+    ///
+    /// let secret = SecretVec::<u8>::from(&mut b"abc".to_owned()[..]);
+    ///
+    /// assert!(secret.matches_digest_ct(&DIGEST));
+    /// ```
+    pub fn matches_digest_ct(&self, expected: &[u8; SHA256_BYTES]) -> bool {
+        sodium::sha256_verify(self.borrow().as_bytes(), expected)
+    }
+
+    /// Returns true if the contents of the [`SecretVec`] equal `other`
+    /// once the shorter of the two is padded with trailing zeroes to the
+    /// length of the longer. This is useful when interfacing with APIs
+    /// that zero-pad keys to a fixed length.
+    ///
+    /// The common prefix is compared in constant time, and every
+    /// remaining byte of the longer side is checked for zero without
+    /// exiting early. The lengths of both sides, and therefore which of
+    /// them is longer, are not considered secret and are revealed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 0, 0][..]);
+    ///
+    /// assert!( secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 3]));
+    /// assert!( secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 3, 0, 0, 0]));
+    /// assert!(!secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 3, 4]));
+    /// ```
+    pub fn eq_ignoring_trailing_zeros_ct(&self, other: &[T]) -> bool {
+        let secret = self.borrow();
+        let lhs    = (*secret).as_bytes();
+        let rhs    = other.as_bytes();
+        let common = lhs.len().min(rhs.len());
+
+        let (prefix, tail) = if lhs.len() > rhs.len() {
+            (lhs[..common].constant_eq(rhs), &lhs[common..])
+        } else {
+            (lhs.constant_eq(&rhs[..common]), &rhs[common..])
+        };
+
+        prefix & (tail.iter().fold(0, |acc, b| acc | b) == 0)
+    }
+
+    /// Clones the [`SecretVec`] into a new protected allocation, returning
+    /// an error rather than panicking if the memory can't be allocated
+    /// or protected. This is useful for callers that can't tolerate a
+    /// panic when cloning under memory pressure.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::Allocation`] if libsodium can't be
+    /// initialized, if the new memory can't be allocated, or if the
+    /// protection level of either allocation can't be changed. Returns
+    /// [`SecretError::MemlockBudgetExceeded`] if locking the new memory
+    /// would exceed the process's limit on locked memory, and
+    /// [`SecretError::Mlock`] if it still can't be locked after every
+    /// retry has been exhausted.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretVec, SecretError};
+    /// let secret = SecretVec::<u8>::random(32);
+    /// let copy   = secret.try_clone()?;
+    ///
+    /// assert_eq!(secret, copy);
+    /// # Ok::<(), SecretError>(())
+    /// ```
+    pub fn try_clone(&self) -> Result<Self, SecretError> {
+        Ok(Self {
+            boxed: self.boxed.try_clone()?,
+        })
+    }
+
+    /// Converts the [`SecretVec`] into a [`SharedSecretVec`], whose
+    /// clones share a single protected allocation until one of them is
+    /// mutably borrowed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let shared_1 = SecretVec::<u8>::random(32).shared();
+    /// let shared_2 = shared_1.clone();
+    ///
+    /// assert!(shared_2.is_shared());
+    /// assert_eq!(shared_1, shared_2);
+    /// ```
+    pub fn shared(self) -> SharedSecretVec<T> {
+        SharedSecretVec::new(self)
+    }
+
+    /// Moves the contents out of the [`SecretVec`], leaving an empty one
+    /// in its place. This is useful for taking a secret out of a struct
+    /// field while leaving the field valid.
+    ///
+    /// The protected allocation is handed over as-is, so its contents
+    /// are neither copied nor unlocked. Only the empty placeholder is
+    /// newly allocated.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let     taken  = secret.take();
+    ///
+    /// assert!(secret.is_empty());
+    /// assert_eq!(*taken.borrow(), [1, 2, 3]);
+    /// ```
+    pub fn take(&mut self) -> Self {
+        Self {
+            boxed: mem::replace(&mut self.boxed, Box::new(0, |_| {})),
+        }
+    }
+}
+
+impl<T: Bytes + Randomizable> SecretVec<T> {
+    /// Creates a new [`SecretVec`] with  `len` elements, filled with
+    /// cryptographically-random bytes.
+    pub fn random(len: usize) -> Self {
+        Self {
+            boxed: Box::random(len),
+        }
+    }
+}
+
+impl<T: Bytes + Zeroable> SecretVec<T> {
+    /// Creates a new [`SecretVec`] with  `len` elements, filled with
+    /// zeroes.
+    pub fn zero(len: usize) -> Self {
+        Self {
+            boxed: Box::zero(len),
+        }
+    }
+
+    /// Instantiates and returns a new [`SecretVec`]. Has equivalent
+    /// semantics to [`new`](SecretVec::new), but the value yielded to
+    /// the initialization callback is filled with zeroes instead of
+    /// garbage, so any elements the callback doesn't write are left
+    /// zeroed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::new_zeroed(4, |s| {
+    ///     s[..2].copy_from_slice(&[0xab, 0xcd]);
+    /// });
+    ///
+    /// assert_eq!(*secret.borrow(), [0xab, 0xcd, 0x00, 0x00]);
+    /// ```
+    pub fn new_zeroed<F>(len: usize, f: F) -> Self
+    where
+        F: FnOnce(&mut [T]),
+    {
+        Self {
+            boxed: Box::new_zeroed(len, |b| f(b.as_mut_slice())),
+        }
+    }
+
+    /// Retains only the elements for which `f` returns true, passing
+    /// each element to `f` mutably so that it may be modified while
+    /// deciding whether or not to retain it.
+    ///
+    /// Retained elements are moved into a newly-allocated region of
+    /// protected memory and zeroed in their original location. The
+    /// memory holding any removed elements is zeroed when the original
+    /// region is freed.
+    ///
+    /// Note that the number of retained elements is revealed by the
+    /// resulting length, and that `f` is free to take a different
+    /// amount of time for different elements.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+    ///
+    /// secret.retain_mut(|x| {
+    ///     *x *= 10;
+    ///     *x > 20
+    /// });
+    ///
+    /// assert_eq!(*secret.borrow(), [30, 40]);
+    /// ```
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.compact(|secret| {
+            let mut kept = 0;
+
+            for i in 0..secret.len() {
+                if f(&mut secret[i]) {
+                    secret.copy_within(i..=i, kept);
+                    kept += 1;
+                }
+            }
+
+            kept
+        });
+    }
+
+    /// Removes consecutive repeated elements, keeping only the first
+    /// of each run. If the [`SecretVec`] is sorted, this removes every
+    /// duplicate.
+    ///
+    /// Adjacent elements are compared in constant time, and the unique
+    /// elements are moved into a newly-allocated region of protected
+    /// memory. The memory holding the removed duplicates is zeroed when
+    /// the original region is freed.
+    ///
+    /// Note that the number of duplicates is revealed by the resulting
+    /// length, and that the time taken to move the unique elements may
+    /// depend on where the duplicates were.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 1, 2, 2, 1][..]);
+    ///
+    /// secret.dedup();
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 2, 1]);
+    /// ```
+    pub fn dedup(&mut self) {
+        self.compact(|secret| {
+            let mut kept = 0;
+
+            for i in 0..secret.len() {
+                if kept == 0 || !secret[i].constant_eq(&secret[kept - 1]) {
+                    secret.copy_within(i..=i, kept);
+                    kept += 1;
+                }
+            }
+
+            kept
+        });
+    }
+
+    /// Removes the elements in `range` from the [`SecretVec`], without
+    /// returning them. The remaining elements are moved into a
+    /// newly-allocated region of protected memory and the original
+    /// region, including the removed elements, is zeroed when freed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5][..]);
+    ///
+    /// secret.drain_range(1..4);
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 5]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of `range` is greater than its end, or if
+    /// its end is greater than the length of the [`SecretVec`].
+    pub fn drain_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let len   = self.len();
+        let range = checked_range(range, len).unwrap_or_else(|| {
+            panic!("secrets: range out of bounds for length {}", len)
+        });
+
+        self.compact(|secret| {
+            secret.copy_within(range.end.., range.start);
+            len - range.len()
+        });
+    }
+
+    /// Copies the elements in `range` into a new, independently-owned
+    /// [`SecretVec`], which may outlive this one. Returns [`None`] if
+    /// the start of `range` is greater than its end, or if its end is
+    /// greater than the length of the [`SecretVec`].
+    ///
+    /// The elements are copied directly from this [`SecretVec`]'s
+    /// protected memory into the new one's, and both are locked again
+    /// before returning.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+    /// let middle = secret.slice_to_owned(1..3).unwrap();
+    ///
+    /// assert_eq!(*middle.borrow(), [2, 3]);
+    /// assert!(secret.slice_to_owned(3..5).is_none());
+    /// ```
+    pub fn slice_to_owned<R>(&self, range: R) -> Option<Self>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range  = checked_range(range, self.len())?;
+        let secret = self.borrow();
+
+        Some(Self::new(range.len(), |s| s.copy_from_slice(&secret[range])))
+    }
+
+    /// Splits the [`SecretVec`] into two independently-owned
+    /// [`SecretVec`]s at index `at`. The first contains the elements in
+    /// `[0, at)` and the second the elements in `[at, len)`.
+    ///
+    /// The contents are copied into two newly-allocated regions of
+    /// protected memory, and the original is zeroed before it is freed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5][..]);
+    ///
+    /// let (nonce, ciphertext) = secret.split_at_owned(2);
+    ///
+    /// assert_eq!(*nonce.borrow(),      [1, 2]);
+    /// assert_eq!(*ciphertext.borrow(), [3, 4, 5]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the length of the [`SecretVec`].
+    pub fn split_at_owned(mut self, at: usize) -> (Self, Self) {
+        let len = self.len();
+
+        assert!(at <= len,
+            "secrets: split index {} out of bounds for length {}", at, len);
+
+        let mut secret = self.borrow_mut();
+
+        let head = Self::new(at,       |h| h.copy_from_slice(&secret[..at]));
+        let tail = Self::new(len - at, |t| t.copy_from_slice(&secret[at..]));
+
+        secret.zero();
+
+        (head, tail)
+    }
+
+    /// Inserts the contents of `data` at the front of the [`SecretVec`],
+    /// and immediately zeroes out the memory of the data being moved
+    /// in.
+    ///
+    /// The [`SecretVec`] is reallocated only once, regardless of the
+    /// length of `data`, and its old allocation is zeroed when freed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret  = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let mut version = [0xff];
+    ///
+    /// secret.prepend(&mut version);
+    ///
+    /// assert_eq!(*secret.borrow(), [0xff, 1, 2, 3]);
+    /// assert_eq!(version,          [0]);
+    /// ```
+    pub fn prepend(&mut self, data: &mut [T]) {
+        let len = self.len();
+
+        self.boxed.grow(len + data.len(), |_| {});
+
+        let mut secret = self.borrow_mut();
+
+        secret.copy_within(..len, data.len());
+        secret[..data.len()].copy_from_slice(data);
+        data.zero();
+    }
+
+    /// Inserts `value` into a [`SecretVec`] whose contents are already
+    /// sorted, keeping them sorted. Elements are ordered by comparing
+    /// their bytes lexicographically, as with `memcmp(3)`. This matches
+    /// the numeric order of `u8`s and of byte arrays holding big-endian
+    /// integers, but not necessarily that of wider integer types, whose
+    /// in-memory representation is usually little-endian. `value` is
+    /// inserted after any elements equal to it.
+    ///
+    /// Every element is compared against `value` in constant time, and
+    /// the scan always visits the whole [`SecretVec`], so the time taken
+    /// doesn't depend on where `value` belongs. However, the position
+    /// `value` ends up at is not hidden from anyone who can later
+    /// observe the layout of the contents. The [`SecretVec`] is
+    /// reallocated to make room, and its old allocation is zeroed when
+    /// freed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 3, 5][..]);
+    ///
+    /// secret.insert_sorted_ct(4);
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 3, 4, 5]);
+    /// ```
+    pub fn insert_sorted_ct(&mut self, value: T) {
+        let index = self.borrow().iter().fold(0, |index, elem| {
+            let le = !ct_lt_bytes(value.as_bytes(), elem.as_bytes());
+
+            index + usize::from(le & 1)
+        });
+
+        self.insert(index, value);
+    }
+
+    /// Consumes the [`SecretVec`], returning an iterator that moves
+    /// each successive, non-overlapping chunk of `N` elements into its
+    /// own [`SecretBox`].
+    ///
+    /// Each chunk is transferred into a newly-allocated [`SecretBox`]
+    /// and zeroed in the source as it is yielded, in the same way as
+    /// [`into_iter`](SecretVec::into_iter) does for single elements.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretBox, SecretVec};
+    /// let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+    /// let keys   = secret.into_box_chunks::<2>().collect::<Vec<SecretBox<[u8; 2]>>>();
+    ///
+    /// assert_eq!(*keys[0].borrow(), [1, 2]);
+    /// assert_eq!(*keys[1].borrow(), [3, 4]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero or does not evenly divide the length of
+    /// the [`SecretVec`].
+    pub fn into_box_chunks<const N: usize>(self) -> IntoBoxChunks<T, N> {
+        assert!(N != 0, "secrets: chunk size must be non-zero");
+
+        #[allow(clippy::manual_is_multiple_of)] // not usable on min supported Rust
+        let divisible = self.len() % N == 0;
+
+        assert!(divisible,
+            "secrets: chunk size {} does not divide length {}", N, self.len());
+
+        IntoBoxChunks { secret: self, index: 0 }
+    }
+
+    /// Resizes the [`SecretVec`] to hold `new_len` elements. If it
+    /// grows, each new element is set to `value`; if it shrinks, any
+    /// elements beyond `new_len` are discarded.
+    ///
+    /// The contents are moved into a newly-allocated region of
+    /// protected memory, and the old region is zeroed when freed. The
+    /// copy of `value` held by this function is zeroed afterward.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2][..]);
+    ///
+    /// secret.resize(4, 0xff);
+    /// assert_eq!(*secret.borrow(), [1, 2, 0xff, 0xff]);
+    ///
+    /// secret.resize(1, 0xff);
+    /// assert_eq!(*secret.borrow(), [1]);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, mut value: T) {
+        self.boxed.grow(new_len, |tail| {
+            for elem in tail {
+                *elem = value;
+            }
+        });
+
+        value.zero();
+    }
+
+    /// Inserts `value` at `index`, shifting every element after it one
+    /// position to the right. The [`SecretVec`] is reallocated to make
+    /// room, and the copy of `value` held by this function is zeroed.
+    fn insert(&mut self, index: usize, mut value: T) {
+        let len = self.len();
+
+        proven!(index <= len,
+            "secrets: insertion index out of bounds");
+
+        self.boxed.grow(len + 1, |_| {});
+
+        let mut secret = self.borrow_mut();
+
+        secret.copy_within(index..len, index + 1);
+        secret[index] = value;
+        value.zero();
+    }
+
+    /// Replaces the backing memory of the [`SecretVec`] with a smaller
+    /// allocation. The callback `f` must move the elements to be kept
+    /// to the front of the mutably-borrowed contents and return how
+    /// many of them there are. Those elements are transferred into the
+    /// new allocation, and the old allocation is zeroed when freed.
+    fn compact<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut [T]) -> usize,
+    {
+        let kept = f(&mut self.borrow_mut());
+
+        self.boxed.grow(kept, |_| {});
+    }
+}
+
+impl SecretVec<u8> {
+    /// Returns true if `needle` appears anywhere within the contents of
+    /// the [`SecretVec`].
+    ///
+    /// Every possible alignment of `needle` is compared in constant
+    /// time, and the scan continues through every window even after a
+    /// match has been found. This reveals only whether `needle` is
+    /// present, and not where. The scan takes `O(n·m)` time where `n`
+    /// is the length of the [`SecretVec`] and `m` is the length of
+    /// `needle`.
+    ///
+    /// An empty `needle` is always contained.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut bytes  = *b"correct horse";
+    /// let     secret = SecretVec::from(&mut bytes[..]);
+    ///
+    /// assert!( secret.contains_subslice_ct(b"horse"));
+    /// assert!(!secret.contains_subslice_ct(b"battery"));
+    /// ```
+    pub fn contains_subslice_ct(&self, needle: &[u8]) -> bool {
+        if needle.is_empty() {
+            return true;
+        }
+
+        self.borrow()
+            .windows(needle.len())
+            .fold(false, |found, window| found | window.constant_eq(needle))
+    }
+
+    /// Reads bytes from `reader` into a new [`SecretVec`] until `delim`
+    /// is found, or until the end of the stream is reached. The
+    /// delimiter is consumed from `reader` but is not included in the
+    /// returned [`SecretVec`].
+    ///
+    /// Bytes are copied directly from the reader's buffer into a
+    /// protected staging area capable of holding `max` bytes, then
+    /// transferred into a [`SecretVec`] of the exact length read. Note
+    /// that any buffers internal to `reader` are outside of our
+    /// control, and are not zeroed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// # use std::io::Cursor;
+    /// let mut reader = Cursor::new(b"hunter2\nhunter3\n");
+    ///
+    /// let first  = SecretVec::from_reader_until(&mut reader, b'\n', 64)?;
+    /// let second = SecretVec::from_reader_until(&mut reader, b'\n', 64)?;
+    ///
+    /// assert_eq!(*first.borrow(),  *b"hunter2");
+    /// assert_eq!(*second.borrow(), *b"hunter3");
+    /// # Ok::<(), secrets::SecretError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::LengthExceeded`] if more than `max` bytes
+    /// are read without finding `delim`, and [`SecretError::Io`] if
+    /// reading from `reader` fails.
+    pub fn from_reader_until<R>(mut reader: R, delim: u8, max: usize) -> Result<Self, SecretError>
+    where
+        R: BufRead,
+    {
+        let mut secret = Self::zero(max);
+        let mut len    = 0;
+
+        {
+            let mut staging = secret.borrow_mut();
+
+            loop {
+                let available = match reader.fill_buf() {
+                    Ok([])  => break,
+                    Ok(buf) => buf,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e)  => return Err(SecretError::Io(e.kind())),
+                };
+
+                let (chunk, found) = match available.iter().position(|b| *b == delim) {
+                    Some(i) => (&available[..i], true),
+                    None    => (available, false),
+                };
+
+                if chunk.len() > max - len {
+                    return Err(SecretError::LengthExceeded);
+                }
+
+                staging[len..len + chunk.len()].copy_from_slice(chunk);
+
+                let consumed = chunk.len() + usize::from(found);
+
+                len += chunk.len();
+                reader.consume(consumed);
+
+                if found {
+                    break;
+                }
+            }
+        }
+
+        secret.compact(|_| len);
+
+        Ok(secret)
+    }
+
+    /// Reads exactly `len` bytes from `reader` into a new [`SecretVec`].
+    ///
+    /// The bytes are read directly into protected memory. Note that any
+    /// buffers internal to `reader` are outside of our control, and are
+    /// not zeroed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// # use std::io::Cursor;
+    /// let mut reader = Cursor::new(b"hunter2");
+    ///
+    /// let secret = SecretVec::from_reader_exact(&mut reader, 6)?;
+    ///
+    /// assert_eq!(*secret.borrow(), *b"hunter");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered by [`Read::read_exact`], including
+    /// an [`io::ErrorKind::UnexpectedEof`] if the stream ends before
+    /// `len` bytes could be read.
+    pub fn from_reader_exact<R>(mut reader: R, len: usize) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let (secret, result) = Self::new_with(len, |s| reader.read_exact(s));
+
+        result.map(|()| secret)
+    }
+
+    /// Reads `reader` to its end into a new [`SecretVec`].
+    ///
+    /// The bytes are read directly into protected memory. Since the
+    /// total length isn't known ahead of time, the bytes are staged in a
+    /// protected buffer that starts at one page and doubles in capacity
+    /// each time it fills, so reading `n` bytes reallocates only
+    /// `O(log n)` times. Each reallocation transfers the contents into a
+    /// newly-allocated and locked region, and zeroes the old one before
+    /// freeing it. Once the stream ends, the contents are transferred
+    /// once more into an allocation of exactly the right size.
+    ///
+    /// Growing the existing region in place (e.g., with `mremap(2)`)
+    /// isn't attempted: libsodium surrounds each allocation with guard
+    /// pages and places a canary immediately before it, none of which
+    /// can be moved, so allocating and transferring is the only option
+    /// available on any platform.
+    ///
+    /// Note that any buffers internal to `reader` are outside of our
+    /// control, and are not zeroed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// # use std::io::Cursor;
+    /// let mut reader = Cursor::new(b"hunter2");
+    ///
+    /// let secret = SecretVec::from_reader(&mut reader)?;
+    ///
+    /// assert_eq!(*secret.borrow(), *b"hunter2");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns any error other than [`io::ErrorKind::Interrupted`]
+    /// encountered by [`Read::read`], in which case any bytes read so far
+    /// are zeroed.
+    pub fn from_reader<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut secret = Self::zero(FROM_READER_CAPACITY);
+        let mut len    = 0;
+
+        loop {
+            if len == secret.len() {
+                secret.boxed.grow(len * 2, <[u8]>::zero);
+            }
+
+            let read = reader.read(&mut secret.borrow_mut()[len..]);
+
+            match read {
+                Ok(0)  => break,
+                Ok(n)  => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    // scrub the partial read now, rather than relying on
+                    // it being zeroed whenever the allocation is freed
+                    secret.borrow_mut()[..len].zero();
+                    return Err(e);
+                },
+            }
+        }
+
+        secret.compact(|_| len);
+
+        Ok(secret)
+    }
+
+    /// Reads an asynchronous `reader` to its end into a new
+    /// [`SecretVec`]. Has equivalent semantics to
+    /// [`from_reader`](SecretVec::from_reader).
+    ///
+    /// The protected staging buffer is only unlocked while `reader` is
+    /// being polled. Whenever `reader` has no data available and the
+    /// read must wait, the buffer is locked again until it is next
+    /// polled.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// # fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    /// #     use std::sync::Arc;
+    /// #     use std::task::{Context, Poll, Wake, Waker};
+    /// #     struct Noop;
+    /// #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+    /// #     let waker = Waker::from(Arc::new(Noop));
+    /// #     let mut future = Box::pin(future);
+    /// #     loop {
+    /// #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+    /// #             return output;
+    /// #         }
+    /// #     }
+    /// # }
+    /// # block_on(async {
+    /// let secret = SecretVec::from_async_reader(&b"hunter2"[..]).await?;
+    ///
+    /// assert_eq!(*secret.borrow(), *b"hunter2");
+    /// # Ok::<(), std::io::Error>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns any error other than [`io::ErrorKind::Interrupted`]
+    /// encountered while reading, in which case any bytes read so far
+    /// are zeroed.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        let mut secret = Self::zero(FROM_READER_CAPACITY);
+        let mut len    = 0;
+
+        loop {
+            if len == secret.len() {
+                secret.boxed.grow(len * 2, <[u8]>::zero);
+            }
+
+            let read = ReadInto { reader: &mut reader, secret: &mut secret, at: len }.await;
+
+            match read {
+                Ok(0)  => break,
+                Ok(n)  => len += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    secret.borrow_mut()[..len].zero();
+                    return Err(e);
+                },
+            }
+        }
+
+        secret.compact(|_| len);
+
+        Ok(secret)
+    }
+
+    /// Reads exactly `len` bytes from an asynchronous `reader` into a
+    /// new [`SecretVec`]. Has equivalent semantics to
+    /// [`from_reader_exact`](SecretVec::from_reader_exact).
+    ///
+    /// As with [`from_async_reader`](SecretVec::from_async_reader), the
+    /// protected memory is only unlocked while `reader` is being
+    /// polled.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// # fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    /// #     use std::sync::Arc;
+    /// #     use std::task::{Context, Poll, Wake, Waker};
+    /// #     struct Noop;
+    /// #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+    /// #     let waker = Waker::from(Arc::new(Noop));
+    /// #     let mut future = Box::pin(future);
+    /// #     loop {
+    /// #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+    /// #             return output;
+    /// #         }
+    /// #     }
+    /// # }
+    /// # block_on(async {
+    /// let secret = SecretVec::from_async_reader_exact(&b"hunter2"[..], 6).await?;
+    ///
+    /// assert_eq!(*secret.borrow(), *b"hunter");
+    /// # Ok::<(), std::io::Error>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns any error other than [`io::ErrorKind::Interrupted`]
+    /// encountered while reading, including an
+    /// [`io::ErrorKind::UnexpectedEof`] if the stream ends before `len`
+    /// bytes could be read.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader_exact<R>(mut reader: R, len: usize) -> io::Result<Self>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        let mut secret = Self::zero(len);
+        let mut filled = 0;
+
+        while filled < len {
+            let read = ReadInto { reader: &mut reader, secret: &mut secret, at: filled }.await;
+
+            match read {
+                Ok(0)  => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n)  => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(secret)
+    }
+
+    /// Copies as many bytes as will fit from the start of the
+    /// [`SecretVec`] into `dest`, an unprotected buffer, returning the
+    /// number of bytes copied.
+    ///
+    /// This is intended only for handing secrets to APIs which can't
+    /// accept one of the wrappers from this crate. Once copied, the
+    /// bytes in `dest` receive none of the protections afforded to the
+    /// [`SecretVec`], and it is up to the caller to zero them when they
+    /// are no longer needed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let     secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+    /// let mut buffer = [0; 3];
+    ///
+    /// let copied = unsafe { secret.expose_into(&mut buffer)? };
+    ///
+    /// assert_eq!(copied, 3);
+    /// assert_eq!(buffer, [1, 2, 3]);
+    /// # Ok::<(), secrets::SecretError>(())
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// This function does not violate memory safety, but is marked
+    /// `unsafe` so that every place a secret is exposed to unprotected
+    /// memory is easily audited. Callers are responsible for the
+    /// lifetime and eventual zeroing of the bytes copied into `dest`.
+    ///
+    /// # Errors
+    ///
+    /// This currently never returns an error, but may in the future
+    /// refuse to expose a [`SecretVec`] whose contents can't be trusted.
+    #[allow(unsafe_code)]
+    pub unsafe fn expose_into(&self, dest: &mut [u8]) -> Result<usize, SecretError> {
+        let secret = self.borrow();
+        let len    = secret.len().min(dest.len());
+
+        dest[..len].copy_from_slice(&secret[..len]);
+
+        Ok(len)
+    }
+
+    /// Writes the entire contents of the [`SecretVec`] to `writer`, an
+    /// unprotected destination such as a pipe to a child process.
+    ///
+    /// The contents are written directly from protected memory with
+    /// [`Write::write_all`]. This is intended only for handing secrets
+    /// to consumers which can't accept one of the wrappers from this
+    /// crate. Once written, the bytes receive none of the protections
+    /// afforded to the [`SecretVec`], including while they sit in any
+    /// buffers internal to `writer`.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let     secret = SecretVec::<u8>::from(&mut b"hunter2".to_owned()[..]);
+    /// let mut output = Vec::new();
+    ///
+    /// unsafe { secret.expose_to_writer(&mut output)? };
+    ///
+    /// assert_eq!(output, b"hunter2");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// This function does not violate memory safety, but is marked
+    /// `unsafe` so that every place a secret is exposed to unprotected
+    /// memory is easily audited. Callers are responsible for the
+    /// lifetime and eventual zeroing of any copies made by `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error encountered by [`Write::write_all`].
+    #[allow(unsafe_code)]
+    pub unsafe fn expose_to_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        writer.write_all(&self.borrow())
+    }
+
+    /// Converts the contents of the [`SecretVec`] into valid UTF-8,
+    /// replacing any invalid sequences with `U+FFFD REPLACEMENT
+    /// CHARACTER`, in the same manner as [`String::from_utf8_lossy`].
+    ///
+    /// The conversion is written directly into a new [`SecretVec`], and
+    /// no intermediate unprotected [`String`] is ever created. Note that
+    /// the conversion is not performed in constant time, so the time
+    /// taken may reveal the location of invalid sequences.
+    ///
+    /// This returns the converted bytes as a [`SecretVec`] rather than
+    /// a [`SecretString`]; use [`to_secret_string_lossy`] when the
+    /// result should be borrowed as a [`str`].
+    ///
+    /// [`to_secret_string_lossy`]: SecretVec::to_secret_string_lossy
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut b"caf\xe9".to_owned()[..]);
+    /// let utf8   = secret.to_utf8_lossy();
+    ///
+    /// assert_eq!(*utf8.borrow(), *"caf\u{fffd}".as_bytes());
+    /// ```
+    pub fn to_utf8_lossy(&self) -> Self {
+        const REPLACEMENT: &[u8] = "\u{fffd}".as_bytes();
+
+        let secret = self.borrow();
+
+        // a first pass determines the length of the output so that it
+        // can be written directly into protected memory by the second
+        let len = utf8_lossy_chunks(&secret, |chunk, invalid| {
+            chunk.len() + if invalid { REPLACEMENT.len() } else { 0 }
+        }).sum();
+
+        Self::new(len, |out| {
+            let mut i = 0;
+
+            for (chunk, invalid) in utf8_lossy_chunks(&secret, |chunk, invalid| (chunk, invalid)) {
+                out[i..i + chunk.len()].copy_from_slice(chunk);
+                i += chunk.len();
+
+                if invalid {
+                    out[i..i + REPLACEMENT.len()].copy_from_slice(REPLACEMENT);
+                    i += REPLACEMENT.len();
+                }
+            }
+        })
+    }
+
+    /// Converts the contents of the [`SecretVec`] into a
+    /// [`SecretString`], replacing any invalid sequences with `U+FFFD
+    /// REPLACEMENT CHARACTER`. Has equivalent semantics to
+    /// [`to_utf8_lossy`](SecretVec::to_utf8_lossy), and likewise never
+    /// creates an intermediate unprotected [`String`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut b"caf\xe9".to_owned()[..]);
+    /// let string = secret.to_secret_string_lossy();
+    ///
+    /// assert_eq!(&*string.borrow(), "caf\u{fffd}");
+    /// ```
+    pub fn to_secret_string_lossy(&self) -> SecretString {
+        // `to_utf8_lossy` only ever produces valid UTF-8
+        #[allow(unsafe_code)]
+        unsafe {
+            SecretString::from_utf8_unchecked(self.to_utf8_lossy())
+        }
+    }
+
+    /// Returns a new [`SecretVec`] containing a copy of `a` if `cond` is
+    /// set, or of `b` if it is not, without branching on `cond`.
+    ///
+    /// Every byte of both `a` and `b` is read and combined with masking,
+    /// so neither the time taken nor the memory accessed depends on
+    /// `cond`. This requires the `subtle` feature flag.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// use subtle::Choice;
+    ///
+    /// let a = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let b = SecretVec::<u8>::from(&mut [4, 5, 6][..]);
+    ///
+    /// let selected = SecretVec::select_ct(Choice::from(1), &a, &b);
+    ///
+    /// assert_eq!(selected, a);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` and `b` differ in length.
+    #[cfg(feature = "subtle")]
+    pub fn select_ct(cond: subtle::Choice, a: &Self, b: &Self) -> Self {
+        use subtle::ConditionallySelectable;
+
+        assert_eq!(a.len(), b.len(),
+            "secrets: may only select between secrets of the same length");
+
+        let a = a.borrow();
+        let b = b.borrow();
+
+        Self::new(a.len(), |s| {
+            for ((out, a), b) in s.iter_mut().zip(a.iter()).zip(b.iter()) {
+                *out = u8::conditional_select(b, a, cond);
+            }
+        })
+    }
+
+    /// Hashes the contents of the [`SecretVec`] as a password, producing
+    /// an encoded verifier suitable for storage.
+    ///
+    /// The hash is computed with libsodium's
+    /// [`crypto_pwhash_str`][pwhash] (currently Argon2id) under a random
+    /// salt. The returned string encodes the algorithm, its parameters,
+    /// and the salt alongside the hash itself; it is not secret, and may
+    /// be persisted and later checked with
+    /// [`verify_password`](SecretVec::verify_password). `ops_limit` and
+    /// `mem_limit` (in bytes) control the computational cost of hashing.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let password = SecretVec::<u8>::from(&mut b"hunter2".to_owned()[..]);
+    /// let hash     = password.hash_password(2, 64 * 1024 * 1024).unwrap();
+    ///
+    /// assert!(hash.starts_with("$argon2id$"));
+    /// assert!(password.verify_password(&hash));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::HashFailed`] if `ops_limit` or `mem_limit`
+    /// is out of the range supported by libsodium, or if the requested
+    /// memory could not be allocated.
+    ///
+    /// [pwhash]: https://doc.libsodium.org/password_hashing/default_phf
+    pub fn hash_password(&self, ops_limit: u64, mem_limit: usize) -> Result<String, SecretError> {
+        sodium::pwhash_str(&self.borrow(), ops_limit, mem_limit)
+            .ok_or(SecretError::HashFailed)
+    }
+
+    /// Verifies, in constant time, that the contents of the
+    /// [`SecretVec`] are the password that produced `stored_hash`.
+    ///
+    /// `stored_hash` is an encoded password verifier in the format
+    /// produced by libsodium's [`crypto_pwhash_str`][pwhash] (e.g., an
+    /// `$argon2id$...` string), which includes the algorithm, its
+    /// parameters, and the salt. Returns `false` if the password does not
+    /// match or if `stored_hash` is malformed.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let hash     = "$argon2id$v=19$m=8,t=1,p=1$P70IBNJ697Os8zrnrXejFQ$mGsdPDVWRey8eMqxTqPwg0W4MinMdTyRU+qa7P3HD4g";
+    /// let password = SecretVec::<u8>::from(&mut b"correct horse battery staple".to_owned()[..]);
+    ///
+    /// assert!(password.verify_password(hash));
+    /// ```
+    ///
+    /// [pwhash]: https://doc.libsodium.org/password_hashing/default_phf
+    pub fn verify_password(&self, stored_hash: &str) -> bool {
+        sodium::pwhash_str_verify(stored_hash, &self.borrow())
+    }
+
+    /// Appends [PKCS #7][pkcs7] padding to the contents of the
+    /// [`SecretVec`], growing it to the next multiple of `block` bytes.
+    /// At least one byte of padding is always added, so a [`SecretVec`]
+    /// whose length is already a multiple of `block` will grow by an
+    /// entire block.
+    ///
+    /// The contents are transferred into a newly-allocated region of
+    /// protected memory, and the padding is written there directly.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    ///
+    /// secret.pad_pkcs7(4);
+    /// assert_eq!(*secret.borrow(), [1, 2, 3, 1]);
+    ///
+    /// secret.unpad_pkcs7().unwrap();
+    /// assert_eq!(*secret.borrow(), [1, 2, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block` is zero or greater than 255.
+    ///
+    /// [pkcs7]: https://tools.ietf.org/html/rfc5652#section-6.3
+    pub fn pad_pkcs7(&mut self, block: usize) {
+        assert!((1..=255).contains(&block),
+            "secrets: PKCS #7 block size must be between 1 and 255");
+
+        let pad = block - self.len() % block;
+
+        #[allow(clippy::cast_possible_truncation)] // pad is at most 255
+        self.boxed.grow(self.len() + pad, |tail| {
+            for byte in tail {
+                *byte = pad as u8;
+            }
+        });
+    }
+
+    /// Removes [PKCS #7][pkcs7] padding from the contents of the
+    /// [`SecretVec`], shrinking it accordingly.
+    ///
+    /// The padding is validated without short-circuiting on the first
+    /// invalid byte, so the time taken does not reveal where the padding
+    /// is malformed. The resulting length, naturally, reveals how much
+    /// padding was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::InvalidPadding`] if the contents do not end
+    /// in valid padding, in which case they are left unmodified.
+    ///
+    /// [pkcs7]: https://tools.ietf.org/html/rfc5652#section-6.3
+    pub fn unpad_pkcs7(&mut self) -> Result<(), SecretError> {
+        let secret = self.borrow();
+        let len    = secret.len();
+
+        let pad = match secret.last() {
+            Some(&pad) => pad,
+            None       => return Err(SecretError::InvalidPadding),
+        };
+
+        // every padding byte must not be zero, must not exceed the
+        // length of the contents, and must be equal to the amount of
+        // padding
+        let mut invalid = u8::from(pad == 0) | u8::from(usize::from(pad) > len);
+
+        // we examine the maximum possible amount of padding regardless
+        // of the actual amount, masking out the bytes that aren't part
+        // of it
+        for (i, byte) in secret.iter().rev().take(255).enumerate() {
+            #[allow(clippy::cast_possible_truncation)] // i is at most 254
+            let in_pad = ((i as u16).wrapping_sub(u16::from(pad)) >> 8) as u8;
+
+            invalid |= in_pad & (byte ^ pad);
+        }
+
+        drop(secret);
+
+        if invalid != 0 {
+            return Err(SecretError::InvalidPadding);
+        }
+
+        self.compact(|_| len - usize::from(pad));
+
+        Ok(())
+    }
+
+    /// Removes any leading zero bytes from the contents of the
+    /// [`SecretVec`], shrinking it accordingly.
+    ///
+    /// The entire contents are scanned without stopping at the first
+    /// nonzero byte, so the time taken to count the leading zeroes does
+    /// not depend on their number. However, the resulting length reveals
+    /// how many were removed, which callers must decide is acceptable.
+    /// The remaining contents are transferred into a newly-allocated
+    /// region of protected memory.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let mut secret = SecretVec::<u8>::from(&mut [0, 0, 5, 0][..]);
+    ///
+    /// secret.trim_leading_zeros();
+    ///
+    /// assert_eq!(*secret.borrow(), [5, 0]);
+    /// ```
+    pub fn trim_leading_zeros(&mut self) {
+        let secret = self.borrow();
+        let len    = secret.len();
+
+        // `in_prefix` remains set only until the first nonzero byte,
+        // and every byte is examined regardless
+        let mut zeros     = 0;
+        let mut in_prefix = 1;
+
+        for byte in secret.iter() {
+            let is_zero = usize::from(u16::from(*byte).wrapping_sub(1) >> 15);
+
+            in_prefix &= is_zero;
+            zeros     += in_prefix;
+        }
+
+        drop(secret);
+
+        self.compact(|s| {
+            s.copy_within(zeros.., 0);
+            len - zeros
+        });
+    }
+
+    /// Decodes hexadecimal directly into a new [`SecretVec`]. Both
+    /// uppercase and lowercase digits are accepted.
+    ///
+    /// Characters are decoded in constant time, and decoding continues
+    /// through the entire input even if an invalid character is found.
+    /// The length of the input is not considered secret. This is also
+    /// available through [`FromStr`], so that secrets may be parsed with
+    /// [`str::parse`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::from_hex("deadBEEF")?;
+    ///
+    /// assert_eq!(*secret.borrow(), [0xde, 0xad, 0xbe, 0xef]);
+    /// # Ok::<(), secrets::SecretError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::InvalidEncoding`] if `encoded` contains
+    /// any characters other than hexadecimal digits, or has an odd
+    /// length.
+    pub fn from_hex(encoded: &str) -> Result<Self, SecretError> {
+        let data = encoded.as_bytes();
+
+        #[allow(clippy::manual_is_multiple_of)] // not usable on min supported Rust
+        if data.len() % 2 != 0 {
+            return Err(SecretError::InvalidEncoding);
+        }
+
+        let (secret, invalid) = Self::new_with(data.len() / 2, |out| {
+            let mut invalid = 0_u8;
+
+            for (byte, pair) in out.iter_mut().zip(data.chunks_exact(2)) {
+                let (hi, hi_valid) = hex_decode_char(pair[0]);
+                let (lo, lo_valid) = hex_decode_char(pair[1]);
+
+                invalid |= !(hi_valid & lo_valid);
+                *byte    = (hi << 4) | lo;
+            }
+
+            invalid
+        });
+
+        if invalid != 0 {
+            return Err(SecretError::InvalidEncoding);
+        }
+
+        Ok(secret)
+    }
+
+    /// Decodes [RFC 4648][rfc4648] base32 directly into a new
+    /// [`SecretVec`], as is commonly used for the seeds of [TOTP][totp]
+    /// and HOTP authenticators. Trailing `=` padding is optional, but if
+    /// present must be complete.
+    ///
+    /// Characters are decoded in constant time, and decoding continues
+    /// through the entire input even if an invalid character is found.
+    /// The length of the input is not considered secret.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::from_base32("MZXW6YTBOI======")?;
+    ///
+    /// assert_eq!(*secret.borrow(), *b"foobar");
+    /// # Ok::<(), secrets::SecretError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::InvalidEncoding`] if `encoded` contains
+    /// any characters outside of the base32 alphabet, is of an
+    /// impossible length, is incorrectly padded, or has non-zero
+    /// trailing bits.
+    ///
+    /// [rfc4648]: https://tools.ietf.org/html/rfc4648#section-6
+    /// [totp]: https://tools.ietf.org/html/rfc6238
+    pub fn from_base32(encoded: &str) -> Result<Self, SecretError> {
+        let data = encoded.trim_end_matches('=').as_bytes();
+        let pad  = encoded.len() - data.len();
+
+        // the number of padding characters required for each possible
+        // number of trailing characters; `None` for those which can't
+        // be produced by any input
+        let required = match data.len() % 8 {
+            0 => Some(0),
+            2 => Some(6),
+            4 => Some(4),
+            5 => Some(3),
+            7 => Some(1),
+            _ => None,
+        };
+
+        if required.is_none() || (pad != 0 && required != Some(pad)) {
+            return Err(SecretError::InvalidEncoding);
+        }
+
+        let (secret, invalid) = Self::new_with(data.len() * 5 / 8, |out| {
+            let mut acc     = 0_u16;
+            let mut bits    = 0;
+            let mut invalid = 0_u8;
+            let mut i       = 0;
+
+            for c in data {
+                let (value, valid) = base32_decode_char(*c);
+
+                invalid |= !valid;
+                acc      = (acc << 5) | u16::from(value);
+                bits    += 5;
+
+                if bits >= 8 {
+                    bits -= 8;
+
+                    #[allow(clippy::cast_possible_truncation)] // intentional
+                    { out[i] = (acc >> bits) as u8; }
+
+                    i   += 1;
+                    acc &= (1 << bits) - 1;
+                }
+            }
+
+            // any leftover bits must be zero for the encoding to be
+            // canonical
+            #[allow(clippy::cast_possible_truncation)] // acc is < 2^5
+            { invalid |= acc as u8; }
+
+            acc.zero();
+            invalid
+        });
+
+        if invalid != 0 {
+            return Err(SecretError::InvalidEncoding);
+        }
+
+        Ok(secret)
+    }
+
+    /// Encodes the contents of the [`SecretVec`] as [RFC 4648][rfc4648]
+    /// base32 into another [`SecretVec`], since the encoding of a secret
+    /// is just as sensitive as the secret itself. If `pad` is true, the
+    /// output is padded with `=` to a multiple of eight characters.
+    ///
+    /// Characters are encoded in constant time.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u8>::from(&mut b"foobar".to_owned()[..]);
+    ///
+    /// assert_eq!(*secret.to_base32(true).borrow(),  *b"MZXW6YTBOI======");
+    /// assert_eq!(*secret.to_base32(false).borrow(), *b"MZXW6YTBOI");
+    /// ```
+    ///
+    /// [rfc4648]: https://tools.ietf.org/html/rfc4648#section-6
+    pub fn to_base32(&self, pad: bool) -> Self {
+        #[allow(clippy::manual_div_ceil)] // not usable on min supported Rust
+        let len = if pad {
+            (self.len() + 4) / 5 * 8
+        } else {
+            (self.len() * 8 + 4) / 5
+        };
+
+        Self::new(len, |out| {
+            let secret   = self.borrow();
+            let mut acc  = 0_u16;
+            let mut bits = 0;
+            let mut i    = 0;
+
+            for byte in secret.iter() {
+                acc   = (acc << 8) | u16::from(*byte);
+                bits += 8;
+
+                while bits >= 5 {
+                    bits -= 5;
+
+                    #[allow(clippy::cast_possible_truncation)] // intentional
+                    { out[i] = base32_encode_char((acc >> bits) as u8 & 0x1f); }
+
+                    i   += 1;
+                    acc &= (1 << bits) - 1;
+                }
+            }
+
+            if bits > 0 {
+                #[allow(clippy::cast_possible_truncation)] // acc is < 2^5
+                { out[i] = base32_encode_char((acc << (5 - bits)) as u8 & 0x1f); }
+
+                i += 1;
+            }
+
+            for c in &mut out[i..] {
+                *c = b'=';
+            }
+
+            acc.zero();
+        })
+    }
+}
+
+impl<T: Bytes + Zeroable> IntoIterator for SecretVec<T> {
+    type Item     = SecretBox<T>;
+    type IntoIter = IntoIter<T>;
+
+    /// Consumes the [`SecretVec`], returning an iterator that moves
+    /// each of its elements into its own [`SecretBox`].
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::{SecretBox, SecretVec};
+    /// let secret = SecretVec::<[u8; 2]>::from(&mut [[1, 2], [3, 4]][..]);
+    /// let boxes  = secret.into_iter().collect::<Vec<SecretBox<[u8; 2]>>>();
+    ///
+    /// assert_eq!(*boxes[0].borrow(), [1, 2]);
+    /// assert_eq!(*boxes[1].borrow(), [3, 4]);
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { secret: self, index: 0 }
+    }
+}
+
+impl<T: Bytes + Zeroable> From<&mut [T]> for SecretVec<T> {
+    /// Creates a new [`SecretVec`] from existing, unprotected data, and
+    /// immediately zeroes out the memory of the data being moved in.
+    fn from(data: &mut [T]) -> Self {
+        Self { boxed: data.into() }
+    }
+}
+
+impl<T: Bytes + Zeroable, const N: usize> From<[T; N]> for SecretVec<T> {
+    /// Creates a new [`SecretVec`] of length `N` from an owned array,
+    /// and immediately zeroes out the memory of the array being moved
+    /// in.
+    ///
+    /// Since arrays of [`Bytes`] are [`Copy`], moving an array into
+    /// this function may leave behind copies of it that can't be
+    /// zeroed. Prefer converting from a `&mut [T]` when the original
+    /// storage is accessible.
+    fn from(mut data: [T; N]) -> Self {
+        (&mut data[..]).into()
+    }
+}
+
+impl FromStr for SecretVec<u8> {
+    type Err = SecretError;
+
+    /// Decodes hexadecimal directly into a new [`SecretVec`]. Has
+    /// equivalent semantics to [`SecretVec::from_hex`].
+    fn from_str(encoded: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(encoded)
+    }
+}
+
+#[allow(clippy::infallible_try_from)] // deliberately not From, see below
+impl TryFrom<&[u8]> for SecretVec<u8> {
+    type Error = Infallible;
+
+    /// Creates a new [`SecretVec`] by copying existing, unprotected
+    /// data. Unlike the conversion from `&mut [u8]`, the source cannot
+    /// be zeroed, so the caller remains responsible for erasing its own
+    /// copy of the data.
+    ///
+    /// This conversion never fails; it is provided as [`TryFrom`]
+    /// rather than [`From`] so that it can't be invoked accidentally
+    /// through [`Into`].
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self::new(data.len(), |s| s.copy_from_slice(data)))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Bytes> arbitrary::Arbitrary<'a> for SecretVec<T> {
+    /// Constructs a [`SecretVec`] of an arbitrary length from the
+    /// fuzzer's input, whose bytes are copied directly into protected
+    /// memory.
+    ///
+    /// This exists only for use in fuzzing harnesses, whose inputs are
+    /// not themselves protected.
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len   = u.arbitrary_len::<u8>()? / T::size().max(1);
+        let bytes = u.bytes(len * T::size())?;
+
+        Ok(Self::new(len, |s| s.as_mut_bytes().copy_from_slice(bytes)))
+    }
+
+    /// Constructs a [`SecretVec`] from as many whole elements as remain
+    /// in the fuzzer's input.
+    fn arbitrary_take_rest(mut u: arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len   = u.len() / T::size().max(1);
+        let bytes = u.bytes(len * T::size())?;
+
+        Ok(Self::new(len, |s| s.as_mut_bytes().copy_from_slice(bytes)))
+    }
+}
+
+#[cfg(feature = "constant-hash")]
+impl<T: Bytes> Hash for SecretVec<T> {
+    /// Hashes a fixed constant, rather than the contents of the
+    /// [`SecretVec`], so that every [`SecretVec`] has the same hash.
+    ///
+    /// This exists only to satisfy derived implementations of [`Hash`]
+    /// on types that contain secrets, and should not be relied upon to
+    /// distinguish one secret from another.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u8(0);
+    }
+}
+
+impl<T: Bytes> Debug for SecretVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt(f)
+    }
+}
+
+impl<T: Bytes + ConstantEq> PartialEq for SecretVec<T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.boxed.eq(&rhs.boxed)
+    }
+}
+
+impl<'a, T: Bytes> Ref<'a, T> {
+    /// Instantiates a new `Ref`.
+    fn new(boxed: &'a Box<T>) -> Self {
+        boxed.check_leaked_borrows(false);
+
+        Self {
+            boxed:  boxed.unlock(),
+            paused: false,
+            cursor: 0,
+        }
+    }
+
+    /// Instantiates a new `Ref`, returning an error rather than
+    /// panicking if `boxed` is borrowed too many times.
+    fn try_new(boxed: &'a Box<T>) -> Result<Self, SecretError> {
+        boxed.check_leaked_borrows(false);
+
+        Ok(Self {
+            boxed:  boxed.try_unlock()?,
+            paused: false,
+            cursor: 0,
+        })
+    }
+
+    /// Temporarily re-locks the memory underlying the borrow without
+    /// giving the borrow up, for instance across an `await` point where
+    /// the contents needn't be readable. The borrow must be
+    /// [`resume`](Ref::resume)d before it is dereferenced again. Has no
+    /// effect if the borrow is already paused.
+    ///
+    /// Note that the memory only becomes inaccessible if no other
+    /// borrows of the [`SecretVec`] are outstanding.
+    ///
+    /// # Panics
+    ///
+    /// Dereferencing the borrow while it is paused panics.
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+            self.paused = true;
+        }
+    }
+
+    /// Unlocks the memory underlying a borrow that was previously
+    /// [`pause`](Ref::pause)d, so that it may be dereferenced again.
+    /// Has no effect if the borrow isn't paused.
+    pub fn resume(&mut self) {
+        if self.paused {
+            let _ = self.boxed.unlock();
+            self.paused = false;
+        }
+    }
+
+    /// Returns a pointer to the borrowed elements along with their
+    /// number, for passing to C functions that expect the pair. The
+    /// pointer is only valid to read from while the borrow is alive
+    /// and not [`pause`](Ref::pause)d.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// let secret = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+    /// let borrow = secret.borrow();
+    ///
+    /// let (ptr, len) = borrow.as_ptr_len();
+    ///
+    /// assert_eq!(len,                          3);
+    /// assert_eq!(unsafe { ptr.add(2).read() }, 3);
+    /// ```
+    pub fn as_ptr_len(&self) -> (*const T, usize) {
+        (self.as_ptr(), self.len())
+    }
+}
+
+impl<T: Bytes> Clone for Ref<'_, T> {
+    /// Clones the borrow. The clone is never paused, even if the
+    /// original is, but continues reading through [`Read`] from the
+    /// same position.
+    fn clone(&self) -> Self {
+        Self {
+            boxed:  self.boxed.unlock(),
+            paused: false,
+            cursor: self.cursor,
+        }
+    }
+}
+
+impl<T: Bytes> Drop for Ref<'_, T> {
+    fn drop(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+        }
+    }
+}
+
+impl<T: Bytes> Deref for Ref<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
+        self.boxed.as_slice()
+    }
+}
+
+impl<T: Bytes> borrow::Borrow<[T]> for Ref<'_, T> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl Read for Ref<'_, u8> {
+    /// Copies as much of the borrowed contents into `buf` as will fit,
+    /// starting where the previous read through this borrow left off
+    /// (or at the beginning, for the first). Returns `Ok(0)` once every
+    /// byte has been read.
+    ///
+    /// Note that functions like [`io::copy`] pass secrets through an
+    /// intermediate buffer in unprotected memory, which is not zeroed
+    /// afterward.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// use std::io::Read;
+    ///
+    /// let     secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+    /// let mut borrow = secret.borrow();
+    /// let mut buf    = [0; 2];
+    ///
+    /// assert_eq!(borrow.read(&mut buf)?, 2);
+    /// assert_eq!(borrow.read(&mut buf)?, 1);
+    /// assert_eq!(borrow.read(&mut buf)?, 0);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = self.cursor;
+        let count = buf.len().min(self.len() - start);
+
+        buf[..count].copy_from_slice(&self[start..start + count]);
+        self.cursor += count;
+
+        Ok(count)
+    }
+}
+
+impl<T: Bytes> Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt(f)
+    }
+}
+
+impl<T: Bytes> PartialEq for Ref<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        // technically we could punt to `self.boxed.eq(&other.boxed),
+        // but the handler for that performs some extra locks and
+        // unlocks which are unnecessary here since we know both sides
+        // are already unlocked
+        self.constant_eq(rhs)
+    }
+}
+
+impl<T: Bytes> PartialEq<RefMut<'_, T>> for Ref<'_, T> {
+    fn eq(&self, rhs: &RefMut<'_, T>) -> bool {
+        // technically we could punt to `self.boxed.eq(&other.boxed),
+        // but the handler for that performs some extra locks and
+        // unlocks which are unnecessary here since we know both sides
+        // are already unlocked
+        self.constant_eq(rhs)
+    }
+}
+
+impl<T: Bytes, const N: usize> PartialEq<secret::RefMut<'_, [T; N]>> for Ref<'_, T> {
+    /// Compares the [`SecretVec`] against a stack-allocated
+    /// [`Secret`](crate::Secret) array of the same element type in
+    /// constant time, without copying either of them. The two are never
+    /// equal if they differ in length.
+    fn eq(&self, rhs: &secret::RefMut<'_, [T; N]>) -> bool {
+        (**self).constant_eq(&rhs[..])
+    }
+}
+
+impl<T: Bytes> Eq for Ref<'_, T> {}
+
+impl<'a, T: Bytes> RefMut<'a, T> {
+    /// Instantiates a new `RefMut`.
+    fn new(boxed: &'a mut Box<T>) -> Self {
+        boxed.check_leaked_borrows(true);
+
+        Self {
+            boxed:  boxed.unlock_mut(),
+            paused: false,
+            cursor: 0,
+        }
+    }
+
+    /// Temporarily re-locks the memory underlying the borrow without
+    /// giving the borrow up. Has equivalent semantics to
+    /// [`Ref::pause`].
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+            self.paused = true;
+        }
+    }
+
+    /// Unlocks the memory underlying a borrow that was previously
+    /// [`pause`](RefMut::pause)d. Has equivalent semantics to
+    /// [`Ref::resume`].
+    pub fn resume(&mut self) {
+        if self.paused {
+            let _ = self.boxed.unlock_mut();
+            self.paused = false;
+        }
+    }
+
+    /// Returns a mutable pointer to the borrowed elements along with
+    /// their number. Has equivalent semantics to [`Ref::as_ptr_len`],
+    /// but the pointer may also be written through.
+    pub fn as_mut_ptr_len(&mut self) -> (*mut T, usize) {
+        (self.as_mut_ptr(), self.len())
+    }
+}
+
+impl<T: Bytes> Drop for RefMut<'_, T> {
+    fn drop(&mut self) {
+        if !self.paused {
+            self.boxed.lock();
+        }
+    }
+}
+
+impl<T: Bytes> Deref for RefMut<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
+        self.boxed.as_slice()
+    }
+}
+
+impl<T: Bytes> DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        assert!(!self.paused,
+            "secrets: attempted to dereference a paused borrow");
+
+        self.boxed.as_mut_slice()
+    }
+}
+
+impl<T: Bytes> borrow::Borrow<[T]> for RefMut<'_, T> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T: Bytes> borrow::BorrowMut<[T]> for RefMut<'_, T> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl Write for RefMut<'_, u8> {
+    /// Copies as much of `buf` as will fit into the borrowed contents,
+    /// starting where the previous write through this borrow left off
+    /// (or at the beginning, for the first).
+    ///
+    /// Once the end of the [`SecretVec`] has been reached, any further
+    /// nonempty write fails with [`io::ErrorKind::WriteZero`]. Note that
+    /// functions like [`io::copy`] pass secrets through an intermediate
+    /// buffer in unprotected memory, which is not zeroed afterward.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// # use secrets::SecretVec;
+    /// use std::io;
+    ///
+    /// let mut secret = SecretVec::<u8>::zero(4);
+    /// let mut reader = &b"\x01\x02\x03\x04"[..];
+    ///
+    /// let _ = io::copy(&mut reader, &mut secret.borrow_mut())?;
+    ///
+    /// assert_eq!(*secret.borrow(), [1, 2, 3, 4]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = self.cursor;
+        let count = buf.len().min(self.len() - start);
+
+        if count == 0 && !buf.is_empty() {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+
+        self[start..start + count].copy_from_slice(&buf[..count]);
+        self.cursor += count;
+
+        Ok(count)
+    }
+
+    /// Does nothing, since writes go directly into protected memory.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: Bytes> Debug for RefMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt(f)
+    }
+}
+
+impl<T: Bytes> PartialEq for RefMut<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        // technically we could punt to `self.boxed.eq(&other.boxed),
+        // but the handler for that performs some extra locks and
+        // unlocks which are unnecessary here since it's already
+        // unlocked
+        self.constant_eq(rhs)
+    }
+}
+
+impl<T: Bytes> PartialEq<Ref<'_, T>> for RefMut<'_, T> {
+    fn eq(&self, rhs: &Ref<'_, T>) -> bool {
+        // technically we could punt to `self.boxed.eq(&other.boxed),
+        // but the handler for that performs some extra locks and
+        // unlocks which are unnecessary here since we know both sides
+        // are already unlocked
+        self.constant_eq(rhs)
+    }
+}
+
+impl<T: Bytes> Eq for RefMut<'_, T> {}
+
+impl<T: Bytes> Deref for RangeRefMut<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<T: Bytes> DerefMut for RangeRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+
+impl<T: Bytes> Debug for RangeRefMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.unlock.boxed.fmt_redacted(f, self.data.size())
+    }
+}
+
+impl<T: Bytes> PartialEq for RangeRefMut<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.data.constant_eq(rhs.data)
+    }
+}
+
+impl<T: Bytes> Eq for RangeRefMut<'_, T> {}
+
+impl<'a, T: Bytes> Iterator for ChunksMut<'a, T> {
+    type Item = RangeRefMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next().map(|data| RangeRefMut {
+            data,
+            unlock: Rc::clone(&self.unlock),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<T: Bytes> ExactSizeIterator for ChunksMut<'_, T> {}
+
+impl<T: Bytes> Debug for ChunksMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.unlock.boxed.debug_size() {
+            return write!(f, "{{ redacted }}");
+        }
+
+        write!(f, "{{ {} chunks redacted }}", self.chunks.len())
+    }
+}
+
+impl<'a, T: Bytes> ItemRef<'a, T> {
+    /// Instantiates a new `ItemRef`.
+    fn new(boxed: &'a Box<T>, index: usize) -> Self {
+        proven!(index < boxed.len(),
+            "secrets: attempted to borrow an out-of-bounds element");
+
+        boxed.check_leaked_borrows(false);
+
+        Self {
+            boxed: boxed.unlock(),
+            index,
+        }
+    }
+}
+
+impl<T: Bytes> Clone for ItemRef<'_, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.boxed, self.index)
+    }
+}
+
+impl<T: Bytes> Drop for ItemRef<'_, T> {
+    fn drop(&mut self) {
+        self.boxed.lock();
+    }
+}
+
+impl<T: Bytes> Deref for ItemRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.boxed.as_slice()[self.index]
+    }
+}
+
+impl<T: Bytes> Debug for ItemRef<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt_redacted(f, T::size())
+    }
+}
+
+impl<T: Bytes> PartialEq for ItemRef<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        (**self).constant_eq(&**rhs)
+    }
+}
+
+impl<T: Bytes> Eq for ItemRef<'_, T> {}
+
+impl<'a, T: Bytes> RangeRef<'a, T> {
+    /// Instantiates a new `RangeRef`.
+    fn new(boxed: &'a Box<T>, range: Range<usize>) -> Self {
+        proven!(range.start <= range.end && range.end <= boxed.len(),
+            "secrets: attempted to borrow an out-of-bounds range");
+
+        boxed.check_leaked_borrows(false);
+
+        Self {
+            boxed: boxed.unlock(),
+            range,
+        }
+    }
+}
+
+impl<T: Bytes> Clone for RangeRef<'_, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.boxed, self.range.clone())
+    }
+}
+
+impl<T: Bytes> Drop for RangeRef<'_, T> {
+    fn drop(&mut self) {
+        self.boxed.lock();
+    }
+}
+
+impl<T: Bytes> Deref for RangeRef<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.boxed.as_slice()[self.range.clone()]
+    }
+}
+
+impl<T: Bytes> Debug for RangeRef<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.boxed.fmt_redacted(f, self.range.len() * T::size())
+    }
+}
+
+impl<T: Bytes> PartialEq for RangeRef<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        (**self).constant_eq(&**rhs)
+    }
+}
+
+impl<T: Bytes> Eq for RangeRef<'_, T> {}
+
+impl<'a, T: Bytes> Iterator for RChunks<'a, T> {
+    type Item = RangeRef<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end == 0 {
+            return None;
+        }
+
+        let start = self.end.saturating_sub(self.size);
+        let chunk = RangeRef::new(&self.secret.boxed, start..self.end);
+
+        self.end = start;
+
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        #[allow(clippy::manual_is_multiple_of)] // not usable on min supported Rust
+        let remaining = self.end / self.size + usize::from(self.end % self.size != 0);
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Bytes> ExactSizeIterator for RChunks<'_, T> {}
+
+impl<'a, T: Bytes> Iterator for IterRef<'a, T> {
+    type Item = ItemRef<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.secret.get(self.index)?;
+
+        self.index += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.secret.len() - self.index;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Bytes> ExactSizeIterator for IterRef<'_, T> {}
+
+impl<T: Bytes> Deref for ItemRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<T: Bytes> DerefMut for ItemRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+
+impl<T: Bytes> Debug for ItemRefMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.unlock.boxed.fmt_redacted(f, T::size())
+    }
+}
+
+impl<T: Bytes> PartialEq for ItemRefMut<'_, T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.data.constant_eq(rhs.data)
+    }
+}
+
+impl<T: Bytes> Eq for ItemRefMut<'_, T> {}
+
+impl<'a, T: Bytes> Iterator for IterMut<'a, T> {
+    type Item = ItemRefMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elements.next().map(|data| ItemRefMut {
+            data,
+            unlock: Rc::clone(&self.unlock),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.elements.size_hint()
+    }
+}
+
+impl<T: Bytes> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T: Bytes> Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.unlock.boxed.debug_size() {
+            return write!(f, "{{ redacted }}");
+        }
+
+        write!(f, "{{ {} elements redacted }}", self.elements.len())
+    }
+}
+
+impl<T: Bytes + Zeroable> Iterator for IntoIter<T> {
+    type Item = SecretBox<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.secret.len() {
+            return None;
+        }
+
+        let item = SecretBox::from(&mut self.secret.borrow_mut()[self.index]);
+
+        self.index += 1;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.secret.len() - self.index;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Bytes + Zeroable> ExactSizeIterator for IntoIter<T> {}
+
+impl<T: Bytes + Zeroable> Debug for IntoIter<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.secret.boxed.debug_size() {
+            return write!(f, "{{ redacted }}");
+        }
+
+        write!(f, "{{ {} elements redacted }}", self.len())
+    }
+}
+
+impl<T: Bytes + Zeroable, const N: usize> Iterator for IntoBoxChunks<T, N> {
+    type Item = SecretBox<[T; N]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.secret.len() {
+            return None;
+        }
+
+        let mut secret = self.secret.borrow_mut();
+        let     chunk  = <&mut [T; N]>::try_from(&mut secret[self.index..self.index + N])
+            .expect("secrets: chunk has the wrong number of elements");
+        let     item   = SecretBox::from(chunk);
+
+        self.index += N;
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.secret.len() - self.index) / N;
+
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Bytes + Zeroable, const N: usize> ExactSizeIterator for IntoBoxChunks<T, N> {}
+
+impl<T: Bytes + Zeroable, const N: usize> Debug for IntoBoxChunks<T, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.secret.boxed.debug_size() {
+            return write!(f, "{{ redacted }}");
+        }
+
+        write!(f, "{{ {} chunks redacted }}", self.len())
+    }
+}
+
+impl<T: Bytes> Drop for Unlock<'_, T> {
+    fn drop(&mut self) {
+        self.boxed.lock();
+    }
+}
+
+/// The initial capacity in bytes of the staging buffer used by
+/// [`SecretVec::from_reader`], chosen to fill a single page.
+const FROM_READER_CAPACITY: usize = 4096;
+
+/// Resolves `range` into a concrete [`Range`] over a slice of length
+/// `len`, returning [`None`] if it is out of bounds.
+fn checked_range<R>(range: R, len: usize) -> Option<Range<usize>>
+where
+    R: RangeBounds<usize>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1)?,
+        Bound::Unbounded    => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1)?,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded    => len,
+    };
+
+    if start > end || end > len {
+        return None;
+    }
+
+    Some(start..end)
+}
+
+/// Compares `lhs` and `rhs` as though both were zero-padded to `width`
+/// bytes, without short-circuiting. Each byte of the padded width is
+/// examined exactly once, so the time taken depends only on `width`.
+/// Slices of differing lengths are never equal.
+fn ct_eq_padded(lhs: &[u8], rhs: &[u8], width: usize) -> bool {
+    // an empty slice has nothing to index, so a single zero byte is
+    // read in its place (and then masked out, since it's out of bounds)
+    let lhs_bytes = if lhs.is_empty() { &[0][..] } else { lhs };
+    let rhs_bytes = if rhs.is_empty() { &[0][..] } else { rhs };
+
+    #[allow(clippy::cast_possible_truncation)] // intentional
+    let mut diff = ((lhs.len() ^ rhs.len()) as u8)
+        | (((lhs.len() ^ rhs.len()) >> 8) != 0) as u8;
+
+    for i in 0..width {
+        let lhs_byte = lhs_bytes[i.min(lhs_bytes.len() - 1)] & ct_lt_mask(i, lhs.len());
+        let rhs_byte = rhs_bytes[i.min(rhs_bytes.len() - 1)] & ct_lt_mask(i, rhs.len());
+
+        diff |= lhs_byte ^ rhs_byte;
+    }
+
+    diff == 0
+}
+
+/// Returns `0xff` if `a < b`, and `0x00` otherwise, in constant time.
+/// Both values must be less than `usize::MAX / 2`.
+fn ct_lt_mask(a: usize, b: usize) -> u8 {
+    #[allow(clippy::cast_possible_truncation)] // the shift leaves one bit
+    #[allow(clippy::manual_bits)] // `usize::BITS` is newer than our MSRV
+    let lt = (a.wrapping_sub(b) >> (size_of::<usize>() * 8 - 1)) as u8;
+
+    0_u8.wrapping_sub(lt)
+}
+
+/// A future that performs a single read from `reader` into the
+/// contents of `secret` starting at `at`, resolving to the number of
+/// bytes read. The contents are unlocked only for the duration of each
+/// poll of `reader`, so they remain locked while the read is waiting
+/// for data.
+#[cfg(feature = "tokio")]
+struct ReadInto<'a, R> {
+    /// the reader being read from
+    reader: &'a mut R,
+
+    /// the [`SecretVec`] being read into
+    secret: &'a mut SecretVec<u8>,
+
+    /// the index in `secret` at which the read begins
+    at: usize,
+}
+
+// tests need to observe whether the contents were left locked whenever
+// a read had to wait for data
+#[cfg(all(test, feature = "tokio"))]
+thread_local! {
+    static LOCKED_WHILE_PENDING: std::cell::Cell<Option<bool>> = std::cell::Cell::new(None);
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> std::future::Future for ReadInto<'_, R> {
+    type Output = io::Result<usize>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx:       &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = &mut *self;
+        let poll = {
+            let mut staging = this.secret.borrow_mut();
+            let mut buf     = tokio::io::ReadBuf::new(&mut staging[this.at..]);
+
+            std::pin::Pin::new(&mut *this.reader)
+                .poll_read(cx, &mut buf)
+                .map_ok(|()| buf.filled().len())
+        };
+
+        #[cfg(test)]
+        if poll.is_pending() {
+            let locked = this.secret.boxed.is_locked();
+
+            LOCKED_WHILE_PENDING.with(|l| l.set(Some(locked)));
+        }
+
+        poll
+    }
+}
+
+/// Returns `0xff` if `lhs` sorts strictly before `rhs` when compared
+/// lexicographically, and `0x00` otherwise, in constant time. Both
+/// slices must be the same length.
+fn ct_lt_bytes(lhs: &[u8], rhs: &[u8]) -> u8 {
+    let mut lt = 0;
+    let mut eq = 0xff;
+
+    for (l, r) in lhs.iter().zip(rhs) {
+        let l_lt = ct_lt_mask(usize::from(*l), usize::from(*r));
+        let r_lt = ct_lt_mask(usize::from(*r), usize::from(*l));
+
+        lt |= eq & l_lt;
+        eq &= !(l_lt | r_lt);
+    }
+
+    lt
+}
+
+/// Splits `bytes` into runs of valid UTF-8, calling `f` with each run
+/// and whether or not it was immediately followed by an invalid
+/// sequence (which is skipped).
+fn utf8_lossy_chunks<'a, R, F>(mut bytes: &'a [u8], mut f: F) -> impl Iterator<Item = R> + 'a
+where
+    F: FnMut(&'a [u8], bool) -> R + 'a,
+{
+    std::iter::from_fn(move || {
+        if bytes.is_empty() {
+            return None;
+        }
+
+        let (valid, skipped) = match str::from_utf8(bytes) {
+            Ok(_)  => (bytes.len(), 0),
+            Err(e) => (e.valid_up_to(), e.error_len().unwrap_or(bytes.len() - e.valid_up_to())),
+        };
+
+        let chunk = &bytes[..valid];
+
+        bytes = &bytes[valid + skipped..];
+
+        Some(f(chunk, skipped != 0))
+    })
+}
+
+/// Decodes a single hexadecimal digit in constant time, returning its
+/// value and a mask that is `0xff` if the digit was valid and `0x00`
+/// otherwise.
+fn hex_decode_char(c: u8) -> (u8, u8) {
+    let digit = ct_in_range(c, b'0', b'9');
+    let upper = ct_in_range(c, b'A', b'F');
+    let lower = ct_in_range(c, b'a', b'f');
+    let value = (digit & c.wrapping_sub(b'0'))
+        | (upper & c.wrapping_sub(b'A' - 10))
+        | (lower & c.wrapping_sub(b'a' - 10));
+
+    (value, digit | upper | lower)
+}
+
+/// Decodes a single character of the base32 alphabet in constant time,
+/// returning its value and a mask that is `0xff` if the character was
+/// valid and `0x00` otherwise.
+fn base32_decode_char(c: u8) -> (u8, u8) {
+    let upper = ct_in_range(c, b'A', b'Z');
+    let digit = ct_in_range(c, b'2', b'7');
+    let value = (upper & c.wrapping_sub(b'A')) | (digit & c.wrapping_sub(b'2' - 26));
+
+    (value, upper | digit)
+}
+
+/// Encodes a single five-bit value into a character of the base32
+/// alphabet in constant time.
+fn base32_encode_char(value: u8) -> u8 {
+    // all ones if the value is encoded as a digit, rather than a letter
+    let digit = ct_in_range(value, 26, 31);
+
+    value.wrapping_add(b'A').wrapping_sub(digit & (b'A' - (b'2' - 26)))
+}
+
+/// Returns `0xff` if `lo <= c <= hi`, and `0x00` otherwise, in
+/// constant time.
+fn ct_in_range(c: u8, lo: u8, hi: u8) -> u8 {
+    let c     = i16::from(c);
+    let below = (c - i16::from(lo)) >> 8;
+    let above = (i16::from(hi) - c) >> 8;
+
+    #[allow(clippy::cast_possible_truncation)] // both are 0 or -1
+    #[allow(clippy::cast_sign_loss)]
+    { !(below | above) as u8 }
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::mem::align_of;
+    use std::ptr;
+
+    #[test]
+    fn it_allows_custom_initialization() {
+        let _ = SecretVec::<u64>::new(4, |s| {
+            s.clone_from_slice(&[1, 2, 3, 4][..]);
+
+            assert_eq!(*s, [1, 2, 3, 4]);
+        });
+    }
+
+    #[test]
+    fn it_allows_zeroed_custom_initialization() {
+        let secret = SecretVec::<u16>::new_zeroed(4, |s| {
+            assert_eq!(*s, [0, 0, 0, 0]);
+
+            s[2] = 0x8f1a;
+        });
+
+        assert_eq!(*secret.borrow(), [0, 0, 0x8f1a, 0]);
+    }
+
+    #[test]
+    fn it_returns_values_from_initialization() {
+        let (secret, len) = SecretVec::<u8>::new_with(8, |s| {
+            s.copy_from_slice(b"abcd\0\0\0\0");
+            s.iter().take_while(|b| **b != 0).count()
+        });
+
+        assert_eq!(len, 4);
+        assert_eq!(*secret.borrow(), *b"abcd\0\0\0\0");
+    }
+
+    #[test]
+    fn it_allows_failing_initialization() {
+        assert!(SecretVec::<u8>::try_new(|_| Ok::<(), ()>(())).is_ok());
+    }
+
+    #[test]
+    fn it_allows_borrowing_immutably() {
+        let secret = SecretVec::<u64>::zero(2);
+        let s      = secret.borrow();
+
+        assert_eq!(*s, [0, 0]);
+    }
+
+    #[test]
+    fn it_refuses_to_overflow_borrows_fallibly() {
+        let secret  = SecretVec::<u64>::zero(2);
+        let borrows = (0..255).map(|_| secret.borrow()).collect::<Vec<_>>();
+
+        secret.boxed.skip_refs(true);
+
+        assert_eq!(secret.try_borrow().err(), Some(SecretError::TooManyBorrows));
+
+        secret.boxed.skip_refs(false);
+
+        drop(borrows);
+
+        assert_eq!(*secret.try_borrow().unwrap(), [0, 0]);
+    }
+
+    #[test]
+    fn it_allows_slicing_borrows() {
+        let secret = SecretVec::<u16>::from(&mut [1, 2, 3, 4][..]);
+        let s      = secret.borrow();
+
+        assert_eq!(s[1..3],         [2, 3]);
+        assert_eq!(s[..2],          [1, 2]);
+        assert_eq!(s[2..],          [3, 4]);
+        assert_eq!(s[..],           [1, 2, 3, 4]);
+        assert_eq!(*secret.slice(), *s);
+    }
+
+    #[test]
+    fn it_allows_borrowing_prefixes() {
+        let secret = SecretVec::<u16>::from(&mut [1, 2, 3, 4, 5][..]);
+        let prefix = secret.borrow_prefix(3).expect("missing prefix");
+
+        assert_eq!(*prefix,                                           [1, 2, 3]);
+        assert_eq!(*secret.borrow_prefix(0).expect("missing prefix"), [0; 0]);
+        assert_eq!(*secret.borrow_prefix(5).expect("missing prefix"), *secret.borrow());
+        assert!(secret.borrow_prefix(6).is_none());
+
+        drop(prefix);
+
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_allows_borrowing_mutably() {
+        let mut secret = SecretVec::<u64>::zero(2);
+        let mut s      = secret.borrow_mut();
+
+        s.clone_from_slice(&[7, 1][..]);
+
+        assert_eq!(*s, [7, 1]);
+    }
+
+    #[test]
+    fn it_allows_storing_fixed_size_arrays() {
+        let secret = SecretVec::<[u8; 2]>::new(2, |s| {
+            s.clone_from_slice(&[[1, 2], [3, 4]][..]);
+        });
+
+        assert_eq!(*secret.borrow(), [[1, 2], [3, 4]]);
+    }
+
+    #[test]
+    fn it_allows_storing_large_tuples() {
+        // the fields add up to 17 bytes, so the tuple must be padded
+        type Record = (u8, u64, u8, u32, u16, u8);
+
+        let mut records : [Record; 2] = [
+            (1, 2, 3, 4, 5, 6),
+            (7, 8, 9, 10, 11, 12),
+        ];
+
+        let     secret_1 = SecretVec::<Record>::from(&mut records[..]);
+        let mut secret_2 = secret_1.clone();
+
+        assert_eq!(secret_1.size(),      48);
+        assert_eq!(secret_1.borrow()[1], (7, 8, 9, 10, 11, 12));
+        assert_eq!(secret_1,             secret_2);
+
+        // find a byte that isn't part of any field
+        let record = secret_1.borrow()[0];
+        let base   = ptr::addr_of!(record) as usize;
+        let fields = [
+            (ptr::addr_of!(record.0) as usize - base, 1),
+            (ptr::addr_of!(record.1) as usize - base, 8),
+            (ptr::addr_of!(record.2) as usize - base, 1),
+            (ptr::addr_of!(record.3) as usize - base, 4),
+            (ptr::addr_of!(record.4) as usize - base, 2),
+            (ptr::addr_of!(record.5) as usize - base, 1),
+        ];
+
+        let padding = (0..size_of::<Record>())
+            .find(|i| fields.iter().all(|&(offset, len)| !(offset..offset + len).contains(i)))
+            .expect("tuple has no padding");
+
+        secret_2.borrow_mut().as_mut_bytes()[padding] ^= 0xff;
+
+        // the fields are identical, but comparisons include the padding
+        assert_eq!(secret_1.borrow()[0], secret_2.borrow()[0]);
+        assert_ne!(secret_1,             secret_2);
+    }
+
+    #[test]
+    fn it_finds_subslices_at_any_offset() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6][..]);
+
+        assert!(secret.contains_subslice_ct(&[1, 2]));
+        assert!(secret.contains_subslice_ct(&[3, 4]));
+        assert!(secret.contains_subslice_ct(&[5, 6]));
+        assert!(secret.contains_subslice_ct(&[1, 2, 3, 4, 5, 6]));
+        assert!(secret.contains_subslice_ct(&[]));
+    }
+
+    #[test]
+    fn it_searches_for_subslices_in_constant_time() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6][..]);
+
+        // every window must be compared, regardless of where (or
+        // whether) the needle is found
+        for (needle, found) in &[([1, 2], true), ([3, 4], true), ([5, 6], true), ([6, 1], false)] {
+            let before = sodium::memcmps();
+
+            assert_eq!(secret.contains_subslice_ct(needle), *found);
+            assert_eq!(sodium::memcmps(),                    before + 5);
+        }
+    }
+
+    #[test]
+    fn it_doesnt_find_absent_subslices() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6][..]);
+
+        assert!(!secret.contains_subslice_ct(&[2, 1]));
+        assert!(!secret.contains_subslice_ct(&[6, 7]));
+        assert!(!secret.contains_subslice_ct(&[1, 2, 3, 4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn it_matches_digests() {
+        let secret = SecretVec::<u8>::from(&mut b"abc".to_owned()[..]);
+        let longer = SecretVec::<u8>::from(&mut b"abcd".to_owned()[..]);
+
+        assert!( secret.matches_digest_ct(&sodium::SHA256_ABC));
+        assert!(!longer.matches_digest_ct(&sodium::SHA256_ABC));
+    }
+
+    #[test]
+    fn it_compares_equality_ignoring_trailing_zeros() {
+        let secret = SecretVec::<u16>::from(&mut [1, 2, 0][..]);
+
+        assert!(secret.eq_ignoring_trailing_zeros_ct(&[1, 2]));
+        assert!(secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 0]));
+        assert!(secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 0, 0, 0]));
+        assert!(SecretVec::<u16>::zero(0).eq_ignoring_trailing_zeros_ct(&[0, 0]));
+    }
+
+    #[test]
+    fn it_compares_inequality_ignoring_trailing_zeros() {
+        let secret = SecretVec::<u16>::from(&mut [1, 2, 0][..]);
+
+        assert!(!secret.eq_ignoring_trailing_zeros_ct(&[1]));
+        assert!(!secret.eq_ignoring_trailing_zeros_ct(&[1, 3]));
+        assert!(!secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 3]));
+        assert!(!secret.eq_ignoring_trailing_zeros_ct(&[1, 2, 0, 0, 0x100]));
+        assert!(!secret.eq_ignoring_trailing_zeros_ct(&[]));
+    }
+
+    #[test]
+    fn it_allows_mutating_chunks() {
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6, 7][..]);
+
+        for (i, mut chunk) in secret.chunks_mut(2).enumerate() {
+            if i % 2 == 0 {
+                chunk.zero();
+            }
+        }
+
+        assert_eq!(*secret.borrow(), [0, 0, 3, 4, 0, 0, 7]);
+    }
+
+    #[test]
+    fn it_yields_a_short_final_chunk() {
+        let mut secret = SecretVec::<u16>::zero(5);
+        let     chunks = secret.chunks_mut(2).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(),    3);
+        assert_eq!(chunks[2].len(), 1);
+    }
+
+    #[test]
+    fn it_yields_chunks_from_the_end() {
+        let secret = SecretVec::<u8>::from(&mut [0, 1, 2, 3, 4][..]);
+        let chunks = secret.rchunks(2).collect::<Vec<_>>();
+
+        assert_eq!(secret.rchunks(2).len(), 3);
+        assert_eq!(*chunks[0],              [3, 4]);
+        assert_eq!(*chunks[1],              [1, 2]);
+        assert_eq!(*chunks[2],              [0]);
+
+        drop(chunks);
+
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_yields_chunks_from_the_end_larger_than_the_secret() {
+        let secret = SecretVec::<u8>::from(&mut [0, 1, 2, 3][..]);
+        let chunks = secret.rchunks(usize::MAX).collect::<Vec<_>>();
+
+        assert_eq!(secret.rchunks(usize::MAX).len(), 1);
+        assert_eq!(*chunks[0],                       [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn it_preserves_secrecy_of_chunks_from_the_end() {
+        let secret = SecretVec::<u32>::random(3);
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 8),
+            format!("{:?}", secret.rchunks(2).next().expect("missing chunk")),
+        );
+    }
+
+    #[test]
+    fn it_relocks_after_the_last_chunk_is_dropped() {
+        let mut secret = SecretVec::<u8>::zero(4);
+
+        let mut chunks = secret.chunks_mut(2);
+        let mut first  = chunks.next().expect("missing chunk");
+
+        drop(chunks);
+
+        first[1] = 0xff;
+
+        drop(first);
+
+        assert_eq!(*secret.borrow(), [0, 0xff, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: chunk size must be non-zero")]
+    fn it_doesnt_allow_empty_chunks() {
+        let _ = SecretVec::<u8>::zero(4).chunks_mut(0);
+    }
+
+    #[test]
+    fn it_retains_and_modifies_elements() {
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6][..]);
+
+        secret.retain_mut(|x| {
+            let keep = *x % 2 == 0;
+            *x *= 2;
+            keep
+        });
+
+        assert_eq!(secret.len(),     3);
+        assert_eq!(*secret.borrow(), [4, 8, 12]);
+    }
+
+    #[test]
+    fn it_retains_nothing() {
+        let mut secret = SecretVec::<u64>::random(8);
+
+        secret.retain_mut(|_| false);
+
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn it_removes_consecutive_duplicates() {
+        let     before = crate::stats::thread_locked_bytes();
+        let mut secret = SecretVec::<u32>::from(&mut [1, 1, 2, 3, 3][..]);
+
+        secret.dedup();
+
+        assert_eq!(*secret.borrow(),                    [1, 2, 3]);
+        assert_eq!(crate::stats::thread_locked_bytes(), before + 12);
+    }
+
+    #[test]
+    fn it_removes_duplicates_from_empty_secrets() {
+        let mut secret = SecretVec::<u8>::zero(0);
+
+        secret.dedup();
+
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn it_prepends_data() {
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+        let mut prefix = [0xff];
+
+        secret.prepend(&mut prefix);
+
+        assert_eq!(*secret.borrow(), [0xff, 1, 2, 3]);
+        assert_eq!(prefix,           [0]);
+    }
+
+    #[test]
+    fn it_extends_from_other_secrets() {
+        let mut secret = SecretVec::<u16>::from(&mut [1, 2][..]);
+        let     suffix = SecretVec::<u16>::from(&mut [3, 4, 5][..]);
+
+        secret.extend_from_secret(&suffix);
+        secret.extend_from_secret(&SecretVec::zero(0));
+
+        assert_eq!(*secret.borrow(), [1, 2, 3, 4, 5]);
+        assert_eq!(*suffix.borrow(), [3, 4, 5]);
+
+        // both remain independently usable and correctly locked
+        secret.borrow_mut()[2] = 6;
+
+        assert_eq!(*secret.borrow(), [1, 2, 6, 4, 5]);
+        assert_eq!(*suffix.borrow(), [3, 4, 5]);
+    }
+
+    #[test]
+    fn it_extends_empty_secrets() {
+        let mut secret = SecretVec::<u8>::zero(0);
+        let     suffix = SecretVec::<u8>::from(&mut [1, 2][..]);
+
+        secret.extend_from_secret(&suffix);
+
+        assert_eq!(secret, suffix);
+    }
+
+    #[test]
+    fn it_prepends_to_empty_secrets() {
+        let mut secret = SecretVec::<u16>::zero(0);
+        let mut prefix = [1, 2];
+
+        secret.prepend(&mut prefix);
+
+        assert_eq!(*secret.borrow(), [1, 2]);
+        assert_eq!(prefix,           [0, 0]);
+    }
+
+    #[test]
+    fn it_inserts_into_sorted_secrets() {
+        let mut secret = SecretVec::<u8>::from(&mut [2, 4, 6][..]);
+
+        for value in &[5, 0, 7, 4] {
+            secret.insert_sorted_ct(*value);
+        }
+
+        assert_eq!(*secret.borrow(), [0, 2, 4, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn it_inserts_into_sorted_secrets_lexicographically() {
+        let mut secret = SecretVec::<[u8; 2]>::zero(0);
+
+        for value in &[[1, 0], [0, 9], [1, 1], [0, 0]] {
+            secret.insert_sorted_ct(*value);
+        }
+
+        assert_eq!(*secret.borrow(), [[0, 0], [0, 9], [1, 0], [1, 1]]);
+    }
+
+    #[test]
+    fn it_takes_secrets_out_of_fields() {
+        struct Credentials {
+            token: SecretVec<u8>,
+        }
+
+        let mut credentials = Credentials {
+            token: SecretVec::from(&mut [0xde, 0xad][..]),
+        };
+
+        let before = crate::stats::thread_locked_bytes();
+        let token  = credentials.token.take();
+
+        // nothing is copied, and the placeholder holds no bytes
+        assert_eq!(crate::stats::thread_locked_bytes(), before);
+
+        assert!(credentials.token.is_empty());
+        assert_eq!(*token.borrow(), [0xde, 0xad]);
+    }
+
+    #[test]
+    fn it_copies_ranges_into_owned_secrets() {
+        let secret = SecretVec::<u32>::from(&mut [1, 2, 3, 4][..]);
+        let middle = secret.slice_to_owned(1..3).unwrap();
+
+        drop(secret);
+
+        assert_eq!(*middle.borrow(), [2, 3]);
+        assert_eq!(middle.len(),     2);
+    }
+
+    #[test]
+    fn it_doesnt_copy_out_of_bounds_ranges_into_owned_secrets() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+
+        assert!(secret.slice_to_owned(2..5).is_none());
+        assert!(secret.slice_to_owned(5..).is_none());
+        assert_eq!(*secret.slice_to_owned(..).unwrap().borrow(), [1, 2, 3, 4]);
+        assert_eq!(secret.slice_to_owned(4..).unwrap().len(),    0);
+    }
+
+    #[test]
+    fn it_splits_into_owned_halves() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5][..]);
+
+        let (head, tail) = secret.split_at_owned(3);
+
+        assert_eq!(*head.borrow(), [1, 2, 3]);
+        assert_eq!(*tail.borrow(), [4, 5]);
+    }
+
+    #[test]
+    fn it_splits_into_owned_halves_at_the_edges() {
+        let secret = SecretVec::<u16>::from(&mut [1, 2][..]);
+
+        let (head, tail) = secret.clone().split_at_owned(0);
+
+        assert_eq!(head.len(),     0);
+        assert_eq!(*tail.borrow(), [1, 2]);
+
+        let (head, tail) = secret.split_at_owned(2);
+
+        assert_eq!(*head.borrow(), [1, 2]);
+        assert_eq!(tail.len(),     0);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: split index 3 out of bounds for length 2")]
+    fn it_doesnt_split_out_of_bounds() {
+        let _ = SecretVec::<u8>::zero(2).split_at_owned(3);
+    }
+
+    #[test]
+    fn it_drains_ranges() {
+        let mut secret = SecretVec::<u8>::from(&mut b"abcd".to_owned()[..]);
+
+        secret.drain_range(1..3);
+
+        assert_eq!(*secret.borrow(), *b"ad");
+    }
+
+    #[test]
+    fn it_drains_unbounded_ranges() {
+        let mut secret_1 = SecretVec::<u32>::from(&mut [1, 2, 3, 4][..]);
+        let mut secret_2 = secret_1.clone();
+        let mut secret_3 = secret_1.clone();
+
+        secret_1.drain_range(..2);
+        secret_2.drain_range(2..);
+        secret_3.drain_range(..);
+
+        assert_eq!(*secret_1.borrow(), [3, 4]);
+        assert_eq!(*secret_2.borrow(), [1, 2]);
+        assert!(secret_3.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: range out of bounds for length 4")]
+    fn it_doesnt_allow_draining_out_of_bounds() {
+        SecretVec::<u8>::zero(4).drain_range(2..=4);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: range out of bounds for length 4")]
+    fn it_doesnt_allow_draining_inverted_ranges() {
+        #[allow(clippy::reversed_empty_ranges)]
+        SecretVec::<u8>::zero(4).drain_range(3..1);
+    }
+
+    #[test]
+    fn it_can_be_borrowed_as_a_slice() {
+        use std::borrow::{Borrow, BorrowMut};
+
+        fn sum<B: Borrow<[u8]>>(bytes: B) -> u32 {
+            bytes.borrow().iter().map(|b| u32::from(*b)).sum()
+        }
+
+        fn clear<B: BorrowMut<[u8]>>(mut bytes: B) {
+            bytes.borrow_mut().zero();
+        }
+
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+
+        assert_eq!(sum(secret.borrow()),     6);
+        assert_eq!(sum(secret.borrow_mut()), 6);
+
+        clear(secret.borrow_mut());
+
+        assert_eq!(*secret.borrow(), [0, 0, 0]);
+    }
+
+    #[test]
+    fn it_reads_from_readers_to_the_end() {
+        let data   = (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let secret = SecretVec::from_reader(&data[..]).unwrap();
+
+        assert_eq!(secret.len(),      data.len());
+        assert_eq!(&*secret.borrow(), &data[..]);
+    }
+
+    #[test]
+    fn it_reads_from_empty_readers() {
+        let secret = SecretVec::from_reader(io::empty()).unwrap();
+
+        assert!(secret.is_empty());
+    }
+
+    #[test]
+    fn it_reads_from_readers_filling_the_buffer_exactly() {
+        let data   = vec![0xa5; FROM_READER_CAPACITY];
+        let secret = SecretVec::from_reader(&data[..]).unwrap();
+
+        assert_eq!(&*secret.borrow(), &data[..]);
+    }
+
+    #[test]
+    fn it_reports_errors_from_readers() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        let reader = (&b"hunter2"[..]).chain(FailingReader);
+
+        assert_eq!(
+            SecretVec::from_reader(reader).unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe,
+        );
+    }
+
+    #[test]
+    fn it_releases_partial_reads_when_readers_fail() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::UnexpectedEof.into())
+            }
+        }
+
+        let allocations = crate::protect::heap_allocations();
+        let unscrubbed  = crate::protect::heap_unscrubbed_frees();
+        let data        = vec![0xa5; FROM_READER_CAPACITY + 1];
+        let reader      = (&data[..]).chain(FailingReader);
+
+        let result = crate::protect::with_backend(&crate::protect::Heap, || {
+            SecretVec::from_reader(reader)
+        });
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        // every buffer the partial read was written to has been zeroed
+        // and released
+        assert_eq!(crate::protect::heap_allocations(),      allocations);
+        assert_eq!(crate::protect::heap_unscrubbed_frees(), unscrubbed);
+    }
+
+    /// Polls `future` on the current thread until it completes.
+    #[cfg(feature = "tokio")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Noop;
+
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let     waker  = Waker::from(Arc::new(Noop));
+        let mut cx     = Context::from_waker(&waker);
+        let mut future = std::boxed::Box::pin(future);
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn it_reads_from_async_readers() {
+        let data   = vec![0xa5; FROM_READER_CAPACITY * 2 + 1];
+        let secret = block_on(SecretVec::from_async_reader(&data[..])).unwrap();
+
+        assert_eq!(*secret.borrow(), data[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn it_reads_exactly_from_async_readers() {
+        let secret = block_on(SecretVec::from_async_reader_exact(&b"hunter2"[..], 6)).unwrap();
+        let error  = block_on(SecretVec::from_async_reader_exact(&b"hunter2"[..], 8)).unwrap_err();
+
+        assert_eq!(*secret.borrow(), *b"hunter");
+        assert_eq!(error.kind(),     io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn it_reads_from_async_readers_that_wait() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        // yields one byte at a time, waiting before each one, and then
+        // fails once its data is exhausted; after each wait, it checks
+        // that the contents being read into were locked in the meantime
+        struct TrickleReader<'a> {
+            data:    &'a [u8],
+            pending: bool,
+            waits:   usize,
+        }
+
+        impl tokio::io::AsyncRead for TrickleReader<'_> {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx:       &mut Context<'_>,
+                buf:      &mut tokio::io::ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                self.pending = !self.pending;
+
+                if self.pending {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+
+                assert_eq!(LOCKED_WHILE_PENDING.with(std::cell::Cell::take), Some(true));
+                self.waits += 1;
+
+                let (byte, rest) = match self.data.split_first() {
+                    Some(split) => split,
+                    None        => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+                };
+
+                buf.put_slice(&[*byte]);
+                self.data = rest;
+
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        let     before = crate::stats::thread_locked_bytes();
+        let mut exact  = TrickleReader { data: b"hunter2", pending: false, waits: 0 };
+        let mut full   = TrickleReader { data: b"hunter2", pending: false, waits: 0 };
+        let     secret = block_on(SecretVec::from_async_reader_exact(&mut exact, 7)).unwrap();
+        let     error  = block_on(SecretVec::from_async_reader(&mut full)).unwrap_err();
+
+        assert_eq!(*secret.borrow(), *b"hunter2");
+        assert_eq!(error.kind(),     io::ErrorKind::BrokenPipe);
+        assert_eq!(exact.waits,      7);
+        assert_eq!(full.waits,       8);
+
+        drop(secret);
+
+        assert_eq!(crate::stats::thread_locked_bytes(), before);
+    }
+
+    #[test]
+    #[cfg(feature = "subtle")]
+    fn it_selects_between_secrets_in_constant_time() {
+        use subtle::Choice;
+
+        let a = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+        let b = SecretVec::<u8>::from(&mut [5, 6, 7, 8][..]);
+
+        assert_eq!(SecretVec::select_ct(Choice::from(1), &a, &b), a);
+        assert_eq!(SecretVec::select_ct(Choice::from(0), &a, &b), b);
+    }
+
+    #[test]
+    #[cfg(feature = "subtle")]
+    #[should_panic(expected = "secrets: may only select between secrets of the same length")]
+    fn it_doesnt_select_between_secrets_of_different_lengths() {
+        let a = SecretVec::<u8>::zero(4);
+        let b = SecretVec::<u8>::zero(5);
+
+        let _ = SecretVec::select_ct(subtle::Choice::from(1), &a, &b);
+    }
+
+    #[test]
+    fn it_verifies_passwords() {
+        let hash  = "$argon2id$v=19$m=8,t=1,p=1$P70IBNJ697Os8zrnrXejFQ$mGsdPDVWRey8eMqxTqPwg0W4MinMdTyRU+qa7P3HD4g";
+        let right = SecretVec::<u8>::from(&mut b"correct horse battery staple".to_owned()[..]);
+        let wrong = SecretVec::<u8>::from(&mut b"correct horse battery stapler".to_owned()[..]);
+        let empty = SecretVec::<u8>::zero(0);
+
+        assert!( right.verify_password(hash));
+        assert!(!wrong.verify_password(hash));
+        assert!(!empty.verify_password(hash));
+    }
+
+    #[test]
+    fn it_hashes_passwords_for_verification() {
+        let password = SecretVec::<u8>::from(&mut b"correct horse battery staple".to_owned()[..]);
+        let wrong    = SecretVec::<u8>::from(&mut b"tr0ub4dor&3".to_owned()[..]);
+        let hash_1   = password.hash_password(1, 8192).unwrap();
+        let hash_2   = password.hash_password(1, 8192).unwrap();
+
+        assert_ne!(hash_1, hash_2);
+
+        assert!( password.verify_password(&hash_1));
+        assert!( password.verify_password(&hash_2));
+        assert!(!wrong   .verify_password(&hash_1));
+    }
+
+    #[test]
+    fn it_doesnt_hash_passwords_with_invalid_limits() {
+        let password = SecretVec::<u8>::from(&mut b"hunter2".to_owned()[..]);
+
+        assert_eq!(password.hash_password(0, 8192), Err(SecretError::HashFailed));
+        assert_eq!(password.hash_password(1, 0),    Err(SecretError::HashFailed));
+    }
+
+    #[test]
+    fn it_doesnt_verify_passwords_against_malformed_hashes() {
+        let password = SecretVec::<u8>::from(&mut b"correct horse battery staple".to_owned()[..]);
+        let hash     = "$argon2id$v=19$m=8,t=1,p=1$P70IBNJ697Os8zrnrXejFQ$mGsdPDVWRey8eMqxTqPwg0W4MinMdTyRU+qa7P3HD4g";
+        let long     = format!("{}{}", hash, "a".repeat(128));
+
+        assert!(!password.verify_password(""));
+        assert!(!password.verify_password("$argon2id$v=19$m=8,t=1,p=1$"));
+        assert!(!password.verify_password(&format!("{}\0", hash)));
+        assert!(!password.verify_password(&long));
+    }
+
+    #[test]
+    fn it_pads_and_unpads_pkcs7() {
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5][..]);
+
+        secret.pad_pkcs7(8);
+        assert_eq!(*secret.borrow(), [1, 2, 3, 4, 5, 3, 3, 3]);
+
+        assert_eq!(secret.unpad_pkcs7(), Ok(()));
+        assert_eq!(*secret.borrow(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn it_pads_pkcs7_by_a_full_block_when_aligned() {
+        let mut secret = SecretVec::<u8>::zero(4);
+
+        secret.pad_pkcs7(4);
+        assert_eq!(*secret.borrow(), [0, 0, 0, 0, 4, 4, 4, 4]);
+
+        assert_eq!(secret.unpad_pkcs7(), Ok(()));
+        assert_eq!(*secret.borrow(), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn it_rejects_invalid_pkcs7_padding() {
+        let mut mismatched = SecretVec::<u8>::from(&mut [1, 2, 2, 3, 3][..]);
+        let mut zero       = SecretVec::<u8>::from(&mut [1, 2, 0][..]);
+        let mut too_long   = SecretVec::<u8>::from(&mut [4, 4, 4][..]);
+        let mut empty      = SecretVec::<u8>::zero(0);
+
+        assert_eq!(mismatched.unpad_pkcs7(), Err(SecretError::InvalidPadding));
+        assert_eq!(zero      .unpad_pkcs7(), Err(SecretError::InvalidPadding));
+        assert_eq!(too_long  .unpad_pkcs7(), Err(SecretError::InvalidPadding));
+        assert_eq!(empty     .unpad_pkcs7(), Err(SecretError::InvalidPadding));
+
+        assert_eq!(*mismatched.borrow(), [1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: PKCS #7 block size must be between 1 and 255")]
+    fn it_doesnt_allow_oversized_pkcs7_blocks() {
+        SecretVec::<u8>::zero(4).pad_pkcs7(256);
+    }
+
+    #[test]
+    fn it_trims_leading_zeros() {
+        let mut secret = SecretVec::<u8>::from(&mut [0, 0, 5, 6][..]);
+
+        secret.trim_leading_zeros();
+
+        assert_eq!(*secret.borrow(), [5, 6]);
+    }
+
+    #[test]
+    fn it_trims_secrets_of_only_zeros() {
+        let mut zeros = SecretVec::<u8>::zero(4);
+        let mut empty = SecretVec::<u8>::zero(0);
+        let mut other = SecretVec::<u8>::from(&mut [1, 0][..]);
+
+        zeros.trim_leading_zeros();
+        empty.trim_leading_zeros();
+        other.trim_leading_zeros();
+
+        assert!(zeros.is_empty());
+        assert!(empty.is_empty());
+        assert_eq!(*other.borrow(), [1, 0]);
+    }
+
+    #[test]
+    fn it_reads_until_a_delimiter() {
+        let mut reader = io::Cursor::new(b"secret:rest");
+        let     secret = SecretVec::from_reader_until(&mut reader, b':', 16);
+
+        assert_eq!(*secret.expect("failed to read").borrow(), *b"secret");
+        assert_eq!(reader.position(), 7);
+    }
+
+    #[test]
+    fn it_reads_until_eof_without_a_delimiter() {
+        let secret = SecretVec::from_reader_until(&b"secret"[..], b':', 16);
+
+        assert_eq!(*secret.expect("failed to read").borrow(), *b"secret");
+    }
+
+    #[test]
+    fn it_reads_up_to_the_maximum_length() {
+        let secret = SecretVec::from_reader_until(&b"secret:"[..], b':', 6);
+
+        assert_eq!(*secret.expect("failed to read").borrow(), *b"secret");
+    }
+
+    #[test]
+    fn it_reads_across_buffer_boundaries() {
+        let reader = io::BufReader::with_capacity(2, &b"secret:"[..]);
+        let secret = SecretVec::from_reader_until(reader, b':', 16);
+
+        assert_eq!(*secret.expect("failed to read").borrow(), *b"secret");
+    }
+
+    #[test]
+    fn it_doesnt_read_beyond_the_maximum_length() {
+        let secret = SecretVec::from_reader_until(&b"secret:"[..], b':', 5);
+
+        assert_eq!(secret.err(), Some(SecretError::LengthExceeded));
+    }
+
+    #[test]
+    fn it_allows_borrowing_elements() {
+        let secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+
+        assert_eq!(*secret.get(0).expect("missing element"), 4);
+        assert_eq!(*secret.get(2).expect("missing element"), 6);
+        assert!(secret.get(3).is_none());
+    }
+
+    #[test]
+    fn it_allows_reading_elements_within_a_closure() {
+        let secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+
+        assert_eq!(secret.with_at(1, |x| *x),     Some(5));
+        assert_eq!(secret.with_at(1, |x| *x + 1), Some(6));
+        assert_eq!(secret.with_at(3, |x| *x),     None);
+    }
+
+    #[test]
+    fn it_allows_mutably_borrowing_elements() {
+        let mut secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+
+        *secret.get_mut(0).expect("missing element") = 7;
+        *secret.get_mut(2).expect("missing element") += 1;
+
+        assert!(secret.get_mut(3).is_none());
+        assert_eq!(*secret.borrow(),    [7, 5, 7]);
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_initializes_zeroed_elements_once() {
+        let mut secret = SecretVec::<u32>::zero(4);
+        let mut calls  = 0;
+
+        for _ in 0..2 {
+            let item = secret.get_or_init(1, || {
+                calls += 1;
+                0x0bad_cafe
+            });
+
+            assert_eq!(*item.expect("missing element"), 0x0bad_cafe);
+        }
+
+        assert_eq!(calls,               1);
+        assert_eq!(*secret.borrow(),    [0, 0x0bad_cafe, 0, 0]);
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_doesnt_initialize_populated_or_missing_elements() {
+        let mut secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+
+        assert_eq!(*secret.get_or_init(0, || 7).expect("missing element"), 4);
+        assert!(secret.get_or_init(3, || 7).is_none());
+        assert_eq!(*secret.borrow(), [4, 5, 6]);
+    }
+
+    #[test]
+    fn it_iterates_mutably_over_elements() {
+        let mut secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+
+        {
+            let mut iter = secret.iter_mut();
+
+            assert_eq!(iter.len(), 3);
+
+            let mut first = iter.next().expect("missing element");
+            let mut rest  = iter.collect::<Vec<_>>();
+
+            // elements yielded by the iterator are disjoint, so they may
+            // all be held and modified at once
+            *first   += 10;
+            *rest[0] += 20;
+            *rest[1] += 30;
+
+            assert_eq!(*first,   14);
+            assert_eq!(*rest[0], 25);
+            assert_eq!(*rest[1], 36);
+        }
+
+        assert_eq!(*secret.borrow(),    [14, 25, 36]);
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_preserves_secrecy_of_mutable_elements() {
+        let mut secret = SecretVec::<u64>::random(2);
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 8),
+            format!("{:?}", secret.get_mut(1).expect("missing element")),
+        );
+
+        assert_eq!(
+            format!("{{ {} elements redacted }}", 2),
+            format!("{:?}", secret.iter_mut()),
+        );
+    }
+
+    #[test]
+    fn it_iterates_by_value_into_boxes() {
+        let     secret = SecretVec::<[u8; 4]>::from(&mut [[1, 2, 3, 4], [5, 6, 7, 8], [9, 10, 11, 12]][..]);
+        let mut iter   = secret.into_iter();
+
+        assert_eq!(iter.len(), 3);
+
+        let first = iter.next().unwrap();
+
+        assert_eq!(*first.borrow(), [1, 2, 3, 4]);
+        assert_eq!(iter.len(),      2);
+
+        // the element has been transferred out of the source
+        assert_eq!(*iter.secret.borrow(), [[0; 4], [5, 6, 7, 8], [9, 10, 11, 12]]);
+
+        let rest = iter.collect::<Vec<_>>();
+
+        assert_eq!(rest.len(),        2);
+        assert_eq!(*rest[0].borrow(), [5, 6, 7, 8]);
+        assert_eq!(*rest[1].borrow(), [9, 10, 11, 12]);
+    }
+
+    #[test]
+    fn it_reads_from_borrows() {
+        let     secret = SecretVec::<u8>::from(&mut [0xaa, 0xbb, 0xcc, 0xdd, 0xee][..]);
+        let mut borrow = secret.borrow();
+        let mut output = Vec::new();
+        let mut chunk  = [0; 2];
+
+        loop {
+            let n = borrow.read(&mut chunk).unwrap();
+
+            if n == 0 {
+                break;
+            }
+
+            output.extend_from_slice(&chunk[..n]);
+        }
+
+        assert_eq!(output, [0xaa, 0xbb, 0xcc, 0xdd, 0xee]);
+
+        // reads resume from the same position in clones, but not in
+        // new borrows
+        assert_eq!(borrow.clone().read(&mut chunk).unwrap(),  0);
+        assert_eq!(secret.borrow().read(&mut chunk).unwrap(), 2);
+    }
+
+    #[test]
+    fn it_writes_into_borrows() {
+        let mut secret = SecretVec::<u8>::zero(6);
+        let mut reader = &b"\xaa\xbb\xcc\xdd"[..];
+
+        {
+            let mut borrow = secret.borrow_mut();
+
+            assert_eq!(io::copy(&mut reader, &mut borrow).unwrap(), 4);
+
+            borrow.write_all(&[0xee]).unwrap();
+            borrow.flush().unwrap();
+        }
+
+        assert_eq!(*secret.borrow(), [0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0x00]);
+    }
+
+    #[test]
+    fn it_refuses_to_write_past_the_end_of_borrows() {
+        let mut secret = SecretVec::<u8>::zero(3);
+        let mut borrow = secret.borrow_mut();
+
+        assert_eq!(borrow.write(&[1, 2]).unwrap(), 2);
+        assert_eq!(borrow.write(&[3, 4]).unwrap(), 1);
+        assert_eq!(borrow.write(&[]).unwrap(),     0);
+
+        assert_eq!(borrow.write(&[5]).unwrap_err().kind(), io::ErrorKind::WriteZero);
+        assert_eq!(*borrow,                                [1, 2, 3]);
+    }
+
+    #[test]
+    fn it_moves_chunks_into_boxes() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4, 5, 6, 7, 8][..]);
+        let mut chunks = secret.into_box_chunks::<2>();
+
+        assert_eq!(chunks.len(), 4);
+
+        let first = chunks.next().unwrap();
+
+        assert_eq!(*first.borrow(), [1, 2]);
+        assert_eq!(chunks.len(),    3);
+
+        // the chunk has been transferred out of the source
+        assert_eq!(*chunks.secret.borrow(), [0, 0, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(format!("{:?}", chunks), "{ 3 chunks redacted }");
+
+        let rest = chunks.collect::<Vec<SecretBox<[u8; 2]>>>();
+
+        assert_eq!(rest.len(),        3);
+        assert_eq!(*rest[0].borrow(), [3, 4]);
+        assert_eq!(*rest[1].borrow(), [5, 6]);
+        assert_eq!(*rest[2].borrow(), [7, 8]);
+    }
+
+    #[test]
+    fn it_resizes() {
+        let     before = crate::stats::thread_locked_bytes();
+        let mut secret = SecretVec::<u16>::zero(2);
+
+        secret.resize(4, 0);
+        secret.borrow_mut()[2..].copy_from_slice(&[0xab, 0xcd]);
+
+        assert_eq!(*secret.borrow(),                    [0, 0, 0xab, 0xcd]);
+        assert_eq!(crate::stats::thread_locked_bytes(), before + 8);
+
+        secret.resize(3, 0);
+
+        assert_eq!(*secret.borrow(),                    [0, 0, 0xab]);
+        assert_eq!(crate::stats::thread_locked_bytes(), before + 6);
+
+        // the truncated element doesn't reappear when growing again
+        secret.resize(4, 7);
+
+        assert_eq!(*secret.borrow(), [0, 0, 0xab, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: chunk size 3 does not divide length 8")]
+    fn it_refuses_to_move_uneven_chunks_into_boxes() {
+        let _ = SecretVec::<u8>::zero(8).into_box_chunks::<3>();
+    }
+
+    #[test]
+    fn it_preserves_secrecy_when_iterating_by_value() {
+        let     secret = SecretVec::<u16>::zero(3);
+        let mut iter   = secret.into_iter();
+
+        let _ = iter.next();
+
+        assert_eq!(format!("{:?}", iter), "{ 2 elements redacted }");
+    }
+
+    #[test]
+    fn it_iterates_over_elements() {
+        let secret = SecretVec::<u32>::from(&mut [4, 5, 6][..]);
+        let values = secret.iter().map(|x| *x).collect::<Vec<_>>();
+
+        assert_eq!(secret.iter().len(), 3);
+        assert_eq!(values,              [4, 5, 6]);
+    }
+
+    #[test]
+    fn it_clones_iterators_independently() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+        let mut iter_1 = secret.iter();
+
+        let _ = iter_1.next();
+
+        let mut iter_2 = iter_1.clone();
+
+        assert_eq!(iter_1.next().map(|x| *x), Some(2));
+        assert_eq!(iter_1.next().map(|x| *x), Some(3));
+        assert_eq!(iter_2.next().map(|x| *x), Some(2));
+        assert_eq!(iter_1.next().map(|x| *x), Some(4));
+        assert_eq!(iter_2.next().map(|x| *x), Some(3));
+        assert_eq!(iter_1.next().map(|x| *x), None);
+        assert_eq!(iter_2.len(),              1);
+    }
+
+    #[test]
+    fn it_preserves_secrecy_of_elements() {
+        let secret = SecretVec::<u64>::random(2);
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 8),
+            format!("{:?}", secret.get(1).expect("missing element")),
+        );
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.boxed.as_slice()
+    #[test]
+    fn it_decodes_hex() {
+        let secret = SecretVec::from_hex("00017f80fFabCDEF")
+            .expect("valid hex was rejected");
+
+        assert_eq!(*secret.borrow(), [0x00, 0x01, 0x7f, 0x80, 0xff, 0xab, 0xcd, 0xef]);
+        assert!(SecretVec::from_hex("").expect("valid hex was rejected").is_empty());
     }
-}
 
-impl<T: Bytes> Debug for Ref<'_, T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.boxed.fmt(f)
+    #[test]
+    fn it_parses_hex() {
+        let secret: SecretVec<u8> = "deadbeef".parse().expect("valid hex was rejected");
+
+        assert_eq!(*secret.borrow(), [0xde, 0xad, 0xbe, 0xef]);
     }
-}
 
-impl<T: Bytes> PartialEq for Ref<'_, T> {
-    fn eq(&self, rhs: &Self) -> bool {
-        // technically we could punt to `self.boxed.eq(&other.boxed),
-        // but the handler for that performs some extra locks and
-        // unlocks which are unnecessary here since we know both sides
-        // are already unlocked
-        self.constant_eq(rhs)
+    #[test]
+    fn it_rejects_invalid_hex() {
+        for encoded in &[
+            "deadbee",   // odd length
+            "deadbeeg",  // g is not a hex digit
+            "dead beef", // nor is whitespace
+            "0x00",      // nor is a prefix
+            "de:ad",     // nor are separators
+        ] {
+            assert_eq!(
+                encoded.parse::<SecretVec<u8>>(),
+                Err(SecretError::InvalidEncoding),
+            );
+        }
     }
-}
 
-impl<T: Bytes> PartialEq<RefMut<'_, T>> for Ref<'_, T> {
-    fn eq(&self, rhs: &RefMut<'_, T>) -> bool {
-        // technically we could punt to `self.boxed.eq(&other.boxed),
-        // but the handler for that performs some extra locks and
-        // unlocks which are unnecessary here since we know both sides
-        // are already unlocked
-        self.constant_eq(rhs)
+    #[test]
+    fn it_decodes_base32() {
+        let seed = SecretVec::from_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")
+            .expect("valid base32 was rejected");
+
+        assert_eq!(*seed.borrow(), *b"12345678901234567890");
     }
-}
 
-impl<T: Bytes> Eq for Ref<'_, T> {}
+    #[test]
+    fn it_decodes_base32_with_and_without_padding() {
+        let cases: &[(&str, &str, &[u8])] = &[
+            ("",           "",                 b""),
+            ("MY",         "MY======",         b"f"),
+            ("MZXQ",       "MZXQ====",         b"fo"),
+            ("MZXW6",      "MZXW6===",         b"foo"),
+            ("MZXW6YQ",    "MZXW6YQ=",         b"foob"),
+            ("MZXW6YTB",   "MZXW6YTB",         b"fooba"),
+            ("MZXW6YTBOI", "MZXW6YTBOI======", b"foobar"),
+        ];
 
-impl<'a, T: Bytes> RefMut<'a, T> {
-    /// Instantiates a new `RefMut`.
-    fn new(boxed: &'a mut Box<T>) -> Self {
-        Self {
-            boxed: boxed.unlock_mut(),
+        for (unpadded, padded, expected) in cases {
+            let secret_1 = SecretVec::from_base32(unpadded).expect("valid base32 was rejected");
+            let secret_2 = SecretVec::from_base32(padded).expect("valid base32 was rejected");
+
+            assert_eq!(*secret_1.borrow(), **expected);
+            assert_eq!(*secret_2.borrow(), **expected);
+
+            assert_eq!(*secret_1.to_base32(false).borrow(), *unpadded.as_bytes());
+            assert_eq!(*secret_1.to_base32(true).borrow(),  *padded.as_bytes());
         }
     }
-}
 
-impl<T: Bytes> Drop for RefMut<'_, T> {
-    fn drop(&mut self) {
-        self.boxed.lock();
+    #[test]
+    fn it_round_trips_base32() {
+        let secret  = SecretVec::<u8>::random(37);
+        let encoded = secret.to_base32(true);
+        let decoded = SecretVec::from_base32(
+            str::from_utf8(&encoded.borrow()).expect("base32 was not ascii")
+        ).expect("encoded base32 was rejected");
+
+        assert_eq!(secret, decoded);
     }
-}
 
-impl<T: Bytes> Deref for RefMut<'_, T> {
-    type Target = [T];
+    #[test]
+    fn it_rejects_invalid_base32() {
+        for encoded in &[
+            "GEZDGNBVGY3TQOJ1", // 1 is not part of the alphabet
+            "GEZDGNBVGY3TQOJ8", // nor is 8
+            "gezdgnbvgy3tqojq", // nor are lowercase letters
+            "MZXW6Y=B",         // padding in the middle
+            "MZXW6YQ==",        // too much padding
+            "MZX",              // impossible length
+            "MZXW6YR",          // non-zero trailing bits
+        ] {
+            assert_eq!(
+                SecretVec::from_base32(encoded),
+                Err(SecretError::InvalidEncoding),
+            );
+        }
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.boxed.as_slice()
+    #[test]
+    fn it_provides_its_element_size_at_compile_time() {
+        let secret = SecretVec::<u64>::zero(2);
+        let buf    = [0_u8; SecretVec::<u64>::ELEMENT_BYTE_SIZE * 2];
+
+        assert_eq!(buf.len(), 16);
+        assert_eq!(buf.len(), secret.size());
     }
-}
 
-impl<T: Bytes> DerefMut for RefMut<'_, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.boxed.as_mut_slice()
+    #[test]
+    fn it_converts_from_owned_arrays() {
+        let secret = SecretVec::<u8>::from([1, 2, 3]);
+
+        assert_eq!(secret.len(),     3);
+        assert_eq!(*secret.borrow(), [1, 2, 3]);
     }
-}
 
-impl<T: Bytes> Debug for RefMut<'_, T> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.boxed.fmt(f)
+    #[test]
+    fn it_copies_from_borrowed_slices() {
+        let source = [0xde, 0xad, 0xbe, 0xef];
+        let secret = SecretVec::<u8>::try_from(&source[..]).expect("infallible");
+
+        assert_eq!(*secret.borrow(), source);
+        assert_eq!(source,           [0xde, 0xad, 0xbe, 0xef]);
     }
-}
 
-impl<T: Bytes> PartialEq for RefMut<'_, T> {
-    fn eq(&self, rhs: &Self) -> bool {
-        // technically we could punt to `self.boxed.eq(&other.boxed),
-        // but the handler for that performs some extra locks and
-        // unlocks which are unnecessary here since it's already
-        // unlocked
-        self.constant_eq(rhs)
+    #[test]
+    fn it_shows_sizes_in_debug_output_by_default() {
+        let mut secret = SecretVec::<u32>::zero(4);
+
+        assert_eq!(format!("{:?}", secret),                          "{ 16 bytes redacted }");
+        assert_eq!(format!("{:?}", secret.borrow()),                 "{ 16 bytes redacted }");
+        assert_eq!(format!("{:?}", secret.get(0).expect("missing")), "{ 4 bytes redacted }");
+        assert_eq!(format!("{:?}", secret.chunks_mut(2)),            "{ 2 chunks redacted }");
     }
-}
 
-impl<T: Bytes> PartialEq<Ref<'_, T>> for RefMut<'_, T> {
-    fn eq(&self, rhs: &Ref<'_, T>) -> bool {
-        // technically we could punt to `self.boxed.eq(&other.boxed),
-        // but the handler for that performs some extra locks and
-        // unlocks which are unnecessary here since we know both sides
-        // are already unlocked
-        self.constant_eq(rhs)
+    #[test]
+    fn it_hides_sizes_in_debug_output_when_configured() {
+        let mut secret = SecretVec::<u32>::zero(4).debug_size(false);
+
+        assert_eq!(format!("{:?}", secret),                          "{ redacted }");
+        assert_eq!(format!("{:?}", secret.clone()),                  "{ redacted }");
+        assert_eq!(format!("{:?}", secret.borrow()),                 "{ redacted }");
+        assert_eq!(format!("{:?}", secret.get(0).expect("missing")), "{ redacted }");
+        assert_eq!(format!("{:?}", secret.chunks_mut(2)),            "{ redacted }");
+
+        secret.drain_range(..1);
+
+        assert_eq!(format!("{:?}", secret), "{ redacted }");
     }
-}
 
-impl<T: Bytes> Eq for RefMut<'_, T> {}
+    #[test]
+    fn it_reads_exact_lengths() {
+        let mut reader = io::Cursor::new(b"12345678901234567890123");
+        let     secret = SecretVec::from_reader_exact(&mut reader, 20)
+            .expect("failed to read from reader");
 
-// LCOV_EXCL_START
+        assert_eq!(*secret.borrow(),  *b"12345678901234567890");
+        assert_eq!(reader.position(), 20);
+    }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    #[test]
+    fn it_errors_on_short_reads_of_exact_lengths() {
+        let reader = io::Cursor::new(b"1234567890");
+        let error  = SecretVec::from_reader_exact(reader, 20)
+            .expect_err("short read succeeded");
+
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
 
     #[test]
-    fn it_allows_custom_initialization() {
-        let _ = SecretVec::<u64>::new(4, |s| {
-            s.clone_from_slice(&[1, 2, 3, 4][..]);
+    fn it_compares_equality_with_stack_secrets() {
+        let secret_1 = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+        let secret_2 = SecretVec::<u8>::from(&mut [1, 2, 3, 5][..]);
+        let secret_3 = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
 
-            assert_eq!(*s, [1, 2, 3, 4]);
+        crate::Secret::<[u8; 4]>::from(&mut [1, 2, 3, 4], |s| {
+            assert_eq!(secret_1.borrow(), s);
+            assert_ne!(secret_2.borrow(), s);
+            assert_ne!(secret_3.borrow(), s);
         });
     }
 
     #[test]
-    fn it_allows_failing_initialization() {
-        assert!(SecretVec::<u8>::try_new(|_| Ok::<(), ()>(())).is_ok());
+    #[allow(unsafe_code)]
+    fn it_exposes_bytes_into_unprotected_buffers() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+        let mut short  = [0; 2];
+        let mut long   = [0; 6];
+
+        assert_eq!(unsafe { secret.expose_into(&mut short) }, Ok(2));
+        assert_eq!(unsafe { secret.expose_into(&mut long) },  Ok(4));
+
+        assert_eq!(short, [1, 2]);
+        assert_eq!(long,  [1, 2, 3, 4, 0, 0]);
     }
 
     #[test]
-    fn it_allows_borrowing_immutably() {
-        let secret = SecretVec::<u64>::zero(2);
-        let s      = secret.borrow();
+    #[allow(unsafe_code)]
+    fn it_exposes_bytes_to_writers() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+        let mut output = io::Cursor::new([0; 6]);
 
-        assert_eq!(*s, [0, 0]);
+        assert!(unsafe { secret.expose_to_writer(&mut output) }.is_ok());
+        assert!(unsafe { secret.expose_to_writer(&mut output) }.is_err());
+
+        assert_eq!(output.into_inner(), [1, 2, 3, 4, 1, 2]);
+        assert_eq!(*secret.borrow(),    [1, 2, 3, 4]);
     }
 
     #[test]
-    fn it_allows_borrowing_mutably() {
-        let mut secret = SecretVec::<u64>::zero(2);
-        let mut s      = secret.borrow_mut();
+    fn it_remains_usable_after_panicking_while_borrowed() {
+        use std::panic::{self, AssertUnwindSafe};
 
-        s.clone_from_slice(&[7, 1][..]);
+        let mut secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
 
-        assert_eq!(*s, [7, 1]);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _r1 = secret.borrow();
+            let _r2 = secret.borrow();
+
+            panic!("interrupted while borrowed");
+        }));
+
+        assert!(result.is_err());
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            secret.borrow_mut()[0] = 5;
+
+            panic!("interrupted while mutably borrowed");
+        }));
+
+        assert!(result.is_err());
+
+        secret.borrow_mut()[1] = 6;
+
+        assert_eq!(*secret.borrow(), [5, 6, 3, 4]);
     }
 
     #[test]
-    fn it_allows_storing_fixed_size_arrays() {
-        let secret = SecretVec::<[u8; 2]>::new(2, |s| {
-            s.clone_from_slice(&[[1, 2], [3, 4]][..]);
+    fn it_zeroes_partial_initialization_when_panicking() {
+        use crate::protect::{self, Heap};
+        use std::panic::{self, AssertUnwindSafe};
+
+        let plaintext   = *b"attack at dawn, bring snacks";
+        let allocations = protect::heap_allocations();
+        let unscrubbed  = protect::heap_unscrubbed_frees();
+
+        let result = protect::with_backend(&Heap, || {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                SecretVec::<u8>::new(plaintext.len(), |s| {
+                    s.copy_from_slice(&plaintext);
+
+                    panic!("interrupted while initializing");
+                })
+            }))
         });
 
-        assert_eq!(*secret.borrow(), [[1, 2], [3, 4]]);
+        assert!(result.is_err());
+
+        // the allocation was released during unwinding, and its
+        // contents were zeroed before it was
+        assert_eq!(protect::heap_allocations(),      allocations);
+        assert_eq!(protect::heap_unscrubbed_frees(), unscrubbed);
+    }
+
+    #[test]
+    fn it_converts_to_utf8_lossily() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"",                      ""),
+            (b"hunter2",               "hunter2"),
+            (b"\xff",                  "\u{fffd}"),
+            (b"pass\xffword",          "pass\u{fffd}word"),
+            (b"\xe2\x82",              "\u{fffd}"),
+            (b"\xc3\xa9t\xc3\xa9\x80", "\u{e9}t\u{e9}\u{fffd}"),
+        ];
+
+        for (bytes, expected) in cases {
+            let secret = SecretVec::<u8>::from(&mut bytes.to_vec()[..]);
+            let utf8   = secret.to_utf8_lossy();
+
+            assert_eq!(*utf8.borrow(), *expected.as_bytes());
+            assert_eq!(
+                *utf8.borrow(),
+                *String::from_utf8_lossy(bytes).as_bytes(),
+            );
+        }
+    }
+
+    #[test]
+    fn it_converts_to_secret_strings_lossily() {
+        let secret = SecretVec::<u8>::from(&mut b"pass\xffword\xe2\x82".to_vec()[..]);
+        let string = secret.to_secret_string_lossy();
+
+        assert_eq!(&*string.borrow(), "pass\u{fffd}word\u{fffd}");
+        assert_eq!(string.len(),      14);
+    }
+
+    #[test]
+    fn it_supports_zero_sized_types() {
+        let before = crate::stats::thread_locked_bytes();
+        let secret = SecretVec::<()>::zero(5);
+
+        assert_eq!(secret.len(),            5);
+        assert_eq!(secret.size(),           0);
+        assert_eq!(secret.borrow().len(),   5);
+        assert_eq!(secret.clone(),          secret);
+        assert_eq!(format!("{:?}", secret), "{ 0 bytes redacted }");
+
+        assert_eq!(crate::stats::thread_locked_bytes(), before);
+
+        drop(secret);
+    }
+
+    #[test]
+    fn it_supports_mutably_borrowing_zero_sized_types() {
+        let mut secret = SecretVec::<[u64; 0]>::zero(3);
+        let mut s      = secret.borrow_mut();
+
+        s[1] = [];
+
+        assert_eq!(s.len(), 3);
+        assert_eq!(s.as_ptr() as usize % align_of::<u64>(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn it_is_constructed_arbitrarily() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let data   = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let secret = SecretVec::<u16>::arbitrary_take_rest(Unstructured::new(&data)).unwrap();
+
+        assert_eq!(secret.len(),               4);
+        assert_eq!(secret.borrow().as_bytes(), data);
+
+        let mut input  = Unstructured::new(&data);
+        let     secret = SecretVec::<u8>::arbitrary(&mut input).unwrap();
+
+        assert!(secret.len() <= data.len());
+    }
+
+    #[test]
+    #[cfg(feature = "constant-hash")]
+    fn it_hashes_to_a_constant() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let secret_1 = SecretVec::<u8>::from(&mut [1, 2][..]);
+        let secret_2 = SecretVec::<u8>::from(&mut [3][..]);
+
+        let mut hasher_1 = DefaultHasher::new();
+        let mut hasher_2 = DefaultHasher::new();
+
+        secret_1.hash(&mut hasher_1);
+        secret_2.hash(&mut hasher_2);
+
+        assert_eq!(hasher_1.finish(), hasher_2.finish());
+        assert_ne!(secret_1,          secret_2);
+    }
+
+    #[test]
+    fn it_compares_equality_without_revealing_lengths() {
+        let secret_1 = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+        let secret_2 = SecretVec::<u16>::from(&mut [1, 2, 3][..]);
+        let secret_3 = SecretVec::<u16>::from(&mut [1, 2, 4][..]);
+        let secret_4 = SecretVec::<u16>::from(&mut [1, 2][..]);
+        let secret_5 = SecretVec::<u16>::from(&mut [1, 2, 3, 0][..]);
+        let secret_6 = SecretVec::<u16>::zero(0);
+
+        assert!( secret_1.ct_eq_total(&secret_2, 4));
+        assert!(!secret_1.ct_eq_total(&secret_3, 4));
+        assert!(!secret_1.ct_eq_total(&secret_4, 4));
+        assert!(!secret_1.ct_eq_total(&secret_5, 4));
+        assert!(!secret_1.ct_eq_total(&secret_6, 4));
+        assert!(!secret_6.ct_eq_total(&secret_1, 4));
+        assert!( secret_6.ct_eq_total(&secret_6, 4));
+    }
+
+    #[test]
+    fn it_compares_padded_bytes_across_the_full_width() {
+        // lengths differing only above the lowest byte must be detected
+        let lhs = [0; 256];
+        let rhs = [0; 512];
+
+        assert!( ct_eq_padded(&lhs, &lhs,         512));
+        assert!(!ct_eq_padded(&lhs, &rhs,         512));
+        assert!(!ct_eq_padded(&lhs, &[],          512));
+        assert!( ct_eq_padded(&[],  &[],          512));
+        assert!(!ct_eq_padded(&[1], &[2],         1));
+        assert!( ct_eq_padded(&[1, 2], &[1, 2],   2));
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: compared secrets may not exceed 2 elements")]
+    fn it_doesnt_compare_secrets_longer_than_the_maximum() {
+        let secret = SecretVec::<u8>::zero(3);
+
+        let _ = secret.ct_eq_total(&secret, 2);
     }
 
     #[test]
@@ -474,6 +5039,15 @@ mod test {
         assert_eq!(secret.size(), 1024);
     }
 
+    #[test]
+    fn it_provides_its_byte_length() {
+        let secret = SecretVec::<u32>::zero(3);
+
+        assert_eq!(secret.len(),      3);
+        assert_eq!(secret.byte_len(), 12);
+        assert_eq!(secret.byte_len(), secret.size());
+    }
+
     #[test]
     fn it_preserves_secrecy() {
         let mut secret = SecretVec::<u64>::random(32);
@@ -494,6 +5068,82 @@ mod test {
         );
     }
 
+    #[test]
+    #[allow(unsafe_code)]
+    fn it_borrows_raw_pointers_and_lengths() {
+        extern "C" fn sum(ptr: *const u32, len: usize) -> u32 {
+            unsafe { slice::from_raw_parts(ptr, len) }.iter().sum()
+        }
+
+        extern "C" fn double(ptr: *mut u32, len: usize) {
+            for x in unsafe { slice::from_raw_parts_mut(ptr, len) } {
+                *x *= 2;
+            }
+        }
+
+        let mut secret = SecretVec::<u32>::from(&mut [1, 2, 3][..]);
+
+        {
+            let mut borrow = secret.borrow_mut();
+            let (ptr, len) = borrow.as_mut_ptr_len();
+
+            double(ptr, len);
+        }
+
+        let borrow     = secret.borrow();
+        let (ptr, len) = borrow.as_ptr_len();
+
+        assert_eq!(sum(ptr, len), 12);
+        assert_eq!(*borrow,       [2, 4, 6]);
+    }
+
+    #[test]
+    fn it_pauses_and_resumes_immutable_borrows() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+        let mut s      = secret.borrow();
+
+        s.pause();
+        assert_eq!(secret.boxed.refs(), 0);
+
+        s.resume();
+        assert_eq!(secret.boxed.refs(), 1);
+        assert_eq!(*s, [1, 2, 3]);
+
+        drop(s);
+        assert_eq!(secret.boxed.refs(), 0);
+    }
+
+    #[test]
+    fn it_pauses_and_resumes_mutable_borrows() {
+        let mut secret = SecretVec::<u8>::zero(3);
+        let mut s      = secret.borrow_mut();
+
+        s.pause();
+        s.pause();
+        assert_eq!(s.boxed.refs(), 0);
+
+        s.resume();
+        s.resume();
+        assert_eq!(s.boxed.refs(), 1);
+
+        s[0] = 4;
+
+        drop(s);
+        assert_eq!(secret.boxed.refs(), 0);
+        assert_eq!(*secret.borrow(),    [4, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "secrets: attempted to dereference a paused borrow")]
+    fn it_doesnt_allow_dereferencing_paused_borrows() {
+        let     secret = SecretVec::<u8>::from(&mut [1, 2, 3][..]);
+        let mut s      = secret.borrow();
+
+        s.pause();
+
+        let _ = *s;
+    }
+
     #[test]
     fn it_moves_safely() {
         let secret_1 = SecretVec::<u8>::zero(1);
@@ -502,6 +5152,23 @@ mod test {
         assert_eq!(*secret_2.borrow(), [0]);
     }
 
+    #[test]
+    fn it_clones_fallibly() {
+        let secret = SecretVec::<u8>::random(4);
+        let copy   = secret.try_clone();
+
+        assert_eq!(copy.expect("failed to clone"), secret);
+    }
+
+    #[test]
+    fn it_reports_failures_when_cloning_fallibly() {
+        let secret = SecretVec::<u8>::random(4);
+
+        sodium::fail();
+
+        assert_eq!(secret.try_clone().err(), Some(SecretError::Allocation));
+    }
+
     #[test]
     fn it_safely_clones_immutable_references() {
         let secret   = SecretVec::<u8>::random(4);
@@ -527,6 +5194,43 @@ mod test {
         assert_ne!(secret_1, secret_2);
     }
 
+    #[test]
+    fn it_never_short_circuits_comparisons_of_equal_lengths() {
+        let secret = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]);
+
+        // every comparison must reach the constant-time primitive,
+        // regardless of where (or whether) the contents differ
+        for other in &[[1, 2, 3, 4], [0, 2, 3, 4], [1, 2, 3, 0]] {
+            let other  = SecretVec::<u8>::from(*other);
+            let before = sodium::memcmps();
+
+            let _ = secret == other;
+
+            assert_eq!(sodium::memcmps(), before + 1);
+        }
+    }
+
+    #[test]
+    fn it_compares_equality_across_element_types() {
+        let bytes = SecretVec::<u8>::from([0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        let words = SecretVec::<u64>::from([u64::from_ne_bytes([
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef,
+        ])]);
+
+        assert!(bytes.eq_bytes(&words));
+        assert!(words.eq_bytes(&bytes));
+    }
+
+    #[test]
+    fn it_compares_inequality_across_element_types() {
+        let bytes = SecretVec::<u8>::zero(8);
+        let short = SecretVec::<u32>::zero(1);
+        let words = SecretVec::<u64>::from([1]);
+
+        assert!(!bytes.eq_bytes(&short));
+        assert!(!bytes.eq_bytes(&words));
+    }
+
     #[test]
     fn it_compares_equality_immutably_on_refs() {
         let secret_1 = SecretVec::<u8>::from(&mut [0xaf][..]);
@@ -553,4 +5257,31 @@ mod test {
     }
 }
 
+#[cfg(all(test, profile = "debug"))]
+mod tests_proven_statements {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::mem_forget)]
+    #[should_panic(expected = "secrets: a borrow was leaked (e.g., with mem::forget) and left the secret unlocked")]
+    fn it_detects_leaked_immutable_borrows() {
+        let mut secret = SecretVec::<u8>::zero(4);
+
+        mem::forget(secret.get(1));
+
+        let _ = secret.iter_mut();
+    }
+
+    #[test]
+    #[allow(clippy::mem_forget)]
+    #[should_panic(expected = "secrets: a borrow was leaked (e.g., with mem::forget) and left the secret unlocked")]
+    fn it_detects_leaked_mutable_borrows() {
+        let mut secret = SecretVec::<u8>::zero(4);
+
+        mem::forget(secret.chunks_mut(2));
+
+        let _ = secret.get(0);
+    }
+}
+
 // LCOV_EXCL_STOP
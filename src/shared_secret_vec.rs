@@ -0,0 +1,185 @@
+use crate::secret_vec::{Ref, RefMut, SecretVec};
+use crate::traits::*;
+
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
+
+/// A [`SecretVec`] that shares its protected allocation between clones
+/// until one of them is mutated.
+///
+/// Cloning a [`SecretVec`] copies its contents into a second protected
+/// allocation, which for read-mostly secrets that are shared widely
+/// can consume a significant number of locked pages. A
+/// [`SharedSecretVec`], created with [`SecretVec::shared`], is instead
+/// cloned by incrementing a reference count. Every clone borrows the
+/// same underlying memory, and a fresh protected copy is only made when
+/// a clone is mutably borrowed while others still share it.
+///
+/// Since every clone shares the same allocation (and that allocation's
+/// borrow tracking), a [`SharedSecretVec`] may not be sent between
+/// threads.
+///
+/// Other than this, a [`SharedSecretVec`] provides the same protections
+/// as a [`SecretVec`]. Its contents are accessed through
+/// [`borrow`](SharedSecretVec::borrow) and
+/// [`borrow_mut`](SharedSecretVec::borrow_mut), which return the same
+/// wrappers as their [`SecretVec`] equivalents. The rest of the
+/// [`SecretVec`] API, such as [`SecretVec::try_clone`] or the methods
+/// that resize a secret, isn't available on a [`SharedSecretVec`].
+///
+/// # Example: copying on write
+///
+/// ```
+/// # use secrets::SecretVec;
+/// let     shared_1 = SecretVec::<u8>::from(&mut [1, 2, 3][..]).shared();
+/// let mut shared_2 = shared_1.clone();
+///
+/// // `shared_2` receives its own copy of the contents here
+/// shared_2.borrow_mut()[0] = 4;
+///
+/// assert_eq!(*shared_1.borrow(), [1, 2, 3]);
+/// assert_eq!(*shared_2.borrow(), [4, 2, 3]);
+/// ```
+#[derive(Clone, Eq)]
+pub struct SharedSecretVec<T: Bytes> {
+    /// the [`SecretVec`] being shared between clones
+    secret: Rc<SecretVec<T>>,
+}
+
+impl<T: Bytes> SharedSecretVec<T> {
+    /// Instantiates a new [`SharedSecretVec`].
+    pub(crate) fn new(secret: SecretVec<T>) -> Self {
+        Self {
+            secret: Rc::new(secret),
+        }
+    }
+
+    /// Returns the number of elements in the [`SharedSecretVec`].
+    pub fn len(&self) -> usize {
+        self.secret.len()
+    }
+
+    /// Returns true if length of the [`SharedSecretVec`] is zero.
+    pub fn is_empty(&self) -> bool {
+        self.secret.is_empty()
+    }
+
+    /// Returns the size in bytes of the [`SharedSecretVec`].
+    pub fn size(&self) -> usize {
+        self.secret.size()
+    }
+
+    /// Returns true if the [`SharedSecretVec`] shares its protected
+    /// allocation with any of its clones.
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.secret) > 1
+    }
+
+    /// Immutably borrows the contents of the [`SharedSecretVec`]. Has
+    /// equivalent semantics to [`SecretVec::borrow`].
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.secret.borrow()
+    }
+
+    /// Mutably borrows the contents of the [`SharedSecretVec`]. Has
+    /// equivalent semantics to [`SecretVec::borrow_mut`], except that
+    /// if the allocation is shared with any clones, it is first copied
+    /// into a new protected allocation owned by this one alone.
+    pub fn borrow_mut(&mut self) -> RefMut<'_, T> {
+        Rc::make_mut(&mut self.secret).borrow_mut()
+    }
+}
+
+impl<T: Bytes> Debug for SharedSecretVec<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.secret.fmt(f)
+    }
+}
+
+impl<T: Bytes> PartialEq for SharedSecretVec<T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        *self.secret == *rhs.secret
+    }
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stats;
+
+    #[test]
+    fn it_allows_borrowing() {
+        let mut shared = SecretVec::<u32>::zero(2).shared();
+
+        shared.borrow_mut()[1] = 0x0bad_cafe;
+
+        assert_eq!(*shared.borrow(), [0, 0x0bad_cafe]);
+        assert_eq!(shared.len(),     2);
+        assert_eq!(shared.size(),    8);
+    }
+
+    #[test]
+    fn it_shares_one_allocation_between_clones() {
+        let before   = stats::thread_locked_bytes();
+        let shared_1 = SecretVec::<u8>::random(64).shared();
+        let shared_2 = shared_1.clone();
+        let shared_3 = shared_2.clone();
+
+        assert_eq!(stats::thread_locked_bytes(), before + 64);
+        assert!(shared_1.is_shared());
+
+        drop(shared_1);
+        drop(shared_2);
+
+        assert_eq!(stats::thread_locked_bytes(), before + 64);
+        assert!(!shared_3.is_shared());
+    }
+
+    #[test]
+    fn it_copies_the_allocation_on_write() {
+        let     before   = stats::thread_locked_bytes();
+        let     shared_1 = SecretVec::<u8>::from(&mut [1, 2, 3, 4][..]).shared();
+        let mut shared_2 = shared_1.clone();
+
+        shared_2.borrow_mut()[0] = 5;
+
+        assert_eq!(stats::thread_locked_bytes(), before + 8);
+        assert!(!shared_1.is_shared());
+        assert!(!shared_2.is_shared());
+
+        assert_eq!(*shared_1.borrow(), [1, 2, 3, 4]);
+        assert_eq!(*shared_2.borrow(), [5, 2, 3, 4]);
+    }
+
+    #[test]
+    fn it_doesnt_copy_unshared_allocations_on_write() {
+        let     before = stats::thread_locked_bytes();
+        let mut shared = SecretVec::<u8>::zero(16).shared();
+
+        shared.borrow_mut()[0] = 1;
+
+        assert_eq!(stats::thread_locked_bytes(), before + 16);
+    }
+
+    #[test]
+    fn it_compares_equality() {
+        let shared_1 = SecretVec::<u8>::random(8).shared();
+        let shared_2 = shared_1.clone();
+
+        assert_eq!(shared_1, shared_2);
+    }
+
+    #[test]
+    fn it_preserves_secrecy() {
+        let shared = SecretVec::<u64>::random(2).shared();
+
+        assert_eq!(
+            format!("{{ {} bytes redacted }}", 16),
+            format!("{:?}", shared),
+        );
+    }
+}
+
+// LCOV_EXCL_STOP
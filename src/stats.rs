@@ -0,0 +1,221 @@
+use crate::error::SecretError;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The total number of bytes of secret data currently held in locked
+/// memory across the entire process.
+static LOCKED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+// tests run in parallel, so they need a view of the allocations made
+// by their own thread in order to make precise assertions
+#[cfg(test)]
+thread_local! {
+    static THREAD_LOCKED_BYTES: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    static MEMLOCK_LIMIT: std::cell::Cell<Option<usize>> = std::cell::Cell::new(None);
+}
+
+/// Returns the number of bytes of secret data currently held in memory
+/// that has been locked by this crate, across all threads.
+///
+/// This includes every live [`Secret`](crate::Secret),
+/// [`SecretBox`](crate::SecretBox), and [`SecretVec`](crate::SecretVec).
+/// Applications that create many secrets can use this to monitor their
+/// footprint and avoid exceeding `RLIMIT_MEMLOCK`. Note that the limit
+/// is enforced on whole pages, and heap-allocated secrets are also
+/// accompanied by a canary, so the amount of memory actually locked will
+/// be somewhat higher than the value returned.
+///
+/// ```
+/// # use secrets::SecretVec;
+/// let secret = SecretVec::<u8>::zero(4096);
+///
+/// assert!(secrets::locked_bytes() >= 4096);
+/// ```
+pub fn locked_bytes() -> usize {
+    LOCKED_BYTES.load(Ordering::Relaxed)
+}
+
+/// Records that `bytes` bytes of secret data have been locked.
+pub(crate) fn record_lock(bytes: usize) {
+    let _ = LOCKED_BYTES.fetch_add(bytes, Ordering::Relaxed);
+
+    #[cfg(test)]
+    THREAD_LOCKED_BYTES.with(|b| b.set(b.get().wrapping_add(bytes)));
+}
+
+/// Records that `bytes` bytes of secret data have been unlocked.
+pub(crate) fn record_unlock(bytes: usize) {
+    let _ = LOCKED_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+
+    #[cfg(test)]
+    THREAD_LOCKED_BYTES.with(|b| b.set(b.get().wrapping_sub(bytes)));
+}
+
+/// Returns an error if locking another `requested` bytes of secret
+/// data would exceed the process's soft `RLIMIT_MEMLOCK`.
+///
+/// libsodium doesn't report a failure to lock the memory it allocates,
+/// and leaves the memory unlocked instead, so every allocation is
+/// checked in advance in order to fail with an actionable error.
+/// Fallible operations like `try_clone` return this error, and all
+/// others panic with it. The check is approximate: the request is
+/// rounded up to whole pages, but is compared against the secret data
+/// held by this crate, while the kernel also counts any other memory
+/// locked by the process. No limit is enforced on non-UNIX platforms,
+/// or when the limit is infinite.
+pub(crate) fn check_memlock_budget(requested: usize) -> Result<(), SecretError> {
+    let limit = match memlock_limit() {
+        Some(limit) => limit,
+        None        => return Ok(()),
+    };
+
+    let requested = round_to_pages(requested);
+    // tests run in parallel, so they only count their own allocations
+    #[cfg(test)]
+    let locked = thread_locked_bytes();
+
+    #[cfg(not(test))]
+    let locked = locked_bytes();
+
+    let available = limit.saturating_sub(locked);
+
+    if requested > available {
+        return Err(SecretError::MemlockBudgetExceeded { requested, available });
+    }
+
+    Ok(())
+}
+
+/// Rounds `bytes` up to a whole number of pages, since memory is
+/// locked one page at a time.
+fn round_to_pages(bytes: usize) -> usize {
+    #[cfg(unix)]
+    #[allow(unsafe_code)]
+    let page = std::convert::TryFrom::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) })
+        .unwrap_or(4096);
+
+    #[cfg(not(unix))]
+    let page = 4096;
+
+    bytes.checked_add(page - 1).map_or(usize::MAX, |b| b / page * page)
+}
+
+/// Returns the soft limit in bytes on the memory this process may lock,
+/// or [`None`] if it has no limit.
+fn memlock_limit() -> Option<usize> {
+    #[cfg(test)]
+    if let Some(limit) = MEMLOCK_LIMIT.with(std::cell::Cell::get) {
+        return Some(limit);
+    }
+
+    #[cfg(unix)]
+    {
+        let mut rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+
+        #[allow(unsafe_code)]
+        if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut rlimit) } != 0
+            || rlimit.rlim_cur == libc::RLIM_INFINITY
+        {
+            return None;
+        }
+
+        Some(std::convert::TryFrom::try_from(rlimit.rlim_cur).unwrap_or(usize::MAX))
+    }
+
+    #[cfg(not(unix))]
+    None
+}
+
+/// Calls `f` with the limit on locked memory checked by
+/// [`check_memlock_budget`] replaced with `limit` on the current thread.
+#[cfg(test)]
+pub(crate) fn with_memlock_limit<R, F: FnOnce() -> R>(limit: usize, f: F) -> R {
+    /// Restores the previous limit, even if `f` panics.
+    struct Restore(Option<usize>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MEMLOCK_LIMIT.with(|l| l.set(self.0));
+        }
+    }
+
+    let _restore = Restore(MEMLOCK_LIMIT.with(|l| l.replace(Some(limit))));
+
+    f()
+}
+
+/// Returns the number of bytes of secret data locked, minus those
+/// unlocked, by the current thread.
+#[cfg(test)]
+pub(crate) fn thread_locked_bytes() -> usize {
+    THREAD_LOCKED_BYTES.with(std::cell::Cell::get)
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Secret, SecretBox, SecretVec};
+
+    #[test]
+    fn it_counts_heap_allocations() {
+        let before = thread_locked_bytes();
+        let secret = SecretVec::<u64>::zero(512);
+
+        assert_eq!(thread_locked_bytes(), before + 4096);
+        assert!(locked_bytes() >= 4096);
+
+        drop(secret);
+
+        assert_eq!(thread_locked_bytes(), before);
+    }
+
+    #[test]
+    fn it_counts_clones() {
+        let before   = thread_locked_bytes();
+        let secret_1 = SecretBox::<[u8; 32]>::zero();
+        let secret_2 = secret_1.clone();
+
+        assert_eq!(thread_locked_bytes(), before + 64);
+
+        drop(secret_1);
+        drop(secret_2);
+
+        assert_eq!(thread_locked_bytes(), before);
+    }
+
+    #[test]
+    fn it_checks_allocations_against_the_memlock_limit() {
+        let page = round_to_pages(1);
+
+        assert_eq!(with_memlock_limit(usize::MAX, || check_memlock_budget(1)), Ok(()));
+        assert_eq!(
+            with_memlock_limit(0, || check_memlock_budget(1)),
+            Err(SecretError::MemlockBudgetExceeded { requested: page, available: 0 }),
+        );
+    }
+
+    #[test]
+    fn it_rounds_requests_up_to_whole_pages() {
+        let page = round_to_pages(1);
+
+        assert_eq!(round_to_pages(0),          0);
+        assert_eq!(round_to_pages(page),       page);
+        assert_eq!(round_to_pages(page + 1),   page * 2);
+        assert_eq!(round_to_pages(usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn it_counts_stack_allocations() {
+        let before = thread_locked_bytes();
+
+        Secret::<[u8; 128]>::zero(|_| {
+            assert_eq!(thread_locked_bytes(), before + 128);
+        });
+
+        assert_eq!(thread_locked_bytes(), before);
+    }
+}
+
+// LCOV_EXCL_STOP
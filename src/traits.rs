@@ -39,6 +39,20 @@
 //! assert_eq!(src, [0, 0, 0, 0]);
 //! assert_eq!(dst, [4, 4, 4, 4]);
 //! ```
+//!
+//! Example: safely copy bytes into a target of the same size
+//!
+//! ```rust
+//! # use secrets::traits::Zeroable;
+//! let mut src = [4u8; 4];
+//! let mut dst = [1u8; 4];
+//!
+//! src.transfer_to(&mut dst)?;
+//!
+//! assert_eq!(src, [0, 0, 0, 0]);
+//! assert_eq!(dst, [4, 4, 4, 4]);
+//! # Ok::<(), secrets::SecretError>(())
+//! ```
 
 #![allow(unsafe_code)]
 
@@ -62,7 +76,7 @@ mod randomizable;
 mod zeroable;
 
 pub use bytes::{AsContiguousBytes, Bytes};
-pub use constant_eq::ConstantEq;
+pub use constant_eq::{constant_eq_all, ConstantEq};
 pub use randomizable::Randomizable;
 pub use zeroable::Zeroable;
 
@@ -88,7 +102,18 @@ unsafe impl Bytes for f64 {}
 
 unsafe impl<T: Bytes, const N: usize> Bytes for [T; N] {}
 
-unsafe impl                                             Bytes for ()               {}
-unsafe impl<T1: Bytes, T2: Bytes>                       Bytes for (T1, T2)         {}
-unsafe impl<T1: Bytes, T2: Bytes, T3: Bytes>            Bytes for (T1, T2, T3)     {}
-unsafe impl<T1: Bytes, T2: Bytes, T3: Bytes, T4: Bytes> Bytes for (T1, T2, T3, T4) {}
+/// Implements [`Bytes`] for tuples of every arity from two up to the
+/// number of type parameters provided.
+macro_rules! impl_bytes_for_tuples {
+    ($last:ident) => {};
+
+    ($head:ident, $($tail:ident),+) => {
+        unsafe impl<$head: Bytes, $($tail: Bytes),+> Bytes for ($head, $($tail),+) {}
+
+        impl_bytes_for_tuples!($($tail),+);
+    };
+}
+
+unsafe impl Bytes for () {}
+
+impl_bytes_for_tuples!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
@@ -1,4 +1,5 @@
-use std::mem::{self, MaybeUninit};
+use std::mem::{size_of, MaybeUninit};
+use std::ptr;
 use std::slice;
 
 /// Marker value for uninitialized data.
@@ -21,6 +22,15 @@ const GARBAGE_VALUE: u8 = 0xdb;
 /// must not exhibit undefined behavior when its underlying bits are set
 /// to any arbitrary bit pattern.
 ///
+/// This is implemented for tuples of up to twelve [`Bytes`] types.
+/// Tuples are not `#[repr(C)]`, so the compiler is free to reorder
+/// their fields and to insert padding between them. The
+/// [`size`](Bytes::size) of a tuple includes any such padding, and
+/// operations over a tuple's bytes (like constant-time comparisons)
+/// operate over its full layout, so two tuples with equal fields may
+/// still compare unequal. Prefer tuples whose fields leave no room for
+/// padding when a predictable layout is needed.
+///
 /// # Safety
 ///
 /// This function constructs a value with a fixed but garbage bit
@@ -29,7 +39,7 @@ const GARBAGE_VALUE: u8 = 0xdb;
 pub unsafe trait Bytes: Sized + Copy {
     /// Returns an uninitialized value.
     ///
-    /// Note that this is *not* the same as [`mem::uninitialized`].
+    /// Note that this is *not* the same as [`mem::uninitialized`](std::mem::uninitialized).
     /// Values returned by this function are guaranteed to be set to a
     /// well-defined bit pattern, though this function makes no
     /// guarantees to what specific bit pattern will be used. The bit
@@ -44,9 +54,31 @@ pub unsafe trait Bytes: Sized + Copy {
         }
     }
 
-    /// Returns the size in bytes of `Self`.
+    /// Returns the size in bytes of `Self`, including any padding.
     fn size() -> usize {
-        mem::size_of::<Self>()
+        size_of::<Self>()
+    }
+
+    /// Returns a value whose underlying bytes are copied from `bytes`,
+    /// or [`None`] if `bytes` isn't exactly [`size`](Bytes::size) bytes
+    /// long.
+    ///
+    /// This is sound for any [`Bytes`] type, since every bit pattern is
+    /// a legal value. Note that the returned value is unprotected, so
+    /// this is intended for parsing non-secret data; secrets should be
+    /// copied directly into one of this crate's wrappers instead.
+    fn from_byte_slice(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::size() {
+            return None;
+        }
+
+        let mut val = Self::uninitialized();
+
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), val.as_mut_u8_ptr(), bytes.len());
+        }
+
+        Some(val)
     }
 
     /// Returns a `*const u8` pointer to the beginning of the data.
@@ -120,3 +152,26 @@ unsafe impl<T: Bytes> AsContiguousBytes for [T] {
         self.as_mut_ptr().cast()
     }
 }
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_constructs_from_byte_slices_of_the_right_length() {
+        assert_eq!(<[u8; 4]>::from_byte_slice(&[1, 2, 3, 4]), Some([1, 2, 3, 4]));
+        assert_eq!(u32::from_byte_slice(&[1, 2, 3, 4]),       Some(u32::from_ne_bytes([1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn it_refuses_to_construct_from_byte_slices_of_the_wrong_length() {
+        assert_eq!(<[u8; 4]>::from_byte_slice(&[1, 2, 3]),       None);
+        assert_eq!(<[u8; 4]>::from_byte_slice(&[1, 2, 3, 4, 5]), None);
+        assert_eq!(u32::from_byte_slice(&[]),                    None);
+        assert_eq!(u32::from_byte_slice(&[1, 2, 3, 4, 5, 6]),    None);
+    }
+}
+
+// LCOV_EXCL_STOP
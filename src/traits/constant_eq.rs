@@ -22,3 +22,82 @@ pub trait ConstantEq: AsContiguousBytes {
 // Any type that can be represented as bytes can be compared in constant time.
 impl<T: AsContiguousBytes> ConstantEq for T {}
 impl<T: Bytes> ConstantEq for [T] {}
+
+/// Compares each of the fields in `lhs` against the corresponding field
+/// in `rhs`, returning true only if every pair is equal. This allows
+/// constant-time [`PartialEq`] implementations to be built for
+/// composite types that hold several secrets.
+///
+/// Every pair of fields is compared with
+/// [`constant_eq`](ConstantEq::constant_eq) and the results are
+/// combined without short-circuiting, so the time taken doesn't depend
+/// on which fields differ. Returns false if the two lists hold a
+/// different number of fields, or if any pair differs in size.
+///
+/// Example:
+///
+/// ```
+/// # use secrets::traits::constant_eq_all;
+/// struct Credentials {
+///     id:  [u8; 4],
+///     key: [u8; 8],
+/// }
+///
+/// impl PartialEq for Credentials {
+///     fn eq(&self, rhs: &Self) -> bool {
+///         constant_eq_all(&[&self.id, &self.key], &[&rhs.id, &rhs.key])
+///     }
+/// }
+///
+/// let a = Credentials { id: [1; 4], key: [2; 8] };
+/// let b = Credentials { id: [1; 4], key: [3; 8] };
+///
+/// assert!(a == a);
+/// assert!(a != b);
+/// ```
+pub fn constant_eq_all(lhs: &[&dyn AsContiguousBytes], rhs: &[&dyn AsContiguousBytes]) -> bool {
+    if lhs.len() != rhs.len() {
+        return false;
+    }
+
+    lhs.iter().zip(rhs).fold(true, |eq, (l, r)| {
+        eq & l.as_bytes().constant_eq(r.as_bytes())
+    })
+}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_compares_all_fields() {
+        let (a_1, b_1) = ([1_u8; 4], [2_u64; 2]);
+        let (a_2, b_2) = ([1_u8; 4], [3_u64; 2]);
+        let (a_3, b_3) = ([9_u8; 4], [2_u64; 2]);
+
+        assert!( constant_eq_all(&[&a_1, &b_1], &[&a_1, &b_1]));
+        assert!(!constant_eq_all(&[&a_1, &b_1], &[&a_2, &b_2]));
+        assert!(!constant_eq_all(&[&a_1, &b_1], &[&a_3, &b_3]));
+        assert!(!constant_eq_all(&[&a_2, &b_2], &[&a_3, &b_3]));
+    }
+
+    #[test]
+    fn it_compares_unequal_numbers_of_fields() {
+        let a = [1_u8; 4];
+
+        assert!(!constant_eq_all(&[&a, &a], &[&a]));
+        assert!( constant_eq_all(&[],       &[]));
+    }
+
+    #[test]
+    fn it_compares_fields_of_unequal_sizes() {
+        let a = [0_u8; 4];
+        let b = [0_u8; 8];
+
+        assert!(!constant_eq_all(&[&a], &[&b]));
+    }
+}
+
+// LCOV_EXCL_STOP
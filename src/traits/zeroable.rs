@@ -1,5 +1,6 @@
 #![allow(unsafe_code)]
 
+use crate::error::SecretError;
 use crate::ffi::sodium;
 use crate::traits::*;
 
@@ -38,9 +39,77 @@ pub unsafe trait Zeroable: AsContiguousBytes {
             other.as_mut_bytes(),
         );
     }
+
+    /// Copies all bytes from `self` into `other` before zeroing out
+    /// `self`. Unlike [`transfer`](Zeroable::transfer), this is safe to
+    /// call: two mutable references can never overlap, and the sizes
+    /// of `self` and `other` are checked before anything is copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SecretError::LengthMismatch`] if `self` and `other`
+    /// differ in size, in which case neither is modified.
+    fn transfer_to(&mut self, other: &mut Self) -> Result<(), SecretError> {
+        if self.as_bytes().len() != other.as_bytes().len() {
+            return Err(SecretError::LengthMismatch);
+        }
+
+        // empty slices may share a dangling address, which would trip
+        // the overlap assertion despite there being nothing to copy
+        if self.as_bytes().is_empty() {
+            return Ok(());
+        }
+
+        // safety: the two references can't overlap since they're both
+        // mutable, and we've just checked that they're the same size
+        unsafe { self.transfer(other) };
+
+        Ok(())
+    }
 }
 
 // Anything that can have its underlying storage randomized can
 // inherently have its underlying storage zeroed out (since zero is a
 // potentially random value).
 unsafe impl<T: AsContiguousBytes + ?Sized> Zeroable for T {}
+
+// LCOV_EXCL_START
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_transfers_between_equal_sizes() {
+        let mut src = [4_u8; 4];
+        let mut dst = [1_u8; 4];
+
+        assert_eq!(src[..].transfer_to(&mut dst[..]), Ok(()));
+        assert_eq!(src,                               [0, 0, 0, 0]);
+        assert_eq!(dst,                               [4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn it_transfers_between_empty_slices() {
+        let mut src : [u8; 0] = [];
+        let mut dst : [u8; 0] = [];
+
+        assert_eq!(src[..].transfer_to(&mut dst[..]), Ok(()));
+    }
+
+    #[test]
+    fn it_refuses_to_transfer_between_unequal_sizes() {
+        let mut src = [4_u8; 4];
+        let mut dst = [1_u8; 8];
+
+        assert_eq!(
+            src[..].transfer_to(&mut dst[..]),
+            Err(SecretError::LengthMismatch),
+        );
+
+        assert_eq!(src, [4; 4]);
+        assert_eq!(dst, [1; 8]);
+    }
+}
+
+// LCOV_EXCL_STOP